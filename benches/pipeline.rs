@@ -0,0 +1,127 @@
+// Timings for the lexer, parser and interpreter on representative programs.
+// Uses a small std-only harness to keep the crate dependency-free:
+//
+//     cargo bench                 # every benchmark
+//     cargo bench -- interpret    # only those whose name contains "interpret"
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use minilang::interpreter::Interpreter;
+use minilang::lexer::Lexer;
+use minilang::parser::parse_source;
+
+const FIB: &str = "fn fib(n) {
+  if n < 2 {
+    return n
+  }
+  return fib(n - 1) + fib(n - 2)
+}
+let result = fib(20)
+";
+
+const ARRAY_LOOP: &str = "let xs = []
+for i in 0..20000 {
+  push(xs, i)
+}
+let total = 0
+for x in xs {
+  total = total + x
+}
+let i = 0
+while i < len(xs) {
+  xs[i] = xs[i] * 2
+  i = i + 1
+}
+";
+
+const STRING_CONCAT: &str = "let s = \"\"
+for i in 0..2000 {
+  s = s + \"x\"
+}
+let n = len(s)
+";
+
+// Nested blocks and parenthesized expressions, generated by `deep_nesting`.
+const NESTING_DEPTH: usize = 60;
+
+fn deep_nesting() -> String {
+    let mut src = String::new();
+    for i in 0..NESTING_DEPTH {
+        src.push_str(&format!("if {} < {} {{\n", i, i + 1));
+    }
+    src.push_str("let x = ");
+    src.push_str(&"(1 + ".repeat(NESTING_DEPTH));
+    src.push('1');
+    src.push_str(&")".repeat(NESTING_DEPTH));
+    src.push('\n');
+    src.push_str(&"}\n".repeat(NESTING_DEPTH));
+    src
+}
+
+// A large source made of every program above, for the front-end benchmarks.
+fn large_source(programs: &[(&str, String)]) -> String {
+    let mut src = String::new();
+    for _ in 0..50 {
+        for (_, program) in programs {
+            src.push_str(program);
+        }
+    }
+    src
+}
+
+const BUDGET: Duration = Duration::from_millis(500);
+
+fn bench(filter: &Option<String>, name: &str, mut f: impl FnMut()) {
+    if filter.as_ref().is_some_and(|f| !name.contains(f.as_str())) {
+        return;
+    }
+    f(); // warm up
+    let mut times = Vec::new();
+    let started = Instant::now();
+    while started.elapsed() < BUDGET || times.len() < 5 {
+        let start = Instant::now();
+        f();
+        times.push(start.elapsed());
+    }
+    times.sort();
+    let mean = times.iter().sum::<Duration>() / times.len() as u32;
+    println!(
+        "{:<28} {:>6} iters   mean {:>12.3?}   median {:>12.3?}   min {:>12.3?}",
+        name,
+        times.len(),
+        mean,
+        times[times.len() / 2],
+        times[0]
+    );
+}
+
+fn main() {
+    // `cargo bench` passes flags such as `--bench`; the first other
+    // argument filters benchmarks by name.
+    let filter = std::env::args().skip(1).find(|a| !a.starts_with("--"));
+
+    let programs = vec![
+        ("fib", FIB.to_string()),
+        ("array_loop", ARRAY_LOOP.to_string()),
+        ("string_concat", STRING_CONCAT.to_string()),
+        ("deep_nesting", deep_nesting()),
+    ];
+    let large = large_source(&programs);
+
+    bench(&filter, "lex/large", || {
+        black_box(Lexer::new(black_box(&large)).tokenize_spanned().unwrap());
+    });
+    bench(&filter, "parse/large", || {
+        black_box(parse_source(black_box(&large)).unwrap());
+    });
+
+    for (name, source) in &programs {
+        let program = parse_source(source).unwrap();
+        bench(&filter, &format!("interpret/{}", name), || {
+            let mut interpreter = Interpreter::new();
+            interpreter.run(black_box(&program)).unwrap();
+            black_box(interpreter);
+        });
+    }
+}
@@ -74,7 +74,7 @@ fn dijkstra(source) {
     # Find unvisited node with smallest distance
     let u = -1
     let min_dist = INF + 1
-    let i = 0
+    i = 0
     while i < num_nodes {
       if visited[i] == 0 and dist[i] < min_dist {
         min_dist = dist[i]
@@ -0,0 +1,185 @@
+use crate::parser::{Expr, Stmt, StmtKind};
+
+// Read-only traversal. Override the hooks you care about and call the
+// matching walk_* function to keep descending into children.
+pub trait Visitor {
+    fn visit_block(&mut self, stmts: &[Stmt]) {
+        walk_block(self, stmts);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(v: &mut V, stmts: &[Stmt]) {
+    for stmt in stmts {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(v: &mut V, stmt: &Stmt) {
+    match &stmt.kind {
+        StmtKind::Let(_, expr)
+        | StmtKind::LetArray(_, _, expr)
+        | StmtKind::Assign(_, expr)
+        | StmtKind::ExprStmt(expr) => v.visit_expr(expr),
+        StmtKind::MultiAssign(_, values) => {
+            for value in values {
+                v.visit_expr(value);
+            }
+        }
+        StmtKind::IndexAssign(_, index, value) => {
+            v.visit_expr(index);
+            v.visit_expr(value);
+        }
+        StmtKind::If(cond, body, else_body) => {
+            v.visit_expr(cond);
+            v.visit_block(body);
+            if let Some(else_b) = else_body {
+                v.visit_block(else_b);
+            }
+        }
+        StmtKind::While(cond, body) => {
+            v.visit_expr(cond);
+            v.visit_block(body);
+        }
+        StmtKind::For(_, start, end, body) => {
+            v.visit_expr(start);
+            v.visit_expr(end);
+            v.visit_block(body);
+        }
+        StmtKind::ForEach(_, iterable, body) => {
+            v.visit_expr(iterable);
+            v.visit_block(body);
+        }
+        StmtKind::Fn(_, _, body) => v.visit_block(body),
+        StmtKind::Return(Some(expr)) => v.visit_expr(expr),
+        StmtKind::Labeled(_, inner) => v.visit_stmt(inner),
+        StmtKind::Return(None) | StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Global(_) => {}
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(v: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Number(_) | Expr::StringLit(_) | Expr::Bool(_) | Expr::Ident(_) => {}
+        Expr::Array(elems) => {
+            for e in elems {
+                v.visit_expr(e);
+            }
+        }
+        Expr::Index(target, index) => {
+            v.visit_expr(target);
+            v.visit_expr(index);
+        }
+        Expr::Call(callee, args) => {
+            v.visit_expr(callee);
+            for a in args {
+                v.visit_expr(a);
+            }
+        }
+        Expr::Unary(_, operand) | Expr::Spread(operand) | Expr::Try(operand) => v.visit_expr(operand),
+        Expr::Binary(left, _, right) => {
+            v.visit_expr(left);
+            v.visit_expr(right);
+        }
+    }
+}
+
+// Same shape as Visitor, for passes that rewrite the tree in place.
+pub trait VisitorMut {
+    fn visit_block_mut(&mut self, stmts: &mut Vec<Stmt>) {
+        walk_block_mut(self, stmts);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(v: &mut V, stmts: &mut Vec<Stmt>) {
+    for stmt in stmts {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut Stmt) {
+    match &mut stmt.kind {
+        StmtKind::Let(_, expr)
+        | StmtKind::LetArray(_, _, expr)
+        | StmtKind::Assign(_, expr)
+        | StmtKind::ExprStmt(expr) => v.visit_expr_mut(expr),
+        StmtKind::MultiAssign(_, values) => {
+            for value in values {
+                v.visit_expr_mut(value);
+            }
+        }
+        StmtKind::IndexAssign(_, index, value) => {
+            v.visit_expr_mut(index);
+            v.visit_expr_mut(value);
+        }
+        StmtKind::If(cond, body, else_body) => {
+            v.visit_expr_mut(cond);
+            v.visit_block_mut(body);
+            if let Some(else_b) = else_body {
+                v.visit_block_mut(else_b);
+            }
+        }
+        StmtKind::While(cond, body) => {
+            v.visit_expr_mut(cond);
+            v.visit_block_mut(body);
+        }
+        StmtKind::For(_, start, end, body) => {
+            v.visit_expr_mut(start);
+            v.visit_expr_mut(end);
+            v.visit_block_mut(body);
+        }
+        StmtKind::ForEach(_, iterable, body) => {
+            v.visit_expr_mut(iterable);
+            v.visit_block_mut(body);
+        }
+        StmtKind::Fn(_, _, body) => {
+            // Bodies are shared, so a rewrite works on a private copy.
+            let mut stmts = body.to_vec();
+            v.visit_block_mut(&mut stmts);
+            *body = stmts.into();
+        }
+        StmtKind::Return(Some(expr)) => v.visit_expr_mut(expr),
+        StmtKind::Labeled(_, inner) => v.visit_stmt_mut(inner),
+        StmtKind::Return(None) | StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Global(_) => {}
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(v: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Number(_) | Expr::StringLit(_) | Expr::Bool(_) | Expr::Ident(_) => {}
+        Expr::Array(elems) => {
+            for e in elems {
+                v.visit_expr_mut(e);
+            }
+        }
+        Expr::Index(target, index) => {
+            v.visit_expr_mut(target);
+            v.visit_expr_mut(index);
+        }
+        Expr::Call(callee, args) => {
+            v.visit_expr_mut(callee);
+            for a in args {
+                v.visit_expr_mut(a);
+            }
+        }
+        Expr::Unary(_, operand) | Expr::Spread(operand) | Expr::Try(operand) => v.visit_expr_mut(operand),
+        Expr::Binary(left, _, right) => {
+            v.visit_expr_mut(left);
+            v.visit_expr_mut(right);
+        }
+    }
+}
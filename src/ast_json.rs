@@ -0,0 +1,278 @@
+use crate::json::{self, Json};
+use crate::parser::{BinOp, Expr, Stmt, StmtKind, UnaryOp};
+use crate::symbol::Symbol;
+
+// Every node is an object tagged with "type"; statements also carry "line"
+// and, when they have a doc comment, "doc".
+pub fn to_json(program: &[Stmt]) -> String {
+    block_to_json(program).to_string()
+}
+
+pub fn from_json(text: &str) -> Result<Vec<Stmt>, String> {
+    block_from_json(&json::parse(text)?)
+}
+
+fn obj(kind: &str, fields: Vec<(&str, Json)>) -> Json {
+    let mut all = vec![("type".to_string(), Json::String(kind.to_string()))];
+    all.extend(fields.into_iter().map(|(k, v)| (k.to_string(), v)));
+    Json::Object(all)
+}
+
+fn string(s: &str) -> Json {
+    Json::String(s.to_string())
+}
+
+pub(crate) fn block_to_json(stmts: &[Stmt]) -> Json {
+    Json::Array(stmts.iter().map(stmt_to_json).collect())
+}
+
+fn stmt_to_json(stmt: &Stmt) -> Json {
+    let (kind, mut fields) = match &stmt.kind {
+        StmtKind::Let(name, value) => ("Let", vec![("name", string(name)), ("value", expr_to_json(value))]),
+        StmtKind::LetArray(names, rest, value) => (
+            "LetArray",
+            vec![
+                ("names", Json::Array(names.iter().map(|n| string(n)).collect())),
+                ("rest", rest.as_ref().map_or(Json::Null, |r| string(r))),
+                ("value", expr_to_json(value)),
+            ],
+        ),
+        StmtKind::Assign(name, value) => {
+            ("Assign", vec![("name", string(name)), ("value", expr_to_json(value))])
+        }
+        StmtKind::MultiAssign(names, values) => (
+            "MultiAssign",
+            vec![
+                ("names", Json::Array(names.iter().map(|n| string(n)).collect())),
+                ("values", Json::Array(values.iter().map(expr_to_json).collect())),
+            ],
+        ),
+        StmtKind::IndexAssign(name, index, value) => (
+            "IndexAssign",
+            vec![
+                ("name", string(name)),
+                ("index", expr_to_json(index)),
+                ("value", expr_to_json(value)),
+            ],
+        ),
+        StmtKind::If(cond, then, otherwise) => (
+            "If",
+            vec![
+                ("cond", expr_to_json(cond)),
+                ("then", block_to_json(then)),
+                ("else", otherwise.as_deref().map_or(Json::Null, block_to_json)),
+            ],
+        ),
+        StmtKind::While(cond, body) => {
+            ("While", vec![("cond", expr_to_json(cond)), ("body", block_to_json(body))])
+        }
+        StmtKind::For(var, start, end, body) => (
+            "For",
+            vec![
+                ("var", string(var)),
+                ("start", expr_to_json(start)),
+                ("end", expr_to_json(end)),
+                ("body", block_to_json(body)),
+            ],
+        ),
+        StmtKind::ForEach(var, iterable, body) => (
+            "ForEach",
+            vec![
+                ("var", string(var)),
+                ("iterable", expr_to_json(iterable)),
+                ("body", block_to_json(body)),
+            ],
+        ),
+        StmtKind::Fn(name, params, body) => (
+            "Fn",
+            vec![
+                ("name", string(name)),
+                ("params", Json::Array(params.iter().map(|p| string(p)).collect())),
+                ("body", block_to_json(body)),
+            ],
+        ),
+        StmtKind::Return(value) => ("Return", vec![("value", value.as_ref().map_or(Json::Null, expr_to_json))]),
+        StmtKind::Labeled(label, inner) => ("Labeled", vec![("label", string(label)), ("body", stmt_to_json(inner))]),
+        StmtKind::Break(label) => ("Break", vec![("label", label.as_ref().map_or(Json::Null, |l| string(l)))]),
+        StmtKind::Continue(label) => ("Continue", vec![("label", label.as_ref().map_or(Json::Null, |l| string(l)))]),
+        StmtKind::Global(names) => {
+            ("Global", vec![("names", Json::Array(names.iter().map(|n| string(n)).collect()))])
+        }
+        StmtKind::ExprStmt(expr) => ("ExprStmt", vec![("expr", expr_to_json(expr))]),
+    };
+    fields.push(("line", Json::Number(stmt.line as f64)));
+    if let Some(doc) = &stmt.doc {
+        fields.push(("doc", string(doc)));
+    }
+    obj(kind, fields)
+}
+
+fn expr_to_json(expr: &Expr) -> Json {
+    match expr {
+        Expr::Number(n) => obj("Number", vec![("value", Json::Number(*n))]),
+        Expr::StringLit(s) => obj("String", vec![("value", string(s))]),
+        Expr::Bool(b) => obj("Bool", vec![("value", Json::Bool(*b))]),
+        Expr::Ident(name) => obj("Ident", vec![("name", string(name))]),
+        Expr::Array(elems) => obj(
+            "Array",
+            vec![("elements", Json::Array(elems.iter().map(expr_to_json).collect()))],
+        ),
+        Expr::Index(target, index) => obj(
+            "Index",
+            vec![("target", expr_to_json(target)), ("index", expr_to_json(index))],
+        ),
+        Expr::Call(callee, args) => obj(
+            "Call",
+            vec![
+                ("callee", expr_to_json(callee)),
+                ("args", Json::Array(args.iter().map(expr_to_json).collect())),
+            ],
+        ),
+        Expr::Spread(operand) => obj("Spread", vec![("operand", expr_to_json(operand))]),
+        Expr::Try(operand) => obj("Try", vec![("operand", expr_to_json(operand))]),
+        Expr::Unary(op, operand) => obj(
+            "Unary",
+            vec![("op", string(op.symbol())), ("operand", expr_to_json(operand))],
+        ),
+        Expr::Binary(left, op, right) => obj(
+            "Binary",
+            vec![
+                ("op", string(op.symbol())),
+                ("left", expr_to_json(left)),
+                ("right", expr_to_json(right)),
+            ],
+        ),
+    }
+}
+
+fn field<'a>(node: &'a Json, name: &str) -> Result<&'a Json, String> {
+    node.get(name)
+        .ok_or_else(|| format!("AST node is missing field '{}'", name))
+}
+
+fn str_field(node: &Json, name: &str) -> Result<String, String> {
+    field(node, name)?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("AST field '{}' must be a string", name))
+}
+
+fn name_field(node: &Json, name: &str) -> Result<Symbol, String> {
+    str_field(node, name).map(|s| Symbol::intern(&s))
+}
+
+fn list<'a>(node: &'a Json, name: &str) -> Result<&'a [Json], String> {
+    match field(node, name)? {
+        Json::Array(items) => Ok(items),
+        _ => Err(format!("AST field '{}' must be an array", name)),
+    }
+}
+
+fn optional_name(node: &Json, name: &str) -> Result<Option<Symbol>, String> {
+    match field(node, name)? {
+        Json::Null => Ok(None),
+        _ => name_field(node, name).map(Some),
+    }
+}
+
+fn names(node: &Json, name: &str) -> Result<Vec<Symbol>, String> {
+    list(node, name)?
+        .iter()
+        .map(|n| n.as_str().map(Symbol::intern).ok_or_else(|| format!("AST field '{}' must hold strings", name)))
+        .collect()
+}
+
+pub(crate) fn block_from_json(json: &Json) -> Result<Vec<Stmt>, String> {
+    match json {
+        Json::Array(items) => items.iter().map(stmt_from_json).collect(),
+        _ => Err("AST block must be an array".to_string()),
+    }
+}
+
+fn stmt_from_json(node: &Json) -> Result<Stmt, String> {
+    let expr = |name| expr_from_json(field(node, name)?);
+    let block = |name| block_from_json(field(node, name)?);
+    let kind = match str_field(node, "type")?.as_str() {
+        "Let" => StmtKind::Let(name_field(node, "name")?, expr("value")?),
+        "Assign" => StmtKind::Assign(name_field(node, "name")?, expr("value")?),
+        "MultiAssign" => {
+            let names = names(node, "names")?;
+            let values = list(node, "values")?.iter().map(expr_from_json).collect::<Result<Vec<_>, _>>()?;
+            if names.len() != values.len() {
+                return Err("MultiAssign needs as many values as names".to_string());
+            }
+            StmtKind::MultiAssign(names, values)
+        }
+        "IndexAssign" => StmtKind::IndexAssign(name_field(node, "name")?, expr("index")?, expr("value")?),
+        "If" => {
+            let otherwise = match field(node, "else")? {
+                Json::Null => None,
+                other => Some(block_from_json(other)?),
+            };
+            StmtKind::If(expr("cond")?, block("then")?, otherwise)
+        }
+        "While" => StmtKind::While(expr("cond")?, block("body")?),
+        "For" => StmtKind::For(name_field(node, "var")?, expr("start")?, expr("end")?, block("body")?),
+        "ForEach" => StmtKind::ForEach(name_field(node, "var")?, expr("iterable")?, block("body")?),
+        "LetArray" => StmtKind::LetArray(names(node, "names")?, optional_name(node, "rest")?, expr("value")?),
+        "Fn" => {
+            let params = names(node, "params")?;
+            StmtKind::Fn(name_field(node, "name")?, params, block("body")?.into())
+        }
+        "Return" => match field(node, "value")? {
+            Json::Null => StmtKind::Return(None),
+            value => StmtKind::Return(Some(expr_from_json(value)?)),
+        },
+        "Global" => StmtKind::Global(names(node, "names")?),
+        "Labeled" => StmtKind::Labeled(name_field(node, "label")?, Box::new(stmt_from_json(field(node, "body")?)?)),
+        "Break" => StmtKind::Break(optional_name(node, "label")?),
+        "Continue" => StmtKind::Continue(optional_name(node, "label")?),
+        "ExprStmt" => StmtKind::ExprStmt(expr("expr")?),
+        other => return Err(format!("Unknown statement type '{}'", other)),
+    };
+    let line = match node.get("line") {
+        Some(Json::Number(n)) => *n as usize,
+        _ => 0,
+    };
+    let doc = match node.get("doc") {
+        Some(Json::String(doc)) => Some(doc.clone()),
+        _ => None,
+    };
+    Ok(Stmt { kind, line, doc })
+}
+
+fn expr_from_json(node: &Json) -> Result<Expr, String> {
+    let sub = |name| -> Result<Box<Expr>, String> { Ok(Box::new(expr_from_json(field(node, name)?)?)) };
+    let exprs = |name| list(node, name)?.iter().map(expr_from_json).collect::<Result<Vec<_>, _>>();
+    let expr = match str_field(node, "type")?.as_str() {
+        "Number" => match field(node, "value")? {
+            Json::Number(n) => Expr::Number(*n),
+            _ => return Err("Number value must be a number".to_string()),
+        },
+        "String" => Expr::StringLit(str_field(node, "value")?),
+        "Bool" => match field(node, "value")? {
+            Json::Bool(b) => Expr::Bool(*b),
+            _ => return Err("Bool value must be a boolean".to_string()),
+        },
+        "Ident" => Expr::Ident(name_field(node, "name")?),
+        "Array" => Expr::Array(exprs("elements")?),
+        "Index" => Expr::Index(sub("target")?, sub("index")?),
+        "Call" => Expr::Call(sub("callee")?, exprs("args")?),
+        "Spread" => Expr::Spread(sub("operand")?),
+        "Try" => Expr::Try(sub("operand")?),
+        "Unary" => {
+            let symbol = str_field(node, "op")?;
+            let op = UnaryOp::from_symbol(&symbol)
+                .ok_or_else(|| format!("Unknown unary operator '{}'", symbol))?;
+            Expr::Unary(op, sub("operand")?)
+        }
+        "Binary" => {
+            let symbol = str_field(node, "op")?;
+            let op = BinOp::from_symbol(&symbol)
+                .ok_or_else(|| format!("Unknown binary operator '{}'", symbol))?;
+            Expr::Binary(sub("left")?, op, sub("right")?)
+        }
+        other => return Err(format!("Unknown expression type '{}'", other)),
+    };
+    Ok(expr)
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::ast_json;
+use crate::json::{self, Json};
+use crate::program::Program;
+
+// Parsed programs keyed by a hash of their source. Entries live in memory
+// and, with a directory, also on disk as AST JSON next to the source, so the
+// next process can skip parsing. The hash only picks the entry: its source
+// must match too, so a collision is a miss. The disk copy is best effort:
+// unreadable or stale files are parsed again and overwritten.
+#[derive(Default)]
+pub struct ProgramCache {
+    programs: HashMap<u64, (String, Arc<Program>)>,
+    dir: Option<PathBuf>,
+}
+
+impl ProgramCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        ProgramCache {
+            dir: Some(dir.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    pub fn get(&mut self, source: &str) -> Option<Arc<Program>> {
+        let key = source_hash(source);
+        if let Some((cached, program)) = self.programs.get(&key) {
+            return (cached == source).then(|| program.clone());
+        }
+        let entry = json::parse(&std::fs::read_to_string(self.path(key)?).ok()?).ok()?;
+        if entry.get("source")?.as_str()? != source {
+            return None;
+        }
+        let program = Program::new(ast_json::block_from_json(entry.get("ast")?).ok()?);
+        self.programs.insert(key, (source.to_string(), program.clone()));
+        Some(program)
+    }
+
+    pub fn insert(&mut self, source: &str, program: &Arc<Program>) {
+        let key = source_hash(source);
+        if let Some(path) = self.path(key) {
+            let entry = Json::Object(vec![
+                ("source".to_string(), Json::String(source.to_string())),
+                ("ast".to_string(), ast_json::block_to_json(program.stmts())),
+            ]);
+            let _ = std::fs::create_dir_all(path.parent().unwrap())
+                .and_then(|_| std::fs::write(&path, entry.to_string()));
+        }
+        self.programs.insert(key, (source.to_string(), program.clone()));
+    }
+
+    // Like Program::parse, but only parses sources it has not seen.
+    pub fn parse(&mut self, source: &str) -> Result<Arc<Program>, String> {
+        if let Some(program) = self.get(source) {
+            return Ok(program);
+        }
+        let program = Program::parse(source)?;
+        self.insert(source, &program);
+        Ok(program)
+    }
+
+    fn path(&self, key: u64) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{:016x}.json", key)))
+    }
+}
+
+// 64-bit FNV-1a of the source, seeded with the crate version so that files
+// written by a build with a different AST are never read back. Unlike std's
+// DefaultHasher it is the same in every build.
+pub fn source_hash(source: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in env!("CARGO_PKG_VERSION").bytes().chain([0]).chain(source.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
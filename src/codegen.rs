@@ -0,0 +1,71 @@
+pub mod python;
+
+use crate::parser::{Expr, Stmt};
+
+// A source-to-source target. `generate` drives the program and the
+// Generator owns indentation; a backend decides how each statement and
+// expression is spelled.
+pub trait Backend {
+    // Emitted once before the program, e.g. imports and runtime helpers.
+    fn prelude(&self) -> &str;
+
+    fn stmt(&mut self, out: &mut Generator, stmt: &Stmt) -> Result<(), String>;
+
+    fn expr(&mut self, expr: &Expr) -> Result<String, String>;
+}
+
+pub struct Generator {
+    code: String,
+    depth: usize,
+    indent: &'static str,
+}
+
+impl Generator {
+    pub fn new(indent: &'static str) -> Self {
+        Generator {
+            code: String::new(),
+            depth: 0,
+            indent,
+        }
+    }
+
+    pub fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.code.push_str(self.indent);
+        }
+        self.code.push_str(text);
+        self.code.push('\n');
+    }
+
+    pub fn indent(&mut self) {
+        self.depth += 1;
+    }
+
+    pub fn dedent(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    pub fn finish(self) -> String {
+        self.code
+    }
+}
+
+pub fn generate(backend: &mut dyn Backend, indent: &'static str, program: &[Stmt]) -> Result<String, String> {
+    let mut out = Generator::new(indent);
+    for line in backend.prelude().lines() {
+        out.line(line);
+    }
+    for stmt in program {
+        backend.stmt(&mut out, stmt)?;
+    }
+    Ok(out.finish())
+}
+
+// Number literal text shared by the backends: integers without a fraction.
+pub fn number_literal(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{}", n)
+    }
+}
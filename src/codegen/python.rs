@@ -0,0 +1,498 @@
+use std::collections::HashSet;
+
+use super::{Backend, Generator, generate, number_literal};
+use crate::interpreter::builtins;
+use crate::json::quote;
+use crate::parser::{Expr, Stmt, StmtKind, UnaryOp};
+use crate::symbol::{self, Symbol};
+
+const PRELUDE: &str = r#"import csv
+import datetime
+import decimal
+import io
+import itertools
+import json
+import math
+import subprocess
+import sys
+import time
+
+
+def _ml_str(v):
+    if v is None:
+        return "null"
+    if isinstance(v, bool):
+        return "true" if v else "false"
+    if isinstance(v, float):
+        return _ml_num(v)
+    if isinstance(v, list):
+        return "[" + ", ".join(_ml_repr(x) for x in v) + "]"
+    if callable(v):
+        return "<function>"
+    if hasattr(v, "__next__"):
+        return "<iterator>"
+    return str(v)
+
+
+def _ml_repr(v):
+    if isinstance(v, _MlError):
+        return "error(" + json.dumps(v.message, ensure_ascii=False) + ")"
+    return json.dumps(v, ensure_ascii=False) if isinstance(v, str) else _ml_str(v)
+
+
+class _MlError:
+    def __init__(self, message):
+        self.message = message
+
+    def __bool__(self):
+        return False
+
+    def __eq__(self, other):
+        return isinstance(other, _MlError) and other.message == self.message
+
+    def __str__(self):
+        return "error: " + self.message
+
+
+def _ml_pretty(v, indent=0):
+    flat = _ml_repr(v)
+    if not isinstance(v, list) or not v or indent + len(flat) <= 80:
+        return flat
+    pad = " " * (indent + 2)
+    return "[\n" + ",\n".join(pad + _ml_pretty(x, indent + 2) for x in v) + "\n" + " " * indent + "]"
+
+
+def _ml_num(v):
+    if v != v:
+        return "NaN"
+    if v in (math.inf, -math.inf):
+        return "inf" if v > 0 else "-inf"
+    if v == 0:
+        return "0"
+    if abs(v) >= 1e21 or abs(v) < 1e-6:
+        mantissa, exp = repr(v).split("e")
+        return mantissa + "e" + str(int(exp))
+    if v.is_integer():
+        return str(int(float(repr(v))))
+    return format(decimal.Decimal(repr(v)), "f")
+
+
+def _ml_is(a, b):
+    return a is b if isinstance(a, list) or callable(a) else a == b
+
+
+def _ml_iter(v):
+    return iter(v, None) if callable(v) else v
+
+
+def _ml_index_of(a, v):
+    return a.index(v) if v in a else -1
+
+
+def _ml_slice(a, start, end=None):
+    start = max(0, int(start))
+    return a[start:] if end is None else a[start:max(start, int(end))]
+
+
+def _ml_assert(cond, message=None):
+    if not cond:
+        raise AssertionError("Assertion failed" if message is None else "Assertion failed: " + _ml_str(message))
+
+
+def _ml_panic(message):
+    raise RuntimeError(message.message if isinstance(message, _MlError) else _ml_str(message))
+
+
+def _ml_assert_eq(actual, expected):
+    if actual != expected:
+        raise AssertionError("values differ: expected %s, got %s" % (_ml_str(expected), _ml_str(actual)))
+
+
+def _ml_run(cmd, args=None):
+    p = subprocess.run([cmd] + [a if isinstance(a, str) else _ml_str(a) for a in args or []], capture_output=True, text=True)
+    return [p.stdout, p.stderr, float(p.returncode) if p.returncode >= 0 else None]
+
+
+def _ml_utc(ts):
+    return datetime.datetime.fromtimestamp(ts, datetime.timezone.utc)
+
+
+def _ml_date(ts=None):
+    d = _ml_utc(time.time() if ts is None else ts)
+    return [float(x) for x in (d.year, d.month, d.day, d.hour, d.minute, d.second, d.isoweekday())]
+
+
+def _ml_parse_time(s):
+    try:
+        d = datetime.datetime.fromisoformat(s.strip())
+    except ValueError:
+        return None
+    return (d if d.tzinfo else d.replace(tzinfo=datetime.timezone.utc)).timestamp()
+
+
+def _ml_csv_stringify(rows):
+    out = io.StringIO()
+    w = csv.writer(out, lineterminator="\n")
+    w.writerows([["" if c is None else c if isinstance(c, str) else _ml_str(c) for c in row] for row in rows])
+    return out.getvalue()
+
+
+def _ml_input():
+    line = sys.stdin.readline()
+    return line.rstrip("\r\n") if line else None
+
+"#;
+
+pub fn transpile(program: &[Stmt]) -> Result<String, String> {
+    generate(&mut Python::default(), "    ", program)
+}
+
+#[derive(Default)]
+pub struct Python {
+    // Names local to each enclosing function, innermost last.
+    functions: Vec<HashSet<Symbol>>,
+    // Labels of the enclosing loops, innermost last.
+    loops: Vec<Option<Symbol>>,
+}
+
+// Names a function body binds itself, not counting nested functions' bodies.
+fn locals(params: &[Symbol], body: &[Stmt]) -> HashSet<Symbol> {
+    fn collect(stmts: &[Stmt], names: &mut HashSet<Symbol>) {
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Let(name, _) | StmtKind::Fn(name, _, _) => {
+                    names.insert(*name);
+                }
+                StmtKind::LetArray(list, rest, _) => {
+                    names.extend(list.iter().chain(rest));
+                }
+                StmtKind::For(var, _, _, body) | StmtKind::ForEach(var, _, body) => {
+                    names.insert(*var);
+                    collect(body, names);
+                }
+                StmtKind::If(_, body, else_body) => {
+                    collect(body, names);
+                    if let Some(else_b) = else_body {
+                        collect(else_b, names);
+                    }
+                }
+                StmtKind::While(_, body) => collect(body, names),
+                StmtKind::Labeled(_, inner) => collect(std::slice::from_ref(inner), names),
+                _ => {}
+            }
+        }
+    }
+    let mut names: HashSet<Symbol> = params.iter().copied().collect();
+    collect(body, &mut names);
+    names
+}
+
+// Names a function body declares with `global`.
+fn globals(stmts: &[Stmt], names: &mut Vec<Symbol>) {
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::Global(list) => {
+                for name in list {
+                    if !names.contains(name) {
+                        names.push(*name);
+                    }
+                }
+            }
+            StmtKind::For(_, _, _, body) | StmtKind::ForEach(_, _, body) | StmtKind::While(_, body) => {
+                globals(body, names)
+            }
+            StmtKind::Labeled(_, inner) => globals(std::slice::from_ref(inner), names),
+            StmtKind::If(_, body, else_body) => {
+                globals(body, names);
+                if let Some(else_b) = else_body {
+                    globals(else_b, names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Names assigned with `=` somewhere in the body.
+fn assigned(stmts: &[Stmt], names: &mut Vec<Symbol>) {
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::Assign(name, _) if !names.contains(name) => names.push(*name),
+            StmtKind::MultiAssign(list, _) => {
+                for name in list {
+                    if !names.contains(name) {
+                        names.push(*name);
+                    }
+                }
+            }
+            StmtKind::For(_, _, _, body) | StmtKind::ForEach(_, _, body) | StmtKind::While(_, body) => {
+                assigned(body, names)
+            }
+            StmtKind::Labeled(_, inner) => assigned(std::slice::from_ref(inner), names),
+            StmtKind::If(_, body, else_body) => {
+                assigned(body, names);
+                if let Some(else_b) = else_body {
+                    assigned(else_b, names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Python {
+    // An indented suite; Python needs `pass` when it would be empty.
+    fn suite(&mut self, out: &mut Generator, header: &[String], stmts: &[Stmt]) -> Result<(), String> {
+        out.indent();
+        for line in header {
+            out.line(line);
+        }
+        if header.is_empty() && stmts.is_empty() {
+            out.line("pass");
+        }
+        let result = stmts.iter().try_for_each(|s| self.stmt(out, s));
+        out.dedent();
+        result
+    }
+
+    fn function(&mut self, out: &mut Generator, name: &str, params: &[Symbol], body: &[Stmt]) -> Result<(), String> {
+        // The prelude's helpers call Python builtins such as len().
+        if builtins::lookup(name).is_some() {
+            return Err(format!("Redefining builtin '{}' is not supported by the Python backend", name));
+        }
+        out.line(&format!("def {}({}):", name, symbol::join(params, ", ")));
+        let own = locals(params, body);
+        let mut declared = Vec::new();
+        globals(body, &mut declared);
+        let mut outer = Vec::new();
+        assigned(body, &mut outer);
+        outer.retain(|n| !own.contains(n) && !declared.contains(n));
+
+        // Assignments to outer variables need a declaration in Python.
+        let header: Vec<String> = declared
+            .iter()
+            .map(|name| format!("global {}", name))
+            .chain(outer.iter().map(|name| {
+                if self.functions.iter().any(|f| f.contains(name)) {
+                    format!("nonlocal {}", name)
+                } else {
+                    format!("global {}", name)
+                }
+            }))
+            .collect();
+
+        self.functions.push(own);
+        let result = self.suite(out, &header, body);
+        self.functions.pop();
+        result
+    }
+
+    fn loop_stmt(&mut self, out: &mut Generator, kind: &StmtKind, label: Option<Symbol>) -> Result<(), String> {
+        let body = match kind {
+            StmtKind::While(cond, body) => {
+                out.line(&format!("while {}:", self.expr(cond)?));
+                body
+            }
+            StmtKind::For(var, start, end, body) => {
+                let start = self.expr(start)?;
+                let end = self.expr(end)?;
+                out.line(&format!("for {} in range(int({}), int({})):", var, start, end));
+                body
+            }
+            StmtKind::ForEach(var, iterable, body) => {
+                let iterable = match iterable {
+                    Expr::Array(_) | Expr::StringLit(_) => self.expr(iterable)?,
+                    _ => format!("_ml_iter({})", self.expr(iterable)?),
+                };
+                out.line(&format!("for {} in {}:", var, iterable));
+                body
+            }
+            _ => return Err("Only loops can be labeled".to_string()),
+        };
+        self.loops.push(label);
+        let result = self.suite(out, &[], body);
+        self.loops.pop();
+        result
+    }
+
+    // Python can only break out of or continue the innermost loop.
+    fn jump(&self, keyword: &str, label: Option<Symbol>) -> Result<String, String> {
+        match label {
+            Some(label) if self.loops.last() != Some(&Some(label)) => Err(format!(
+                "'{} {}' targets an outer loop, which the Python backend does not support",
+                keyword, label
+            )),
+            _ => Ok(keyword.to_string()),
+        }
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr]) -> Result<String, String> {
+        let mut parts = Vec::new();
+        for a in args {
+            parts.push(self.expr(a)?);
+        }
+        let joined = parts.join(", ");
+        if let Expr::Ident(name) = callee {
+            match name.as_str() {
+                "print" => {
+                    return Ok(match parts.first() {
+                        Some(v) => format!("print(_ml_str({}))", v),
+                        None => "None".to_string(),
+                    });
+                }
+                "len" => return Ok(format!("len({})", joined)),
+                "args" => return Ok("sys.argv[1:]".to_string()),
+                "exit" => return Ok(format!("sys.exit(int({}))", parts.first().map_or("0", |s| s))),
+                "input" => return Ok("_ml_input()".to_string()),
+                "push" if parts.len() == 2 => return Ok(format!("{}.append({})", parts[0], parts[1])),
+                "index_of" => return Ok(format!("_ml_index_of({})", joined)),
+                "slice" => return Ok(format!("_ml_slice({})", joined)),
+                "assert" => return Ok(format!("_ml_assert({})", joined)),
+                "panic" => return Ok(format!("_ml_panic({})", joined)),
+                "assert_eq" => return Ok(format!("_ml_assert_eq({})", joined)),
+                "bytes" => return Ok(format!("list({}.encode())", joined)),
+                "run" => return Ok(format!("_ml_run({})", joined)),
+                "now" => return Ok("time.time()".to_string()),
+                "date" => return Ok(format!("_ml_date({})", joined)),
+                "format_time" if parts.len() == 2 => {
+                    return Ok(format!("_ml_utc({}).strftime({})", parts[0], parts[1]));
+                }
+                "parse_time" => return Ok(format!("_ml_parse_time({})", joined)),
+                "csv_parse" => return Ok(format!("list(csv.reader(io.StringIO({}, newline=\"\")))", joined)),
+                "csv_stringify" => return Ok(format!("_ml_csv_stringify({})", joined)),
+                "sum" => return Ok(format!("sum({})", joined)),
+                "min" | "max" => return Ok(format!("{}({}, default=None)", name, joined)),
+                "any" | "all" if parts.len() == 2 => {
+                    return Ok(format!("{}({}(_ml_x) for _ml_x in {})", name, parts[1], parts[0]));
+                }
+                "any" | "all" => return Ok(format!("{}({})", name, joined)),
+                "sorted" => return Ok(format!("sorted({})", joined)),
+                "reverse" => return Ok(format!("list(reversed({}))", joined)),
+                "pprint" => return Ok(format!("print(_ml_pretty({}))", joined)),
+                "repr" => return Ok(format!("_ml_repr({})", joined)),
+                "error" => return Ok(format!("_MlError({})", joined)),
+                "iter" => return Ok(format!("iter(_ml_iter({}))", joined)),
+                "take" if parts.len() == 2 => {
+                    return Ok(format!("itertools.islice(_ml_iter({}), int({}))", parts[0], parts[1]));
+                }
+                "skip" if parts.len() == 2 => {
+                    return Ok(format!("itertools.islice(_ml_iter({}), int({}), None)", parts[0], parts[1]));
+                }
+                "map_lazy" if parts.len() == 2 => return Ok(format!("map({}, _ml_iter({}))", parts[1], parts[0])),
+                "collect" => return Ok(format!("list(_ml_iter({}))", joined)),
+                "is_error" => return Ok(format!("isinstance({}, _MlError)", joined)),
+                "error_message" => return Ok(format!("{}.message", joined)),
+                "to_fixed" if parts.len() == 2 => {
+                    return Ok(format!("format({}, \".%df\" % int({}))", parts[0], parts[1]));
+                }
+                "find" if parts.len() == 2 => {
+                    return Ok(format!("next((_ml_x for _ml_x in {} if {}(_ml_x)), None)", parts[0], parts[1]));
+                }
+                "format_number" => {
+                    return Err("format_number() is not supported by the Python backend".to_string());
+                }
+                "builtin" => {
+                    return match args.first() {
+                        Some(Expr::StringLit(target)) if builtins::lookup(target).is_some() && target != "builtin" => {
+                            self.call(&Expr::Ident(Symbol::intern(target)), &args[1..])
+                        }
+                        _ => Err("builtin() needs a builtin's name as a string literal in the Python backend".to_string()),
+                    };
+                }
+                "eval" | "globals" | "locals" | "help" | "freeze" | "is_frozen" => {
+                    return Err(format!("{}() is not supported by the Python backend", name));
+                }
+                _ => {}
+            }
+        }
+        Ok(format!("{}({})", self.expr(callee)?, joined))
+    }
+}
+
+impl Backend for Python {
+    fn prelude(&self) -> &str {
+        PRELUDE
+    }
+
+    fn stmt(&mut self, out: &mut Generator, stmt: &Stmt) -> Result<(), String> {
+        match &stmt.kind {
+            StmtKind::Let(name, value) | StmtKind::Assign(name, value) => {
+                let value = self.expr(value)?;
+                out.line(&format!("{} = {}", name, value));
+            }
+            StmtKind::LetArray(names, rest, value) => {
+                let mut targets: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+                if let Some(rest) = rest {
+                    targets.push(format!("*{}", rest));
+                }
+                out.line(&format!("[{}] = {}", targets.join(", "), self.expr(value)?));
+            }
+            StmtKind::MultiAssign(names, values) => {
+                let values = values.iter().map(|v| self.expr(v)).collect::<Result<Vec<_>, _>>()?;
+                out.line(&format!("{} = {}", symbol::join(names, ", "), values.join(", ")));
+            }
+            StmtKind::IndexAssign(name, index, value) => {
+                let index = self.expr(index)?;
+                let value = self.expr(value)?;
+                out.line(&format!("{}[int({})] = {}", name, index, value));
+            }
+            StmtKind::If(cond, body, else_body) => {
+                out.line(&format!("if {}:", self.expr(cond)?));
+                self.suite(out, &[], body)?;
+                if let Some(else_b) = else_body {
+                    out.line("else:");
+                    self.suite(out, &[], else_b)?;
+                }
+            }
+            StmtKind::While(..) | StmtKind::For(..) | StmtKind::ForEach(..) => self.loop_stmt(out, &stmt.kind, None)?,
+            StmtKind::Labeled(label, inner) => self.loop_stmt(out, &inner.kind, Some(*label))?,
+            StmtKind::Break(label) => out.line(&self.jump("break", *label)?),
+            StmtKind::Continue(label) => out.line(&self.jump("continue", *label)?),
+            StmtKind::Fn(name, params, body) => self.function(out, name, params, body)?,
+            // A top-level `return` ends the program.
+            StmtKind::Return(_) if self.functions.is_empty() => out.line("raise SystemExit"),
+            StmtKind::Return(Some(value)) => out.line(&format!("return {}", self.expr(value)?)),
+            StmtKind::Return(None) => out.line("return"),
+            // Declared in the function header instead.
+            StmtKind::Global(_) => {}
+            StmtKind::ExprStmt(expr) => out.line(&self.expr(expr)?),
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<String, String> {
+        Ok(match expr {
+            Expr::Number(n) => number_literal(*n),
+            Expr::StringLit(s) => quote(s),
+            Expr::Bool(true) => "True".to_string(),
+            Expr::Bool(false) => "False".to_string(),
+            Expr::Ident(name) => name.to_string(),
+            Expr::Array(elems) => {
+                let mut parts = Vec::new();
+                for e in elems {
+                    parts.push(self.expr(e)?);
+                }
+                format!("[{}]", parts.join(", "))
+            }
+            Expr::Index(target, index) => format!("{}[int({})]", self.expr(target)?, self.expr(index)?),
+            Expr::Call(callee, args) => self.call(callee, args)?,
+            Expr::Spread(operand) => format!("*{}", self.expr(operand)?),
+            Expr::Try(_) => return Err("'?' is not supported by the Python backend".to_string()),
+            Expr::Unary(UnaryOp::Neg, operand) => format!("(-{})", self.expr(operand)?),
+            Expr::Unary(UnaryOp::Not, operand) => format!("(not {})", self.expr(operand)?),
+            Expr::Binary(left, op, right) => {
+                let left = self.expr(left)?;
+                let right = self.expr(right)?;
+                match op.symbol() {
+                    // Python's % floors; minilang's truncates like C.
+                    "%" => format!("math.fmod({}, {})", left, right),
+                    "===" => format!("({} == {})", left, right),
+                    "is" => format!("_ml_is({}, {})", left, right),
+                    "is not" => format!("(not _ml_is({}, {}))", left, right),
+                    "!==" => format!("({} != {})", left, right),
+                    symbol => format!("({} {} {})", left, symbol, right),
+                }
+            }
+        })
+    }
+}
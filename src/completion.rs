@@ -0,0 +1,43 @@
+use crate::interpreter::{Interpreter, builtins};
+use crate::lexer::KEYWORDS;
+
+pub fn candidates(interpreter: &Interpreter) -> Vec<String> {
+    let mut names = interpreter.names();
+    names.extend(builtins::STANDARD.iter().map(|b| b.name.to_string()));
+    names.extend(KEYWORDS.iter().map(|s| s.to_string()));
+    names.sort();
+    names.dedup();
+    names
+}
+
+// Returns the start of the identifier being typed before `cursor` (a char
+// index) and the candidates it is a prefix of.
+pub fn complete(line: &str, cursor: usize, candidates: &[String]) -> (usize, Vec<String>) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut start = cursor.min(chars.len());
+    while start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        start -= 1;
+    }
+    let prefix: String = chars[start..cursor.min(chars.len())].iter().collect();
+    if prefix.is_empty() {
+        return (start, Vec::new());
+    }
+    let matches = candidates
+        .iter()
+        .filter(|c| c.starts_with(&prefix))
+        .cloned()
+        .collect();
+    (start, matches)
+}
+
+pub fn common_prefix(words: &[String]) -> String {
+    let mut prefix: Vec<char> = match words.first() {
+        Some(w) => w.chars().collect(),
+        None => return String::new(),
+    };
+    for w in &words[1..] {
+        let len = prefix.iter().zip(w.chars()).take_while(|(a, b)| **a == *b).count();
+        prefix.truncate(len);
+    }
+    prefix.into_iter().collect()
+}
@@ -0,0 +1,170 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Calendar fields of a timestamp, in UTC. `weekday` follows ISO 8601:
+// 1 is Monday, 7 is Sunday.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub weekday: u32,
+}
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Seconds since the Unix epoch, with a fractional part.
+pub fn now() -> f64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's
+// days_from_civil).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl DateTime {
+    pub fn from_timestamp(ts: f64) -> DateTime {
+        let secs = ts.floor() as i64;
+        let days = secs.div_euclid(86400);
+        let rem = secs.rem_euclid(86400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem / 60 % 60,
+            second: rem % 60,
+            weekday: (days + 3).rem_euclid(7) as u32 + 1,
+        }
+    }
+
+    // strftime-style: %Y %m %d %H %M %S %j %a %b and %%.
+    pub fn format(&self, pattern: &str) -> Result<String, String> {
+        let mut out = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&format!("{:04}", self.year)),
+                Some('m') => out.push_str(&format!("{:02}", self.month)),
+                Some('d') => out.push_str(&format!("{:02}", self.day)),
+                Some('H') => out.push_str(&format!("{:02}", self.hour)),
+                Some('M') => out.push_str(&format!("{:02}", self.minute)),
+                Some('S') => out.push_str(&format!("{:02}", self.second)),
+                Some('j') => {
+                    let yday = days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1) + 1;
+                    out.push_str(&format!("{:03}", yday));
+                }
+                Some('a') => out.push_str(WEEKDAYS[self.weekday as usize - 1]),
+                Some('b') => out.push_str(MONTHS[self.month as usize - 1]),
+                Some('%') => out.push('%'),
+                Some(other) => return Err(format!("Unknown format specifier '%{}'", other)),
+                None => return Err("Format pattern ends with '%'".to_string()),
+            }
+        }
+        Ok(out)
+    }
+}
+
+// Reads a fixed number of ASCII digits.
+fn digits(s: &str, n: usize) -> Option<(u32, &str)> {
+    let head = s.get(..n)?;
+    if !head.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((head.parse().ok()?, &s[n..]))
+}
+
+// `YYYY-MM-DD`, optionally followed by `T` or a space and `HH:MM[:SS[.fff]]`
+// and a `Z` or `±HH:MM` offset. Times without an offset are UTC.
+pub fn parse_iso8601(text: &str) -> Option<f64> {
+    let s = text.trim();
+    let (year, s) = digits(s, 4)?;
+    let (month, s) = digits(s.strip_prefix('-')?, 2)?;
+    let (day, mut s) = digits(s.strip_prefix('-')?, 2)?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year as i64, month) {
+        return None;
+    }
+    let mut seconds = 0.0;
+    if let Some(time) = s.strip_prefix(['T', 't', ' ']) {
+        let (hour, rest) = digits(time, 2)?;
+        let (minute, mut rest) = digits(rest.strip_prefix(':')?, 2)?;
+        let mut second = 0.0;
+        if let Some(sec) = rest.strip_prefix(':') {
+            let (whole, after) = digits(sec, 2)?;
+            second = whole as f64;
+            rest = after;
+            if let Some(frac) = rest.strip_prefix('.') {
+                let len = frac.bytes().take_while(u8::is_ascii_digit).count();
+                if len == 0 {
+                    return None;
+                }
+                second += format!("0.{}", &frac[..len]).parse::<f64>().ok()?;
+                rest = &frac[len..];
+            }
+        }
+        if hour > 23 || minute > 59 || second >= 61.0 {
+            return None;
+        }
+        seconds = (hour * 3600 + minute * 60) as f64 + second;
+        s = rest;
+    }
+    let offset = match s {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match s.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let (hours, rest) = digits(&s[1..], 2)?;
+            let (minutes, rest) = digits(rest.strip_prefix(':').unwrap_or(rest), 2)?;
+            if !rest.is_empty() || hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * (hours * 3600 + minutes * 60) as i64
+        }
+    };
+    let days = days_from_civil(year as i64, month, day);
+    Some((days * 86400 - offset) as f64 + seconds)
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+
+use crate::interpreter::{DebugHook, Interpreter, Value};
+use crate::parser::Stmt;
+use crate::symbol;
+
+const PROMPT: &str = "(debug) ";
+// Returned by the hook to end the run; not reported as an error.
+const QUIT: &str = "Debugger quit";
+
+const HELP: &str = "\
+break <line>    stop before the statement on <line> (b)
+delete <line>   remove the breakpoint on <line> (d)
+step            run one statement and stop again (s)
+continue        run until the next breakpoint (c)
+vars            show the variables in each scope, innermost first (v)
+list            show the source around the current line (l)
+quit            stop the program (q)";
+
+struct Debugger {
+    lines: Vec<String>,
+    breakpoints: BTreeSet<usize>,
+    stepping: bool,
+    input: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+}
+
+// Runs `program` under a command prompt read from `input`, starting paused
+// before the first statement. Program output goes wherever the interpreter
+// already sends it.
+pub fn run(
+    interpreter: &mut Interpreter,
+    program: &[Stmt],
+    source: &str,
+    input: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+) -> Result<(), String> {
+    let debugger = Debugger {
+        lines: source.lines().map(str::to_string).collect(),
+        breakpoints: BTreeSet::new(),
+        stepping: true,
+        input,
+        output,
+    };
+    interpreter.set_debug_hook(Some(Box::new(debugger)));
+    let result = interpreter.run(program);
+    interpreter.set_debug_hook(None);
+    match result {
+        Err(e) if e == QUIT => Ok(()),
+        r => r,
+    }
+}
+
+fn describe(name: &str, value: &Value) -> String {
+    match value {
+        Value::Function { params, .. } => format!("fn {}({})", name, symbol::join(params, ", ")),
+        Value::Str(s) => format!("{} = {:?}", name, s),
+        value => format!("{} = {}", name, value),
+    }
+}
+
+impl Debugger {
+    fn source_line(&self, line: usize) -> &str {
+        line.checked_sub(1)
+            .and_then(|i| self.lines.get(i))
+            .map_or("", |l| l.trim())
+    }
+
+    fn list(&self, line: usize) -> String {
+        let mut out = String::new();
+        for n in line.saturating_sub(2).max(1)..=line + 2 {
+            let Some(text) = n.checked_sub(1).and_then(|i| self.lines.get(i)) else { break };
+            let marker = if n == line { '>' } else { ' ' };
+            let bp = if self.breakpoints.contains(&n) { '*' } else { ' ' };
+            out.push_str(&format!("{}{}{:>4} {}\n", marker, bp, n, text));
+        }
+        out
+    }
+
+    fn vars(interpreter: &Interpreter) -> String {
+        let chain = interpreter.scope_chain();
+        let last = chain.len() - 1;
+        let mut out = String::new();
+        for (depth, scope) in chain.iter().enumerate() {
+            let label = if depth == last { "globals".to_string() } else { format!("scope {}", depth) };
+            out.push_str(&format!("{}:\n", label));
+            for (name, value) in scope {
+                out.push_str(&format!("  {}\n", describe(name, value)));
+            }
+        }
+        out
+    }
+
+    fn set_breakpoint(&mut self, arg: Option<&str>, add: bool) -> String {
+        match arg.and_then(|a| a.parse::<usize>().ok()) {
+            Some(line) if add => {
+                self.breakpoints.insert(line);
+                format!("Breakpoint at line {}", line)
+            }
+            Some(line) if self.breakpoints.remove(&line) => format!("Removed breakpoint at line {}", line),
+            Some(line) => format!("No breakpoint at line {}", line),
+            None => "Expected a line number".to_string(),
+        }
+    }
+
+    // Reads commands until one resumes the program.
+    fn prompt(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), String> {
+        loop {
+            write!(self.output, "{}", PROMPT).map_err(|e| e.to_string())?;
+            self.output.flush().map_err(|e| e.to_string())?;
+            let mut command = String::new();
+            if self.input.read_line(&mut command).map_err(|e| e.to_string())? == 0 {
+                return Err(QUIT.to_string());
+            }
+            let mut words = command.split_whitespace();
+            let reply = match words.next() {
+                None => continue,
+                Some("s" | "step") => {
+                    self.stepping = true;
+                    return Ok(());
+                }
+                Some("c" | "continue") => {
+                    self.stepping = false;
+                    return Ok(());
+                }
+                Some("q" | "quit") => return Err(QUIT.to_string()),
+                Some("b" | "break") => self.set_breakpoint(words.next(), true) + "\n",
+                Some("d" | "delete") => self.set_breakpoint(words.next(), false) + "\n",
+                Some("v" | "vars") => Self::vars(interpreter),
+                Some("l" | "list") => self.list(line),
+                Some("h" | "help") => format!("{}\n", HELP),
+                Some(other) => format!("Unknown command '{}' (try help)\n", other),
+            };
+            write!(self.output, "{}", reply).map_err(|e| e.to_string())?;
+        }
+    }
+}
+
+impl DebugHook for Debugger {
+    fn before_stmt(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), String> {
+        if !self.stepping && !self.breakpoints.contains(&line) {
+            return Ok(());
+        }
+        let text = format!("Stopped at line {}: {}\n", line, self.source_line(line));
+        self.output.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+        self.prompt(interpreter, line)
+    }
+}
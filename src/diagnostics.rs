@@ -0,0 +1,53 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub line: usize,
+}
+
+impl Diagnostic {
+    // Wraps a lexer or parser error, taking the line from its
+    // "... at line N" suffix (0 when there is none).
+    pub fn from_error(error: &str) -> Diagnostic {
+        let (message, line) = match error.rsplit_once(" at line ") {
+            Some((message, line)) if line.parse::<usize>().is_ok() => {
+                (message.to_string(), line.parse().unwrap())
+            }
+            _ => (error.to_string(), 0),
+        };
+        Diagnostic {
+            severity: Severity::Error,
+            code: "syntax",
+            message,
+            line,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {}[{}]: {}",
+            self.line, self.severity, self.code, self.message
+        )
+    }
+}
@@ -0,0 +1,62 @@
+use crate::parser::{Stmt, StmtKind, docstring};
+
+// A top-level function and the `##` comment written above it, or else its
+// docstring.
+pub struct FnDoc {
+    pub name: String,
+    pub params: Vec<String>,
+    pub doc: Option<String>,
+}
+
+impl FnDoc {
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.params.join(", "))
+    }
+}
+
+pub fn functions(program: &[Stmt]) -> Vec<FnDoc> {
+    program
+        .iter()
+        .filter_map(|stmt| match &stmt.kind {
+            StmtKind::Fn(name, params, body) => Some(FnDoc {
+                name: name.to_string(),
+                params: params.iter().map(|p| p.to_string()).collect(),
+                doc: stmt.doc.clone().or_else(|| docstring(body)),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn markdown(title: &str, program: &[Stmt]) -> String {
+    let mut out = format!("# {}\n", title);
+    for f in functions(program) {
+        out.push_str(&format!("\n## `{}`\n", f.signature()));
+        if let Some(doc) = &f.doc {
+            out.push_str(&format!("\n{}\n", doc));
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn html(title: &str, program: &[Stmt]) -> String {
+    let title = escape(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    for f in functions(program) {
+        out.push_str(&format!("<h2 id=\"{}\"><code>{}</code></h2>\n", escape(&f.name), escape(&f.signature())));
+        if let Some(doc) = &f.doc {
+            for paragraph in doc.split("\n\n") {
+                out.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
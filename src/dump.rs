@@ -0,0 +1,260 @@
+use crate::lexer::SpannedToken;
+use crate::parser::{Expr, Stmt, StmtKind};
+use crate::symbol;
+
+pub fn tokens(tokens: &[SpannedToken]) -> String {
+    let mut out = String::new();
+    for t in tokens {
+        let pos = format!("{}:{}", t.span.line, t.span.col);
+        let range = format!("{}..{}", t.span.start, t.span.end);
+        out.push_str(&format!("{:<8} {:<10} {:?}\n", pos, range, t.token));
+    }
+    out
+}
+
+pub fn ast_tree(program: &[Stmt]) -> String {
+    let mut out = String::new();
+    for stmt in program {
+        tree_stmt(&mut out, stmt, 0);
+    }
+    out
+}
+
+pub fn ast_sexp(program: &[Stmt]) -> String {
+    let mut out = String::new();
+    for stmt in program {
+        out.push_str(&sexp_stmt(stmt));
+        out.push('\n');
+    }
+    out
+}
+
+fn line(out: &mut String, depth: usize, text: &str) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+    out.push_str(text);
+    out.push('\n');
+}
+
+// The names of a `let [...]`, with the rest binding last.
+fn pattern(names: &[symbol::Symbol], rest: &Option<symbol::Symbol>, separator: &str) -> String {
+    let mut parts: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+    if let Some(rest) = rest {
+        parts.push(format!("...{}", rest));
+    }
+    parts.join(separator)
+}
+
+fn jump(keyword: &str, label: &Option<symbol::Symbol>) -> String {
+    match label {
+        Some(label) => format!("{} {}", keyword, label),
+        None => keyword.to_string(),
+    }
+}
+
+fn tree_block(out: &mut String, label: &str, stmts: &[Stmt], depth: usize) {
+    line(out, depth, label);
+    for stmt in stmts {
+        tree_stmt(out, stmt, depth + 1);
+    }
+}
+
+fn tree_stmt(out: &mut String, stmt: &Stmt, depth: usize) {
+    match &stmt.kind {
+        StmtKind::Let(name, expr) => {
+            line(out, depth, &format!("Let {}", name));
+            tree_expr(out, expr, depth + 1);
+        }
+        StmtKind::LetArray(names, rest, expr) => {
+            line(out, depth, &format!("LetArray [{}]", pattern(names, rest, ", ")));
+            tree_expr(out, expr, depth + 1);
+        }
+        StmtKind::Assign(name, expr) => {
+            line(out, depth, &format!("Assign {}", name));
+            tree_expr(out, expr, depth + 1);
+        }
+        StmtKind::MultiAssign(names, values) => {
+            line(out, depth, &format!("MultiAssign {}", symbol::join(names, ", ")));
+            for value in values {
+                tree_expr(out, value, depth + 1);
+            }
+        }
+        StmtKind::IndexAssign(name, index, value) => {
+            line(out, depth, &format!("IndexAssign {}", name));
+            tree_expr(out, index, depth + 1);
+            tree_expr(out, value, depth + 1);
+        }
+        StmtKind::If(cond, body, else_body) => {
+            line(out, depth, "If");
+            tree_expr(out, cond, depth + 1);
+            tree_block(out, "Then", body, depth + 1);
+            if let Some(else_b) = else_body {
+                tree_block(out, "Else", else_b, depth + 1);
+            }
+        }
+        StmtKind::While(cond, body) => {
+            line(out, depth, "While");
+            tree_expr(out, cond, depth + 1);
+            tree_block(out, "Body", body, depth + 1);
+        }
+        StmtKind::For(var, start, end, body) => {
+            line(out, depth, &format!("For {}", var));
+            tree_expr(out, start, depth + 1);
+            tree_expr(out, end, depth + 1);
+            tree_block(out, "Body", body, depth + 1);
+        }
+        StmtKind::ForEach(var, iterable, body) => {
+            line(out, depth, &format!("ForEach {}", var));
+            tree_expr(out, iterable, depth + 1);
+            tree_block(out, "Body", body, depth + 1);
+        }
+        StmtKind::Fn(name, params, body) => {
+            line(out, depth, &format!("Fn {}({})", name, symbol::join(params, ", ")));
+            for stmt in body.iter() {
+                tree_stmt(out, stmt, depth + 1);
+            }
+        }
+        StmtKind::Labeled(label, inner) => {
+            line(out, depth, &format!("Labeled {}", label));
+            tree_stmt(out, inner, depth + 1);
+        }
+        StmtKind::Break(label) => line(out, depth, &jump("Break", label)),
+        StmtKind::Continue(label) => line(out, depth, &jump("Continue", label)),
+        StmtKind::Global(names) => line(out, depth, &format!("Global {}", symbol::join(names, ", "))),
+        StmtKind::Return(expr) => {
+            line(out, depth, "Return");
+            if let Some(e) = expr {
+                tree_expr(out, e, depth + 1);
+            }
+        }
+        StmtKind::ExprStmt(expr) => {
+            line(out, depth, "ExprStmt");
+            tree_expr(out, expr, depth + 1);
+        }
+    }
+}
+
+fn tree_expr(out: &mut String, expr: &Expr, depth: usize) {
+    match expr {
+        Expr::Number(n) => line(out, depth, &format!("Number {}", n)),
+        Expr::StringLit(s) => line(out, depth, &format!("String {:?}", s)),
+        Expr::Bool(b) => line(out, depth, &format!("Bool {}", b)),
+        Expr::Ident(name) => line(out, depth, &format!("Ident {}", name)),
+        Expr::Array(elems) => {
+            line(out, depth, "Array");
+            for e in elems {
+                tree_expr(out, e, depth + 1);
+            }
+        }
+        Expr::Index(arr, idx) => {
+            line(out, depth, "Index");
+            tree_expr(out, arr, depth + 1);
+            tree_expr(out, idx, depth + 1);
+        }
+        Expr::Call(func, args) => {
+            line(out, depth, "Call");
+            tree_expr(out, func, depth + 1);
+            for a in args {
+                tree_expr(out, a, depth + 1);
+            }
+        }
+        Expr::Unary(op, operand) => {
+            line(out, depth, &format!("Unary {}", op.symbol()));
+            tree_expr(out, operand, depth + 1);
+        }
+        Expr::Spread(operand) => {
+            line(out, depth, "Spread");
+            tree_expr(out, operand, depth + 1);
+        }
+        Expr::Try(operand) => {
+            line(out, depth, "Try");
+            tree_expr(out, operand, depth + 1);
+        }
+        Expr::Binary(left, op, right) => {
+            line(out, depth, &format!("Binary {}", op.symbol()));
+            tree_expr(out, left, depth + 1);
+            tree_expr(out, right, depth + 1);
+        }
+    }
+}
+
+fn sexp_block(stmts: &[Stmt]) -> String {
+    let mut parts = vec!["block".to_string()];
+    parts.extend(stmts.iter().map(sexp_stmt));
+    format!("({})", parts.join(" "))
+}
+
+fn sexp_stmt(stmt: &Stmt) -> String {
+    match &stmt.kind {
+        StmtKind::Let(name, expr) => format!("(let {} {})", name, sexp_expr(expr)),
+        StmtKind::LetArray(names, rest, expr) => format!("(let [{}] {})", pattern(names, rest, " "), sexp_expr(expr)),
+        StmtKind::Assign(name, expr) => format!("(set {} {})", name, sexp_expr(expr)),
+        StmtKind::MultiAssign(names, values) => {
+            let values: Vec<String> = values.iter().map(sexp_expr).collect();
+            format!("(set ({}) ({}))", symbol::join(names, " "), values.join(" "))
+        }
+        StmtKind::IndexAssign(name, index, value) => {
+            format!("(set-index {} {} {})", name, sexp_expr(index), sexp_expr(value))
+        }
+        StmtKind::If(cond, body, else_body) => match else_body {
+            Some(else_b) => format!(
+                "(if {} {} {})",
+                sexp_expr(cond),
+                sexp_block(body),
+                sexp_block(else_b)
+            ),
+            None => format!("(if {} {})", sexp_expr(cond), sexp_block(body)),
+        },
+        StmtKind::While(cond, body) => format!("(while {} {})", sexp_expr(cond), sexp_block(body)),
+        StmtKind::For(var, start, end, body) => format!(
+            "(for {} {} {} {})",
+            var,
+            sexp_expr(start),
+            sexp_expr(end),
+            sexp_block(body)
+        ),
+        StmtKind::ForEach(var, iterable, body) => {
+            format!("(foreach {} {} {})", var, sexp_expr(iterable), sexp_block(body))
+        }
+        StmtKind::Fn(name, params, body) => {
+            format!("(fn {} ({}) {})", name, symbol::join(params, " "), sexp_block(body))
+        }
+        StmtKind::Return(Some(e)) => format!("(return {})", sexp_expr(e)),
+        StmtKind::Return(None) => "(return)".to_string(),
+        StmtKind::Global(names) => format!("(global {})", symbol::join(names, " ")),
+        StmtKind::Labeled(label, inner) => format!("(label {} {})", label, sexp_stmt(inner)),
+        StmtKind::Break(label) => format!("({})", jump("break", label)),
+        StmtKind::Continue(label) => format!("({})", jump("continue", label)),
+        StmtKind::ExprStmt(expr) => sexp_expr(expr),
+    }
+}
+
+fn sexp_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => format!("{}", n),
+        Expr::StringLit(s) => format!("{:?}", s),
+        Expr::Bool(b) => format!("{}", b),
+        Expr::Ident(name) => name.to_string(),
+        Expr::Array(elems) => {
+            let mut parts = vec!["array".to_string()];
+            parts.extend(elems.iter().map(sexp_expr));
+            format!("({})", parts.join(" "))
+        }
+        Expr::Index(arr, idx) => format!("(index {} {})", sexp_expr(arr), sexp_expr(idx)),
+        Expr::Call(func, args) => {
+            let mut parts = vec!["call".to_string(), sexp_expr(func)];
+            parts.extend(args.iter().map(sexp_expr));
+            format!("({})", parts.join(" "))
+        }
+        Expr::Unary(op, operand) => format!("({} {})", op.symbol(), sexp_expr(operand)),
+        Expr::Spread(operand) => format!("(... {})", sexp_expr(operand)),
+        Expr::Try(operand) => format!("(? {})", sexp_expr(operand)),
+        Expr::Binary(left, op, right) => format!(
+            "({} {} {})",
+            op.symbol(),
+            sexp_expr(left),
+            sexp_expr(right)
+        ),
+    }
+}
@@ -0,0 +1,159 @@
+use crate::lexer::{Lexer, SpannedToken, Token};
+use crate::parser::parse_source;
+
+const INDENT: &str = "  ";
+
+struct Line {
+    tokens: Vec<(Token, String)>,
+    blank_before: bool,
+}
+
+// Reprints a program with canonical indentation, spacing and brace placement.
+// Works on the token stream so comments and the original line structure
+// survive; the source is parsed first so only valid programs are touched.
+pub fn format_source(source: &str) -> Result<String, String> {
+    parse_source(source)?;
+
+    let tokens = Lexer::with_comments(source).tokenize_spanned()?;
+    let lines = group_lines(source, tokens);
+
+    let mut out = String::new();
+    let mut depth: usize = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if line.blank_before && i > 0 {
+            out.push('\n');
+        }
+        let closers = line
+            .tokens
+            .iter()
+            .take_while(|(t, _)| is_closer(t))
+            .count();
+        for _ in 0..depth.saturating_sub(closers) {
+            out.push_str(INDENT);
+        }
+        out.push_str(&render_line(&line.tokens));
+        out.push('\n');
+
+        for (t, _) in &line.tokens {
+            if is_opener(t) {
+                depth += 1;
+            } else if is_closer(t) {
+                depth = depth.saturating_sub(1);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn group_lines(source: &str, tokens: Vec<SpannedToken>) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut prev_end_line = 0;
+
+    for t in tokens {
+        if t.token == Token::Eof {
+            break;
+        }
+        let text = source[t.span.start..t.span.end].trim_end().to_string();
+        let end_line = t.span.line + text.matches('\n').count();
+
+        let starts_line = lines.is_empty() || t.span.line > prev_end_line;
+        // Opening braces and `else` always stay on the line they continue.
+        let attaches = matches!(t.token, Token::LBrace | Token::Else)
+            && lines
+                .last()
+                .and_then(|l| l.tokens.last())
+                .is_some_and(|(last, _)| !matches!(last, Token::Comment(_)));
+
+        if starts_line && !attaches {
+            lines.push(Line {
+                tokens: Vec::new(),
+                blank_before: t.span.line > prev_end_line + 1,
+            });
+        }
+        lines.last_mut().unwrap().tokens.push((t.token, text));
+        prev_end_line = end_line;
+    }
+    lines
+}
+
+fn render_line(tokens: &[(Token, String)]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<&Token> = None;
+    let mut prev_unary = false;
+
+    for (token, text) in tokens {
+        if let Some(p) = prev {
+            if matches!(token, Token::Comment(_)) {
+                out.push_str("  ");
+            } else if needs_space(p, token, prev_unary) {
+                out.push(' ');
+            }
+        }
+        out.push_str(text);
+        prev_unary = *token == Token::Minus && is_unary_position(prev);
+        prev = Some(token);
+    }
+    out
+}
+
+fn needs_space(prev: &Token, next: &Token, prev_unary: bool) -> bool {
+    if prev_unary {
+        return false;
+    }
+    !matches!(
+        (prev, next),
+        (Token::LParen | Token::LBracket, _)
+            | (_, Token::RParen | Token::RBracket | Token::Comma | Token::Colon | Token::Question)
+            | (Token::DotDot | Token::Ellipsis, _)
+            | (_, Token::DotDot)
+            | (Token::LBrace, Token::RBrace)
+            | (
+                Token::Ident(_) | Token::RParen | Token::RBracket | Token::StringLit(_),
+                Token::LParen | Token::LBracket,
+            )
+    )
+}
+
+fn is_unary_position(prev: Option<&Token>) -> bool {
+    match prev {
+        None => true,
+        Some(t) => matches!(
+            t,
+            Token::Plus
+                | Token::Minus
+                | Token::Star
+                | Token::Slash
+                | Token::Percent
+                | Token::Eq
+                | Token::EqEq
+                | Token::BangEq
+                | Token::EqEqEq
+                | Token::BangEqEq
+                | Token::Lt
+                | Token::LtEq
+                | Token::Gt
+                | Token::GtEq
+                | Token::DotDot
+                | Token::LParen
+                | Token::LBracket
+                | Token::LBrace
+                | Token::Comma
+                | Token::Return
+                | Token::If
+                | Token::While
+                | Token::In
+                | Token::And
+                | Token::Or
+                | Token::Not
+                | Token::Is
+        ),
+    }
+}
+
+fn is_opener(t: &Token) -> bool {
+    matches!(t, Token::LBrace | Token::LParen | Token::LBracket)
+}
+
+fn is_closer(t: &Token) -> bool {
+    matches!(t, Token::RBrace | Token::RParen | Token::RBracket)
+}
@@ -0,0 +1,69 @@
+use crate::lexer::{Lexer, Span, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Keyword,
+    String,
+    Number,
+    Identifier,
+    Comment,
+    Operator,
+    Punctuation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub class: TokenClass,
+    pub span: Span,
+}
+
+pub fn classify(token: &Token) -> Option<TokenClass> {
+    let class = match token {
+        Token::Number(_) => TokenClass::Number,
+        Token::StringLit(_) => TokenClass::String,
+        Token::Ident(_) => TokenClass::Identifier,
+        Token::Comment(_) | Token::DocComment(_) => TokenClass::Comment,
+        Token::Let
+        | Token::Fn
+        | Token::If
+        | Token::Else
+        | Token::While
+        | Token::For
+        | Token::In
+        | Token::Return
+        | Token::Global
+        | Token::Break
+        | Token::Continue
+        | Token::True
+        | Token::False
+        | Token::And
+        | Token::Or
+        | Token::Not
+        | Token::Is => TokenClass::Keyword,
+        Token::LParen
+        | Token::RParen
+        | Token::LBrace
+        | Token::RBrace
+        | Token::LBracket
+        | Token::RBracket
+        | Token::Comma => TokenClass::Punctuation,
+        Token::Eof => return None,
+        _ => TokenClass::Operator,
+    };
+    Some(class)
+}
+
+// Classified tokens in source order, comments included. Spans use the
+// lexer's byte offsets, so `&source[start..end]` is the token's text.
+pub fn highlight(source: &str) -> Result<Vec<Highlight>, String> {
+    let tokens = Lexer::with_comments(source).tokenize_spanned()?;
+    Ok(tokens
+        .iter()
+        .filter_map(|t| {
+            classify(&t.token).map(|class| Highlight {
+                class,
+                span: t.span,
+            })
+        })
+        .collect())
+}
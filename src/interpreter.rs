@@ -1,64 +1,801 @@
-use std::collections::HashMap;
+pub mod builtins;
 
-use crate::parser::{BinOp, Expr, Stmt, UnaryOp};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use builtins::Builtin;
+use crate::lexer::KEYWORDS;
+use crate::locale::NumberLocale;
+use crate::parser::{BinOp, Expr, Stmt, StmtKind, UnaryOp};
+use crate::profile::Profile;
+use crate::program::Program;
+use crate::state::InterpreterState;
+use crate::suggest::did_you_mean;
+use crate::symbol::Symbol;
+
+// Leaves room for the host within the 2 MiB stack of a spawned thread.
+pub const DEFAULT_STACK_LIMIT: usize = 1 << 20;
+
+// Values may be shared between threads, so arrays and iterators sit behind
+// a mutex. Nothing panics while holding one, but recover from poisoning anyway.
+pub(crate) fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Line width pprint() tries to stay within.
+const PPRINT_WIDTH: usize = 80;
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Number(f64),
     Str(String),
     Bool(bool),
-    Array(Vec<Value>),
+    // Arrays are references: copies of the value alias the same elements.
+    Array(Arc<Mutex<Elements>>),
     Function {
-        params: Vec<String>,
-        body: Vec<Stmt>,
+        params: Vec<Symbol>,
+        body: Arc<[Stmt]>,
     },
+    // Made by error(msg); `expr?` returns it from the enclosing function.
+    Error(String),
+    // Made by iter() and the lazy adapters; shared, and used up as it is read.
+    Iterator(Arc<Mutex<LazyIter>>),
     Null,
 }
 
-impl std::fmt::Display for Value {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+// The elements of an array. freeze() sets `frozen`, after which push()
+// and index assignment fail; it derefs to the Vec for everything else.
+#[derive(Debug, Clone, Default)]
+pub struct Elements {
+    pub items: Vec<Value>,
+    pub frozen: bool,
+}
+
+impl std::ops::Deref for Elements {
+    type Target = Vec<Value>;
+
+    fn deref(&self) -> &Vec<Value> {
+        &self.items
+    }
+}
+
+impl std::ops::DerefMut for Elements {
+    fn deref_mut(&mut self) -> &mut Vec<Value> {
+        &mut self.items
+    }
+}
+
+// A lazy sequence: a source, or an adapter that pulls from another iterator
+// only when it is asked for its next element.
+#[derive(Debug)]
+pub enum LazyIter {
+    // Reads the array as it is at each step, so pushes during the loop show up.
+    Array(Arc<Mutex<Elements>>, usize),
+    // The string and the byte offset of the next character.
+    Chars(String, usize),
+    // The iterator protocol: a function called until it returns null.
+    Function(Value),
+    Take(Arc<Mutex<LazyIter>>, usize),
+    Skip(Arc<Mutex<LazyIter>>, usize),
+    Map(Arc<Mutex<LazyIter>>, Value),
+}
+
+impl Value {
+    pub fn array(items: Vec<Value>) -> Value {
+        Value::Array(Arc::new(Mutex::new(Elements { items, frozen: false })))
+    }
+
+    // Makes this array and every array inside it read-only, for sharing
+    // values with scripts that must not change them. Returns the value.
+    pub fn freeze(&self) -> Value {
+        if let Value::Array(elems) = self {
+            let items = {
+                let mut elems = lock(elems);
+                if elems.frozen {
+                    return self.clone();
+                }
+                elems.frozen = true;
+                elems.to_vec()
+            };
+            for item in &items {
+                item.freeze();
+            }
+        }
+        self.clone()
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, Value::Array(elems) if lock(elems).frozen)
+    }
+
+    // A copy that shares no array or iterator with the original.
+    pub fn deep_copy(&self) -> Value {
+        self.copy_with(&mut HashMap::new())
+    }
+
+    // `copies` maps each array or iterator already copied to its copy, so
+    // values that alias one another (or themselves) still do afterwards.
+    pub(crate) fn copy_with(&self, copies: &mut HashMap<usize, Value>) -> Value {
         match self {
-            Value::Number(n) => {
-                if *n == (*n as i64 as f64) {
-                    write!(f, "{}", *n as i64)
-                } else {
-                    write!(f, "{}", n)
+            Value::Array(elems) => {
+                let key = Arc::as_ptr(elems) as usize;
+                if let Some(copy) = copies.get(&key) {
+                    return copy.clone();
                 }
+                let copy = Arc::new(Mutex::new(Elements::default()));
+                copies.insert(key, Value::Array(copy.clone()));
+                let Elements { items, frozen } = lock(elems).clone();
+                let items = items.iter().map(|v| v.copy_with(copies)).collect();
+                *lock(&copy) = Elements { items, frozen };
+                Value::Array(copy)
             }
-            Value::Str(s) => write!(f, "{}", s),
-            Value::Bool(b) => write!(f, "{}", b),
+            Value::Iterator(it) => {
+                let key = Arc::as_ptr(it) as usize;
+                if let Some(copy) = copies.get(&key) {
+                    return copy.clone();
+                }
+                let copy = Arc::new(Mutex::new(LazyIter::Chars(String::new(), 0)));
+                copies.insert(key, Value::Iterator(copy.clone()));
+                let state = match &*lock(it) {
+                    LazyIter::Array(elems, pos) => LazyIter::Array(elems.clone(), *pos),
+                    LazyIter::Chars(s, pos) => LazyIter::Chars(s.clone(), *pos),
+                    LazyIter::Function(f) => LazyIter::Function(f.clone()),
+                    LazyIter::Take(inner, n) => LazyIter::Take(inner.clone(), *n),
+                    LazyIter::Skip(inner, n) => LazyIter::Skip(inner.clone(), *n),
+                    LazyIter::Map(inner, f) => LazyIter::Map(inner.clone(), f.clone()),
+                };
+                let copy_array = |elems, copies: &mut HashMap<usize, Value>| match Value::Array(elems).copy_with(copies) {
+                    Value::Array(elems) => elems,
+                    _ => unreachable!(),
+                };
+                let copy_iter = |inner, copies: &mut HashMap<usize, Value>| match Value::Iterator(inner).copy_with(copies) {
+                    Value::Iterator(inner) => inner,
+                    _ => unreachable!(),
+                };
+                *lock(&copy) = match state {
+                    LazyIter::Array(elems, pos) => LazyIter::Array(copy_array(elems, copies), pos),
+                    LazyIter::Take(inner, n) => LazyIter::Take(copy_iter(inner, copies), n),
+                    LazyIter::Skip(inner, n) => LazyIter::Skip(copy_iter(inner, copies), n),
+                    LazyIter::Map(inner, f) => LazyIter::Map(copy_iter(inner, copies), f),
+                    other => other,
+                };
+                Value::Iterator(copy)
+            }
+            v => v.clone(),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Array(_) => "array",
+            Value::Function { .. } => "function",
+            Value::Error(_) => "error",
+            Value::Iterator(_) => "iterator",
+            Value::Null => "null",
+        }
+    }
+
+    // Writes the value, showing an array that contains itself as `[...]`.
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, open: &mut Vec<*const Mutex<Elements>>) -> std::fmt::Result {
+        match self {
             Value::Array(elems) => {
+                if open.contains(&Arc::as_ptr(elems)) {
+                    return write!(f, "[...]");
+                }
+                open.push(Arc::as_ptr(elems));
                 write!(f, "[")?;
-                for (i, v) in elems.iter().enumerate() {
+                for (i, v) in lock(elems).iter().enumerate() {
                     if i > 0 {
                         write!(f, ", ")?;
                     }
-                    write!(f, "{}", v)?;
+                    match v {
+                        Value::Str(_) | Value::Error(_) => write!(f, "{}", v.repr())?,
+                        v => v.write(f, open)?,
+                    }
                 }
+                open.pop();
                 write!(f, "]")
             }
+            _ => write!(f, "{}", self),
+        }
+    }
+
+    // repr() on one line when it fits in `width` columns after `indent`,
+    // otherwise one array element per line, indented by two spaces a level.
+    pub fn pretty(&self, indent: usize, width: usize) -> String {
+        self.pretty_nested(indent, width, &mut Vec::new())
+    }
+
+    fn pretty_nested(&self, indent: usize, width: usize, open: &mut Vec<*const Mutex<Elements>>) -> String {
+        let flat = self.repr();
+        let Value::Array(elems) = self else {
+            return flat;
+        };
+        if indent + flat.chars().count() <= width || lock(elems).is_empty() {
+            return flat;
+        }
+        if open.contains(&Arc::as_ptr(elems)) {
+            return "[...]".to_string();
+        }
+        open.push(Arc::as_ptr(elems));
+        let pad = " ".repeat(indent + 2);
+        // A copy: an element may be this same array, and repr() locks it.
+        let items = lock(elems).clone();
+        let lines: Vec<String> =
+            items.iter().map(|v| format!("{}{}", pad, v.pretty_nested(indent + 2, width, open))).collect();
+        open.pop();
+        format!("[\n{}\n{}]", lines.join(",\n"), " ".repeat(indent))
+    }
+
+    // Like Display, but strings are quoted, so `"1"` and `1` look different.
+    // Strings inside arrays are always shown this way.
+    pub fn repr(&self) -> String {
+        match self {
+            Value::Str(s) => format!("{:?}", s),
+            Value::Error(message) => format!("error({:?})", message),
+            v => v.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Shortest digits that read back as the same number; integers
+            // print without a fraction and very large or small magnitudes
+            // in exponent form, as in JavaScript.
+            Value::Number(n) if *n == 0.0 => write!(f, "0"),
+            Value::Number(n) if n.is_finite() && (n.abs() >= 1e21 || n.abs() < 1e-6) => write!(f, "{:e}", n),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Array(_) => self.write(f, &mut Vec::new()),
             Value::Function { .. } => write!(f, "<function>"),
+            Value::Error(message) => write!(f, "error: {}", message),
+            Value::Iterator(_) => write!(f, "<iterator>"),
             Value::Null => write!(f, "null"),
         }
     }
 }
 
+// Where print() sends its lines. `Capture` collects them in
+// `Interpreter::output`, which is the default.
+pub enum Output {
+    Capture,
+    Stdout,
+    Writer(Box<dyn Write + Send>),
+}
+
+// Capabilities a script may use. Builtins that touch the file system,
+// spawn or end processes, use the network, read the environment, read
+// input or run code given as a string check the matching flag; everything
+// else is always available.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterpreterConfig {
+    pub fs: bool,
+    pub process: bool,
+    pub net: bool,
+    pub env: bool,
+    // input(), which reads the process's stdin unless the host has
+    // installed another InputSource.
+    pub stdin: bool,
+    pub eval: bool,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        InterpreterConfig {
+            fs: true,
+            process: true,
+            net: true,
+            env: true,
+            stdin: true,
+            eval: true,
+        }
+    }
+}
+
+impl InterpreterConfig {
+    // Only pure builtins: for running untrusted scripts.
+    pub fn sandboxed() -> Self {
+        InterpreterConfig {
+            fs: false,
+            process: false,
+            net: false,
+            env: false,
+            stdin: false,
+            eval: false,
+        }
+    }
+
+    pub fn allows(&self, capability: &str) -> bool {
+        match capability {
+            "fs" => self.fs,
+            "process" => self.process,
+            "net" => self.net,
+            "env" => self.env,
+            "stdin" => self.stdin,
+            "eval" => self.eval,
+            _ => false,
+        }
+    }
+}
+
+// Where input() reads its lines from. Returns None at end of input.
+pub trait InputSource: Send {
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+}
+
+pub struct StdinInput;
+
+impl InputSource for StdinInput {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(Some(line))
+    }
+}
+
+// Scripted input, one entry per line.
+impl InputSource for VecDeque<String> {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.pop_front())
+    }
+}
+
+// Called before every statement while installed with
+// `Interpreter::set_debug_hook`; an error aborts the run.
+pub trait DebugHook: Send {
+    fn before_stmt(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), String>;
+}
+
+// A call to a builtin that needs a capability, recorded while auditing is
+// enabled with `Interpreter::enable_audit`.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub builtin: &'static str,
+    // Copies of the argument values, recorded as they are evaluated; empty
+    // for a refused call, whose arguments are never evaluated.
+    pub args: Vec<Value>,
+    pub line: usize,
+    // False when the capability was disabled and the call failed.
+    pub allowed: bool,
+}
+
+impl std::fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args: Vec<String> = self.args.iter().map(Value::repr).collect();
+        write!(f, "line {}: {}({})", self.line, self.builtin, args.join(", "))?;
+        if !self.allowed {
+            write!(f, " [denied]")?;
+        }
+        Ok(())
+    }
+}
+
+// Host function exposed to scripts through `Interpreter::register_fn`.
+pub type NativeFn = Box<dyn Fn(&[Value]) -> Result<Value, String> + Send>;
+
 enum Signal {
     None,
     Return(Value),
+    // The label of the targeted loop, or None for the innermost one.
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
+}
+
+// What a loop does with the signal its body ended with.
+enum Flow {
+    Next,
+    Exit,
+    Propagate(Signal),
+}
+
+impl Signal {
+    fn flow(self, label: Option<Symbol>) -> Flow {
+        match self {
+            Signal::None => Flow::Next,
+            Signal::Continue(target) if target.is_none() || target == label => Flow::Next,
+            Signal::Break(target) if target.is_none() || target == label => Flow::Exit,
+            other => Flow::Propagate(other),
+        }
+    }
+}
+
+// A function call in progress: the index of its first scope, and the names
+// it has declared `global`.
+struct Frame {
+    base: usize,
+    globals: Vec<Symbol>,
 }
 
 pub struct Interpreter {
-    scopes: Vec<HashMap<String, Value>>,
+    scopes: Vec<HashMap<Symbol, Value>>,
+    frames: Vec<Frame>,
+    // Set by `?` while an error value unwinds to its function's caller.
+    returning: Option<Value>,
+    // Set when a limit or the debug hook stops the run, so that eval()
+    // passes the error on instead of returning it as a value.
+    aborting: bool,
     pub output: Vec<String>,
+    locale: NumberLocale,
+    script_args: Vec<String>,
+    exit_code: Option<i32>,
+    natives: HashMap<Symbol, NativeFn>,
+    builtins: HashMap<Symbol, Builtin>,
+    sink: Output,
+    input: Box<dyn InputSource>,
+    step_limit: Option<u64>,
+    steps: u64,
+    depth: usize,
+    stack_base: usize,
+    stack_limit: usize,
+    memory_limit: Option<usize>,
+    deadline: Option<Instant>,
+    config: InterpreterConfig,
+    debug_hook: Option<Box<dyn DebugHook>>,
+    profile: Option<Profile>,
+    audit: Option<Vec<AuditEntry>>,
+    // Indexes into `audit` of the audited builtins still running; the
+    // innermost one gets the arguments evaluated through `eval_audited`.
+    audit_open: Vec<usize>,
+    strict_math: bool,
+    strict_equality: bool,
+    strict_vars: bool,
+    // Line of the statement being run, for errors that name it.
+    line: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         Interpreter {
             scopes: vec![HashMap::new()],
+            frames: Vec::new(),
+            returning: None,
+            aborting: false,
             output: Vec::new(),
+            locale: NumberLocale::default(),
+            script_args: Vec::new(),
+            exit_code: None,
+            natives: HashMap::new(),
+            builtins: builtins::STANDARD.iter().map(|b| (Symbol::intern(b.name), *b)).collect(),
+            sink: Output::Capture,
+            input: Box::new(StdinInput),
+            step_limit: None,
+            steps: 0,
+            depth: 0,
+            stack_base: 0,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            memory_limit: None,
+            deadline: None,
+            config: InterpreterConfig::default(),
+            debug_hook: None,
+            profile: None,
+            audit: None,
+            audit_open: Vec::new(),
+            strict_math: false,
+            strict_equality: false,
+            strict_vars: false,
+            line: 0,
+        }
+    }
+
+    // An interpreter whose scripts start with these global bindings.
+    pub fn with_globals<K: Into<String>>(globals: impl IntoIterator<Item = (K, Value)>) -> Self {
+        let mut interp = Interpreter::new();
+        for (name, value) in globals {
+            interp.scopes[0].insert(Symbol::intern(&name.into()), value);
         }
+        interp
+    }
+
+    pub fn set_locale(&mut self, locale: NumberLocale) {
+        self.locale = locale;
+    }
+
+    pub fn set_output(&mut self, sink: Output) {
+        self.sink = sink;
+    }
+
+    pub fn set_input<I: InputSource + 'static>(&mut self, input: I) {
+        self.input = Box::new(input);
+    }
+
+    pub fn set_config(&mut self, config: InterpreterConfig) {
+        self.config = config;
+    }
+
+    pub fn set_debug_hook(&mut self, hook: Option<Box<dyn DebugHook>>) {
+        self.debug_hook = hook;
+    }
+
+    // Starts recording call counts and timings of minilang functions.
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    pub fn enable_audit(&mut self) {
+        self.audit = Some(Vec::new());
+    }
+
+    // Every capability-gated builtin call so far, allowed or not.
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        self.audit.as_deref().unwrap_or_default()
+    }
+
+    fn require(&self, enabled: bool, capability: &str, builtin: &str) -> Result<(), String> {
+        if enabled {
+            Ok(())
+        } else {
+            Err(format!("{}() is unavailable: capability '{}' is disabled", builtin, capability))
+        }
+    }
+
+    // Division or modulo by zero and arithmetic yielding NaN become errors
+    // instead of inf/NaN values.
+    pub fn set_strict_math(&mut self, strict: bool) {
+        self.strict_math = strict;
+    }
+
+    // `==` and `!=` between values of different types, other than null, become
+    // errors; `===` and `!==` still compare them.
+    pub fn set_strict_equality(&mut self, strict: bool) {
+        self.strict_equality = strict;
+    }
+
+    // Assigning a name that no enclosing scope has declared with `let`
+    // becomes an error instead of creating the variable.
+    pub fn set_strict_vars(&mut self, strict: bool) {
+        self.strict_vars = strict;
+    }
+
+    // Caps the statements and expressions a single run() may evaluate.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = Some(limit);
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if let Some(limit) = self.step_limit
+            && self.steps > limit
+        {
+            return self.abort("Step limit exceeded");
+        }
+        // Reading the clock on every step is measurably slow.
+        if self.steps.is_multiple_of(1024)
+            && let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            return self.abort("Timeout: execution took too long");
+        }
+        Ok(())
+    }
+
+    // Caps the host stack a run may use for nested statements, expressions
+    // and calls, so deep recursion fails with an error instead of crashing.
+    pub fn set_stack_limit(&mut self, bytes: usize) {
+        self.stack_limit = bytes;
+    }
+
+    // Measures host stack use directly, since frame sizes vary a lot
+    // between debug and release builds.
+    fn enter(&mut self) -> Result<(), String> {
+        let marker = 0u8;
+        let here = &marker as *const u8 as usize;
+        if self.depth == 0 {
+            self.stack_base = here;
+        } else if self.stack_base.abs_diff(here) > self.stack_limit {
+            return self.abort("Maximum recursion depth exceeded");
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // Caps the approximate size in bytes of any single string or array a
+    // script builds.
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.memory_limit = Some(bytes);
+    }
+
+    fn check_alloc(&mut self, size: impl FnOnce() -> usize) -> Result<(), String> {
+        match self.memory_limit {
+            Some(limit) if size() > limit => self.abort("Memory limit exceeded"),
+            _ => Ok(()),
+        }
+    }
+
+    fn abort<T>(&mut self, message: &str) -> Result<T, String> {
+        self.aborting = true;
+        Err(message.to_string())
+    }
+
+    fn add(&mut self, lv: Value, rv: Value) -> Result<Value, String> {
+        match (lv, rv) {
+            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+            (Value::Str(a), Value::Str(b)) => {
+                self.check_alloc(|| a.len() + b.len())?;
+                Ok(Value::Str(a + &b))
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                // `a` and `b` may be the same array, so lock them one at a time.
+                let mut items = lock(&a).to_vec();
+                self.check_alloc(|| Self::heap_size(&items) + Self::heap_size(&lock(&b)))?;
+                items.extend(lock(&b).iter().cloned());
+                Ok(Value::array(items))
+            }
+            _ => Err("'+' requires two numbers, two strings, or two arrays".to_string()),
+        }
+    }
+
+    fn has_call(expr: &Expr) -> bool {
+        match expr {
+            Expr::Number(_) | Expr::StringLit(_) | Expr::Bool(_) | Expr::Ident(_) => false,
+            Expr::Call(..) => true,
+            Expr::Array(elems) => elems.iter().any(Self::has_call),
+            Expr::Index(a, b) | Expr::Binary(a, _, b) => Self::has_call(a) || Self::has_call(b),
+            Expr::Spread(e) | Expr::Try(e) | Expr::Unary(_, e) => Self::has_call(e),
+        }
+    }
+
+    // Nested arrays are shared rather than copied, so only their handle counts.
+    fn heap_size(elems: &[Value]) -> usize {
+        elems
+            .iter()
+            .map(|v| {
+                std::mem::size_of::<Value>()
+                    + match v {
+                        Value::Str(s) | Value::Error(s) => s.len(),
+                        _ => 0,
+                    }
+            })
+            .sum()
+    }
+
+    fn emit(&mut self, line: String) -> Result<(), String> {
+        let result = match &mut self.sink {
+            Output::Capture => {
+                self.output.push(line);
+                Ok(())
+            }
+            Output::Stdout => writeln!(std::io::stdout(), "{}", line),
+            Output::Writer(w) => writeln!(w, "{}", line),
+        };
+        result.map_err(|e| format!("Output error: {}", e))
+    }
+
+    // Makes `name(...)` call `f` with the evaluated arguments. Registered
+    // functions take precedence over the builtins of the same name.
+    pub fn register_fn<F>(&mut self, name: &str, f: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + Send + 'static,
+    {
+        self.natives.insert(Symbol::intern(name), Box::new(f));
+    }
+
+    // Command-line arguments returned by args().
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.script_args = args;
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.keys())
+            .chain(self.natives.keys())
+            .map(|name| name.to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn define_global(&mut self, name: &str, val: Value) {
+        self.scopes[0].insert(Symbol::intern(name), val);
+    }
+
+    // Global bindings sorted by name.
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        let mut vars: Vec<(String, Value)> = self.scopes[0]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        vars
+    }
+
+    // The bindings of the running function call (or of the top level),
+    // with inner scopes hiding outer ones, sorted by name.
+    fn locals(&self) -> Vec<(String, Value)> {
+        let base = self.frames.last().map_or(0, |f| f.base);
+        let mut vars: HashMap<Symbol, Value> = HashMap::new();
+        for scope in &self.scopes[base..] {
+            vars.extend(scope.iter().map(|(k, v)| (*k, v.clone())));
+        }
+        let mut vars: Vec<(String, Value)> = vars.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        vars
+    }
+
+    // Every scope's bindings, innermost first, each sorted by name.
+    pub fn scope_chain(&self) -> Vec<Vec<(String, Value)>> {
+        self.scopes
+            .iter()
+            .rev()
+            .map(|scope| {
+                let mut vars: Vec<(String, Value)> =
+                    scope.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+                vars.sort_by(|a, b| a.0.cmp(&b.0));
+                vars
+            })
+            .collect()
+    }
+
+    // Drops every binding and the captured output; the locale is kept.
+    pub fn reset(&mut self) {
+        self.reset_to(HashMap::new());
+    }
+
+    // Captures the global variables and functions so a later interpreter
+    // can pick up where this one left off.
+    pub fn snapshot(&self) -> InterpreterState {
+        InterpreterState {
+            globals: self.variables(),
+        }
+    }
+
+    // Replaces all bindings with the snapshot's, like reset() followed by
+    // defining each global.
+    pub fn restore(&mut self, state: InterpreterState) {
+        self.reset_to(
+            state
+                .globals
+                .into_iter()
+                .map(|(name, value)| (Symbol::intern(&name), value))
+                .collect(),
+        );
+    }
+
+    pub(crate) fn globals(&self) -> &HashMap<Symbol, Value> {
+        &self.scopes[0]
+    }
+
+    pub(crate) fn reset_to(&mut self, globals: HashMap<Symbol, Value>) {
+        self.scopes = vec![globals];
+        self.output.clear();
+        self.exit_code = None;
+    }
+
+    // Calls the script function bound to `name`, as `name(args...)` would.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, String> {
+        let func = self.get_var(Symbol::intern(name))?;
+        let result = self.call_value(func, args);
+        self.aborting = false;
+        result
+    }
+
+    // Set once the program has called exit().
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
     }
 
     fn push_scope(&mut self) {
@@ -69,32 +806,126 @@ impl Interpreter {
         self.scopes.pop();
     }
 
-    fn get_var(&self, name: &str) -> Result<Value, String> {
-        for scope in self.scopes.iter().rev() {
-            if let Some(val) = scope.get(name) {
-                return Ok(val.clone());
-            }
+    fn is_global(&self, name: Symbol) -> bool {
+        self.frames.last().is_some_and(|f| f.globals.contains(&name))
+    }
+
+    fn get_var(&self, name: Symbol) -> Result<Value, String> {
+        self.lookup(name).cloned().ok_or_else(|| self.undefined(&name))
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<&Value> {
+        if self.is_global(name) {
+            return self.scopes[0].get(&name);
         }
-        Err(format!("Undefined variable '{}'", name))
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
     }
 
-    fn set_var(&mut self, name: &str, val: Value) {
+    fn undefined(&self, name: &str) -> String {
+        let names = self.names();
+        let candidates = names
+            .iter()
+            .map(String::as_str)
+            .chain(builtins::STANDARD.iter().map(|b| b.name))
+            .chain(KEYWORDS.iter().copied());
+        match did_you_mean(name, candidates) {
+            Some(suggestion) => format!("Undefined variable '{}'; did you mean '{}'?", name, suggestion),
+            None => format!("Undefined variable '{}'", name),
+        }
+    }
+
+    fn set_var(&mut self, name: Symbol, val: Value) -> Result<(), String> {
+        if self.is_global(name) {
+            self.scopes[0].insert(name, val);
+            return Ok(());
+        }
         // Set in the nearest scope that has it, or current scope
-        for scope in self.scopes.iter_mut().rev() {
-            if scope.contains_key(name) {
-                scope.insert(name.to_string(), val);
-                return;
+        let base = self.frames.last().map_or(0, |f| f.base);
+        for (i, scope) in self.scopes.iter_mut().enumerate().rev() {
+            if let Some(slot) = scope.get_mut(&name) {
+                if self.strict_vars && i < base {
+                    return Err(format!(
+                        "Assignment to outer variable '{}' at line {} (declare it with 'global {}' first)",
+                        name, self.line, name
+                    ));
+                }
+                *slot = val;
+                return Ok(());
             }
         }
+        if self.strict_vars {
+            return Err(format!(
+                "Assignment to undeclared variable '{}' at line {} (declare it with 'let' first)",
+                name, self.line
+            ));
+        }
         // New variable in current (top) scope
-        self.scopes.last_mut().unwrap().insert(name.to_string(), val);
+        self.scopes.last_mut().unwrap().insert(name, val);
+        Ok(())
+    }
+
+    // The slot `set_var` would overwrite, if it already exists and may be
+    // assigned from here.
+    fn assignable_slot(&mut self, name: Symbol) -> Option<&mut Value> {
+        if self.is_global(name) {
+            return self.scopes[0].get_mut(&name);
+        }
+        let base = self.frames.last().map_or(0, |f| f.base);
+        let (i, scope) = self.scopes.iter_mut().enumerate().rev().find(|(_, s)| s.contains_key(&name))?;
+        if self.strict_vars && i < base {
+            return None;
+        }
+        scope.get_mut(&name)
+    }
+
+    fn check_not_global(&self, name: Symbol) -> Result<(), String> {
+        if self.is_global(name) {
+            return Err(format!(
+                "'{}' is declared global in this function at line {}; assign it without 'let'",
+                name, self.line
+            ));
+        }
+        Ok(())
     }
 
-    fn define_var(&mut self, name: String, val: Value) {
+    fn define_var(&mut self, name: Symbol, val: Value) {
         self.scopes.last_mut().unwrap().insert(name, val);
     }
 
+    pub fn run_program(&mut self, program: &Arc<Program>) -> Result<(), String> {
+        self.run(program.stmts())
+    }
+
     pub fn run(&mut self, program: &[Stmt]) -> Result<(), String> {
+        self.steps = 0;
+        let result = self.run_stmts(program);
+        if result.is_err() {
+            self.unwind();
+        }
+        match result {
+            Err(_) if self.exit_code.is_some() => Ok(()),
+            r => r,
+        }
+    }
+
+    // An error skips the pops of the blocks and calls it passes through, so
+    // drop back to the top-level scope before the next run.
+    fn unwind(&mut self) {
+        self.scopes.truncate(1);
+        self.frames.clear();
+        self.returning = None;
+        self.aborting = false;
+    }
+
+    // Like `run`, but aborts with a timeout error once `timeout` has elapsed.
+    pub fn run_with_timeout(&mut self, program: &[Stmt], timeout: Duration) -> Result<(), String> {
+        self.deadline = Some(Instant::now() + timeout);
+        let result = self.run(program);
+        self.deadline = None;
+        result
+    }
+
+    fn run_stmts(&mut self, program: &[Stmt]) -> Result<(), String> {
         for stmt in program {
             if let Signal::Return(_) = self.exec_stmt(stmt)? {
                 break;
@@ -103,103 +934,203 @@ impl Interpreter {
         Ok(())
     }
 
+    // Like `run`, but returns the value of the final statement when it is a
+    // non-null expression, for the REPL to echo.
+    pub fn run_interactive(&mut self, program: &[Stmt]) -> Result<Option<Value>, String> {
+        self.steps = 0;
+        let result = self.run_stmts_interactive(program);
+        if result.is_err() {
+            self.unwind();
+        }
+        match result {
+            Err(_) if self.exit_code.is_some() => Ok(None),
+            r => r,
+        }
+    }
+
+    fn run_stmts_interactive(&mut self, program: &[Stmt]) -> Result<Option<Value>, String> {
+        let mut last = None;
+        for stmt in program {
+            last = None;
+            if let StmtKind::ExprStmt(expr) = &stmt.kind {
+                let val = self.in_stmt(stmt, |interp| interp.eval_expr(expr))?;
+                if !matches!(val, Value::Null) {
+                    last = Some(val);
+                }
+            } else if let Signal::Return(_) = self.exec_stmt(stmt)? {
+                break;
+            }
+        }
+        Ok(last)
+    }
+
+    // Runs eval()'d code in the caller's scope. Its runtime errors become
+    // error values, unless they end the program (exit()) or are `?` leaving
+    // the function eval() was called from.
+    fn eval_program(&mut self, program: &[Stmt]) -> Result<Value, String> {
+        let (scopes, frames) = (self.scopes.len(), self.frames.len());
+        match self.run_stmts_interactive(program) {
+            Ok(value) => Ok(value.unwrap_or(Value::Null)),
+            // exit(), `?` and aborts unwind through eval() like through
+            // any other call.
+            Err(e) if self.exit_code.is_some() || self.returning.is_some() || self.aborting => Err(e),
+            Err(e) => {
+                self.scopes.truncate(scopes);
+                self.frames.truncate(frames);
+                Ok(Value::Error(e))
+            }
+        }
+    }
+
     fn exec_stmt(&mut self, stmt: &Stmt) -> Result<Signal, String> {
-        match stmt {
-            Stmt::Let(name, expr) => {
+        self.in_stmt(stmt, |interp| interp.exec_stmt_inner(stmt))
+    }
+
+    // What every statement goes through, including the expression
+    // statements the REPL and eval() evaluate for their value: the stack
+    // guard, the line for error messages, the step limit and the debug hook.
+    fn in_stmt<T>(&mut self, stmt: &Stmt, run: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.enter()?;
+        let outer = std::mem::replace(&mut self.line, stmt.line);
+        let result = self.before_stmt(stmt.line).and_then(|()| run(self));
+        self.line = outer;
+        self.depth -= 1;
+        result
+    }
+
+    fn before_stmt(&mut self, line: usize) -> Result<(), String> {
+        self.step()?;
+        // Taken out while it runs so it can look at the interpreter.
+        if let Some(mut hook) = self.debug_hook.take() {
+            let result = hook.before_stmt(self, line);
+            self.debug_hook = Some(hook);
+            if let Err(e) = result {
+                return self.abort(&e);
+            }
+        }
+        Ok(())
+    }
+
+    fn exec_stmt_inner(&mut self, stmt: &Stmt) -> Result<Signal, String> {
+        match &stmt.kind {
+            StmtKind::Let(name, expr) => {
+                self.check_not_global(*name)?;
                 let val = self.eval_expr(expr)?;
-                self.define_var(name.clone(), val);
+                self.define_var(*name, val);
             }
-            Stmt::Assign(name, expr) => {
+            StmtKind::LetArray(names, rest, expr) => {
+                for name in names.iter().chain(rest) {
+                    self.check_not_global(*name)?;
+                }
+                let elems = match self.eval_expr(expr)? {
+                    Value::Array(elems) => lock(&elems).to_vec(),
+                    other => return Err(format!("Cannot destructure a {}; expected an array", other.type_name())),
+                };
+                let (fits, expected) = match rest {
+                    Some(_) => (elems.len() >= names.len(), "at least "),
+                    None => (elems.len() == names.len(), ""),
+                };
+                if !fits {
+                    return Err(format!(
+                        "Cannot destructure an array of length {}: expected {}{}",
+                        elems.len(),
+                        expected,
+                        names.len()
+                    ));
+                }
+                let mut elems = elems.into_iter();
+                for name in names {
+                    self.define_var(*name, elems.next().unwrap_or(Value::Null));
+                }
+                if let Some(rest) = rest {
+                    self.define_var(*rest, Value::array(elems.collect()));
+                }
+            }
+            // `s = s + piece` appends in place rather than copying `s` each
+            // time. Without calls on the right nothing can reassign `s`
+            // before the append, so evaluating the right side first is safe.
+            StmtKind::Assign(name, Expr::Binary(left, BinOp::Add, right))
+                if matches!(**left, Expr::Ident(n) if n == *name)
+                    && matches!(self.assignable_slot(*name), Some(Value::Str(_)))
+                    && !Self::has_call(right) =>
+            {
+                let rv = self.eval_expr(right)?;
+                let limit = self.memory_limit;
+                let Some(Value::Str(s)) = self.assignable_slot(*name) else { unreachable!() };
+                match rv {
+                    Value::Str(piece) if limit.is_none_or(|limit| s.len() + piece.len() <= limit) => {
+                        s.push_str(&piece)
+                    }
+                    rv => {
+                        let lv = Value::Str(s.clone());
+                        let val = self.add(lv, rv)?;
+                        self.set_var(*name, val)?;
+                    }
+                }
+            }
+            StmtKind::Assign(name, expr) => {
                 let val = self.eval_expr(expr)?;
-                self.set_var(name, val);
+                self.set_var(*name, val)?;
             }
-            Stmt::IndexAssign(name, index_expr, value_expr) => {
+            StmtKind::MultiAssign(names, exprs) => {
+                let vals = exprs.iter().map(|e| self.eval_expr(e)).collect::<Result<Vec<_>, _>>()?;
+                for (name, val) in names.iter().zip(vals) {
+                    self.set_var(*name, val)?;
+                }
+            }
+            StmtKind::IndexAssign(name, index_expr, value_expr) => {
                 let idx = self.eval_expr(index_expr)?;
                 let val = self.eval_expr(value_expr)?;
                 let i = match idx {
                     Value::Number(n) => n as usize,
                     _ => return Err("Array index must be a number".to_string()),
                 };
-                // Find and mutate the array in-place
-                for scope in self.scopes.iter_mut().rev() {
-                    if let Some(arr_val) = scope.get_mut(name) {
-                        match arr_val {
-                            Value::Array(elems) => {
-                                if i >= elems.len() {
-                                    return Err(format!("Index {} out of bounds", i));
-                                }
-                                elems[i] = val;
-                                return Ok(Signal::None);
-                            }
-                            _ => return Err(format!("'{}' is not an array", name)),
+                // Mutates the shared array, so every alias sees the change
+                match self.get_var(*name)? {
+                    Value::Array(elems) => {
+                        let mut elems = lock(&elems);
+                        if elems.frozen {
+                            return Err(format!("FrozenError: cannot assign to an element of frozen array '{}'", name));
                         }
+                        if i >= elems.len() {
+                            return Err(format!("Index {} out of bounds", i));
+                        }
+                        elems[i] = val;
                     }
+                    _ => return Err(format!("'{}' is not an array", name)),
                 }
-                return Err(format!("Undefined variable '{}'", name));
             }
-            Stmt::If(cond, body, else_body) => {
+            StmtKind::If(cond, body, else_body) => {
                 let val = self.eval_expr(cond)?;
                 if Self::is_truthy(&val) {
-                    let sig = self.exec_block(body)?;
-                    if let Signal::Return(_) = sig {
-                        return Ok(sig);
-                    }
+                    return self.exec_block(body);
                 } else if let Some(else_b) = else_body {
-                    let sig = self.exec_block(else_b)?;
-                    if let Signal::Return(_) = sig {
-                        return Ok(sig);
-                    }
-                }
-            }
-            Stmt::While(cond, body) => {
-                loop {
-                    let val = self.eval_expr(cond)?;
-                    if !Self::is_truthy(&val) {
-                        break;
-                    }
-                    let sig = self.exec_block(body)?;
-                    if let Signal::Return(_) = sig {
-                        return Ok(sig);
-                    }
-                }
-            }
-            Stmt::For(var, start_expr, end_expr, body) => {
-                let start = match self.eval_expr(start_expr)? {
-                    Value::Number(n) => n as i64,
-                    _ => return Err("For range start must be a number".to_string()),
-                };
-                let end = match self.eval_expr(end_expr)? {
-                    Value::Number(n) => n as i64,
-                    _ => return Err("For range end must be a number".to_string()),
-                };
-                for i in start..end {
-                    self.push_scope();
-                    self.define_var(var.clone(), Value::Number(i as f64));
-                    for s in body {
-                        let sig = self.exec_stmt(s)?;
-                        if let Signal::Return(_) = sig {
-                            self.pop_scope();
-                            return Ok(sig);
-                        }
-                    }
-                    self.pop_scope();
+                    return self.exec_block(else_b);
                 }
             }
-            Stmt::Fn(name, params, body) => {
+            StmtKind::While(..) | StmtKind::For(..) | StmtKind::ForEach(..) => return self.exec_loop(&stmt.kind, None),
+            StmtKind::Labeled(label, inner) => return self.exec_loop(&inner.kind, Some(*label)),
+            StmtKind::Break(label) => return Ok(Signal::Break(*label)),
+            StmtKind::Continue(label) => return Ok(Signal::Continue(*label)),
+            StmtKind::Fn(name, params, body) => {
                 let func = Value::Function {
                     params: params.clone(),
                     body: body.clone(),
                 };
-                self.define_var(name.clone(), func);
+                self.define_var(*name, func);
             }
-            Stmt::Return(expr) => {
+            StmtKind::Global(names) => match self.frames.last_mut() {
+                Some(frame) => frame.globals.extend(names),
+                None => return Err(format!("'global' used outside a function at line {}", self.line)),
+            },
+            StmtKind::Return(expr) => {
                 let val = match expr {
                     Some(e) => self.eval_expr(e)?,
                     None => Value::Null,
                 };
                 return Ok(Signal::Return(val));
             }
-            Stmt::ExprStmt(expr) => {
+            StmtKind::ExprStmt(expr) => {
                 self.eval_expr(expr)?;
             }
         }
@@ -208,107 +1139,251 @@ impl Interpreter {
 
     fn exec_block(&mut self, stmts: &[Stmt]) -> Result<Signal, String> {
         self.push_scope();
+        let sig = self.exec_stmts(stmts);
+        self.pop_scope();
+        sig
+    }
+
+    // Runs statements in the current scope until one returns, breaks or
+    // continues.
+    fn exec_stmts(&mut self, stmts: &[Stmt]) -> Result<Signal, String> {
         for stmt in stmts {
             let sig = self.exec_stmt(stmt)?;
-            if let Signal::Return(_) = sig {
-                self.pop_scope();
+            if !matches!(sig, Signal::None) {
                 return Ok(sig);
             }
         }
+        Ok(Signal::None)
+    }
+
+    // Anything for-each accepts, as an iterator; iterators are passed through.
+    fn to_iter(source: Value, builtin: &str) -> Result<Arc<Mutex<LazyIter>>, String> {
+        let it = match source {
+            Value::Iterator(it) => return Ok(it),
+            Value::Array(elems) => LazyIter::Array(elems, 0),
+            Value::Str(s) => LazyIter::Chars(s, 0),
+            Value::Function { ref params, .. } if params.is_empty() => LazyIter::Function(source),
+            _ => {
+                return Err(format!(
+                    "{}() requires an array, string, function without parameters or iterator",
+                    builtin
+                ));
+            }
+        };
+        Ok(Arc::new(Mutex::new(it)))
+    }
+
+    // Advances an iterator. The lock is never held across a call
+    // into the script, which may use the same iterator.
+    fn iter_next(&mut self, it: &Arc<Mutex<LazyIter>>) -> Result<Option<Value>, String> {
+        self.step()?;
+        let mut state = lock(it);
+        match &mut *state {
+            LazyIter::Array(elems, pos) => {
+                let item = lock(elems).get(*pos).cloned();
+                *pos += 1;
+                Ok(item)
+            }
+            LazyIter::Chars(s, pos) => match s[*pos..].chars().next() {
+                Some(c) => {
+                    *pos += c.len_utf8();
+                    Ok(Some(Value::Str(c.to_string())))
+                }
+                None => Ok(None),
+            },
+            LazyIter::Function(func) => {
+                let func = func.clone();
+                drop(state);
+                match self.call_value(func, Vec::new())? {
+                    Value::Null => Ok(None),
+                    item => Ok(Some(item)),
+                }
+            }
+            LazyIter::Take(_, 0) => Ok(None),
+            LazyIter::Take(inner, remaining) => {
+                *remaining -= 1;
+                let inner = inner.clone();
+                drop(state);
+                self.iter_next(&inner)
+            }
+            LazyIter::Skip(inner, pending) => {
+                let (inner, n) = (inner.clone(), std::mem::take(pending));
+                drop(state);
+                for _ in 0..n {
+                    if self.iter_next(&inner)?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                self.iter_next(&inner)
+            }
+            LazyIter::Map(inner, func) => {
+                let (inner, func) = (inner.clone(), func.clone());
+                drop(state);
+                match self.iter_next(&inner)? {
+                    Some(item) => Ok(Some(self.call_value(func, vec![item])?)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    // One pass through a `for` body with `var` bound to `item`.
+    fn iteration(&mut self, var: Symbol, item: Value, body: &[Stmt], label: Option<Symbol>) -> Result<Flow, String> {
+        self.push_scope();
+        self.define_var(var, item);
+        let sig = self.exec_stmts(body);
         self.pop_scope();
+        Ok(sig?.flow(label))
+    }
+
+    fn exec_loop(&mut self, kind: &StmtKind, label: Option<Symbol>) -> Result<Signal, String> {
+        match kind {
+            StmtKind::While(cond, body) => loop {
+                let val = self.eval_expr(cond)?;
+                if !Self::is_truthy(&val) {
+                    break;
+                }
+                match self.exec_block(body)?.flow(label) {
+                    Flow::Next => {}
+                    Flow::Exit => break,
+                    Flow::Propagate(sig) => return Ok(sig),
+                }
+            },
+            StmtKind::For(var, start_expr, end_expr, body) => {
+                let start = match self.eval_expr(start_expr)? {
+                    Value::Number(n) => n as i64,
+                    _ => return Err("For range start must be a number".to_string()),
+                };
+                let end = match self.eval_expr(end_expr)? {
+                    Value::Number(n) => n as i64,
+                    _ => return Err("For range end must be a number".to_string()),
+                };
+                for i in start..end {
+                    match self.iteration(*var, Value::Number(i as f64), body, label)? {
+                        Flow::Next => {}
+                        Flow::Exit => break,
+                        Flow::Propagate(sig) => return Ok(sig),
+                    }
+                }
+            }
+            StmtKind::ForEach(var, iterable, body) => {
+                let items = match self.eval_expr(iterable)? {
+                    // Loops over the elements present when the loop starts.
+                    Value::Array(elems) => lock(&elems).to_vec(),
+                    Value::Str(s) => s.chars().map(|c| Value::Str(c.to_string())).collect(),
+                    // The iterator protocol: call it until it returns null.
+                    Value::Function { params, body: next } if params.is_empty() => {
+                        let next = Value::Function { params, body: next };
+                        loop {
+                            let item = self.call_value(next.clone(), Vec::new())?;
+                            if let Value::Null = item {
+                                break;
+                            }
+                            match self.iteration(*var, item, body, label)? {
+                                Flow::Next => {}
+                                Flow::Exit => break,
+                                Flow::Propagate(sig) => return Ok(sig),
+                            }
+                        }
+                        return Ok(Signal::None);
+                    }
+                    Value::Function { .. } => {
+                        return Err("For-each over a function requires one that takes no arguments".to_string());
+                    }
+                    Value::Iterator(it) => {
+                        while let Some(item) = self.iter_next(&it)? {
+                            match self.iteration(*var, item, body, label)? {
+                                Flow::Next => {}
+                                Flow::Exit => break,
+                                Flow::Propagate(sig) => return Ok(sig),
+                            }
+                        }
+                        return Ok(Signal::None);
+                    }
+                    _ => return Err("For-each requires an array, string, function or iterator".to_string()),
+                };
+                for item in items {
+                    match self.iteration(*var, item, body, label)? {
+                        Flow::Next => {}
+                        Flow::Exit => break,
+                        Flow::Propagate(sig) => return Ok(sig),
+                    }
+                }
+            }
+            _ => return Err("Only loops can be labeled".to_string()),
+        }
         Ok(Signal::None)
     }
 
     fn eval_expr(&mut self, expr: &Expr) -> Result<Value, String> {
+        self.enter()?;
+        let result = self.eval_expr_inner(expr);
+        self.depth -= 1;
+        result
+    }
+
+    fn eval_expr_inner(&mut self, expr: &Expr) -> Result<Value, String> {
+        self.step()?;
         match expr {
             Expr::Number(n) => Ok(Value::Number(*n)),
             Expr::StringLit(s) => Ok(Value::Str(s.clone())),
             Expr::Bool(b) => Ok(Value::Bool(*b)),
-            Expr::Ident(name) => self.get_var(name),
+            Expr::Ident(name) => self.get_var(*name),
             Expr::Array(elems) => {
-                let mut vals = Vec::new();
-                for e in elems {
-                    vals.push(self.eval_expr(e)?);
-                }
-                Ok(Value::Array(vals))
+                let vals = self.eval_items(elems)?;
+                self.check_alloc(|| Self::heap_size(&vals))?;
+                Ok(Value::array(vals))
             }
+            Expr::Spread(_) => Err("'...' can only be used in array literals and call arguments".to_string()),
+            Expr::Try(inner) => match self.eval_expr(inner)? {
+                Value::Error(message) if self.frames.is_empty() => {
+                    Err(format!("Unhandled error at line {}: {}", self.line, message))
+                }
+                Value::Error(message) => {
+                    // Unwinds like a runtime error until call_value turns it
+                    // back into the function's return value.
+                    self.returning = Some(Value::Error(message));
+                    Err(String::new())
+                }
+                value => Ok(value),
+            },
             Expr::Index(arr_expr, idx_expr) => {
                 let arr = self.eval_expr(arr_expr)?;
                 let idx = self.eval_expr(idx_expr)?;
                 match (arr, idx) {
                     (Value::Array(elems), Value::Number(n)) => {
+                        let elems = lock(&elems);
                         let i = n as usize;
                         if i >= elems.len() {
                             return Err(format!("Index {} out of bounds", i));
                         }
                         Ok(elems[i].clone())
                     }
-                    _ => Err("Index operator requires array and number".to_string()),
+                    // Strings index by character, not byte.
+                    (Value::Str(s), Value::Number(n)) => {
+                        let i = n as usize;
+                        match s.chars().nth(i) {
+                            Some(c) => Ok(Value::Str(c.to_string())),
+                            None => Err(format!("Index {} out of bounds", i)),
+                        }
+                    }
+                    _ => Err("Index operator requires array or string and number".to_string()),
                 }
             }
             Expr::Call(func_expr, args) => {
-                // Check for built-in functions
-                if let Expr::Ident(name) = func_expr.as_ref() {
-                    match name.as_str() {
-                        "print" => {
-                            let mut vals = Vec::new();
-                            for a in args {
-                                vals.push(self.eval_expr(a)?);
-                            }
-                            if let Some(v) = vals.first() {
-                                self.output.push(format!("{}", v));
-                            }
-                            return Ok(Value::Null);
-                        }
-                        "len" => {
-                            if args.len() != 1 {
-                                return Err("len() takes exactly 1 argument".to_string());
-                            }
-                            let val = self.eval_expr(&args[0])?;
-                            return match val {
-                                Value::Array(elems) => Ok(Value::Number(elems.len() as f64)),
-                                Value::Str(s) => Ok(Value::Number(s.len() as f64)),
-                                _ => Err("len() requires array or string".to_string()),
-                            };
-                        }
-                        _ => {}
-                    }
+                // Host and built-in functions, unless the script has defined
+                // a function of the same name.
+                if let Expr::Ident(name) = func_expr.as_ref()
+                    && self.is_builtin(*name)
+                    && !matches!(self.lookup(*name), Some(Value::Function { .. }))
+                {
+                    return self.call_host_or_builtin(*name, args);
                 }
 
                 let func = self.eval_expr(func_expr)?;
-                let mut arg_vals = Vec::new();
-                for a in args {
-                    arg_vals.push(self.eval_expr(a)?);
-                }
-
-                match func {
-                    Value::Function { params, body } => {
-                        if params.len() != arg_vals.len() {
-                            return Err(format!(
-                                "Expected {} arguments, got {}",
-                                params.len(),
-                                arg_vals.len()
-                            ));
-                        }
-                        self.push_scope();
-                        for (p, v) in params.iter().zip(arg_vals) {
-                            self.define_var(p.clone(), v);
-                        }
-                        let mut result = Value::Null;
-                        for stmt in &body {
-                            match self.exec_stmt(stmt)? {
-                                Signal::Return(val) => {
-                                    result = val;
-                                    break;
-                                }
-                                Signal::None => {}
-                            }
-                        }
-                        self.pop_scope();
-                        Ok(result)
-                    }
-                    _ => Err("Attempted to call a non-function".to_string()),
-                }
+                let arg_vals = self.eval_items(args)?;
+
+                self.call_profiled(func_expr, func, arg_vals)
             }
             Expr::Unary(op, operand) => {
                 let val = self.eval_expr(operand)?;
@@ -320,60 +1395,195 @@ impl Interpreter {
                     UnaryOp::Not => Ok(Value::Bool(!Self::is_truthy(&val))),
                 }
             }
-            Expr::Binary(left, op, right) => {
-                // Short-circuit for and/or
-                if matches!(op, BinOp::And) {
-                    let lv = self.eval_expr(left)?;
-                    if !Self::is_truthy(&lv) {
-                        return Ok(lv);
-                    }
-                    return self.eval_expr(right);
+            Expr::Binary(..) => {
+                // Walks left-leaning chains like `a + b + c` in a loop, so a
+                // long one is not mistaken for deep recursion.
+                let mut ops = Vec::new();
+                let mut leftmost = expr;
+                while let Expr::Binary(left, op, right) = leftmost {
+                    ops.push((op, right));
+                    leftmost = left;
                 }
-                if matches!(op, BinOp::Or) {
-                    let lv = self.eval_expr(left)?;
-                    if Self::is_truthy(&lv) {
-                        return Ok(lv);
+                let mut value = self.eval_expr(leftmost)?;
+                for (i, (op, right)) in ops.into_iter().rev().enumerate() {
+                    if i > 0 {
+                        self.step()?;
                     }
-                    return self.eval_expr(right);
+                    value = self.binary(value, op, right)?;
                 }
+                Ok(value)
+            }
+        }
+    }
 
-                let lv = self.eval_expr(left)?;
-                let rv = self.eval_expr(right)?;
+    fn binary(&mut self, lv: Value, op: &BinOp, right: &Expr) -> Result<Value, String> {
+        // Short-circuit for and/or
+        if matches!(op, BinOp::And) {
+            if !Self::is_truthy(&lv) {
+                return Ok(lv);
+            }
+            return self.eval_expr(right);
+        }
+        if matches!(op, BinOp::Or) {
+            if Self::is_truthy(&lv) {
+                return Ok(lv);
+            }
+            return self.eval_expr(right);
+        }
 
-                match op {
-                    BinOp::Add => match (lv, rv) {
-                        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
-                        (Value::Str(a), Value::Str(b)) => Ok(Value::Str(a + &b)),
-                        (Value::Array(mut a), Value::Array(b)) => {
-                            a.extend(b);
-                            Ok(Value::Array(a))
-                        }
-                        _ => Err("'+' requires two numbers, two strings, or two arrays".to_string()),
-                    },
-                    BinOp::Sub => Self::num_op(lv, rv, |a, b| a - b),
-                    BinOp::Mul => Self::num_op(lv, rv, |a, b| a * b),
-                    BinOp::Div => Self::num_op(lv, rv, |a, b| a / b),
-                    BinOp::Mod => Self::num_op(lv, rv, |a, b| a % b),
-                    BinOp::Lt => Self::cmp_op(lv, rv, |a, b| a < b),
-                    BinOp::LtEq => Self::cmp_op(lv, rv, |a, b| a <= b),
-                    BinOp::Gt => Self::cmp_op(lv, rv, |a, b| a > b),
-                    BinOp::GtEq => Self::cmp_op(lv, rv, |a, b| a >= b),
-                    BinOp::Eq => Ok(Value::Bool(Self::values_equal(&lv, &rv))),
-                    BinOp::Neq => Ok(Value::Bool(!Self::values_equal(&lv, &rv))),
-                    BinOp::And | BinOp::Or => unreachable!(),
+        let rv = self.eval_expr(right)?;
+
+        if self.strict_math
+            && matches!(op, BinOp::Div | BinOp::Mod)
+            && matches!(rv, Value::Number(b) if b == 0.0)
+        {
+            let what = if *op == BinOp::Div { "Division" } else { "Modulo" };
+            return Err(format!("{} by zero at line {}", what, self.line));
+        }
+        let result = match op {
+            BinOp::Add => self.add(lv, rv),
+            BinOp::Sub => Self::num_op(lv, rv, |a, b| a - b),
+            BinOp::Mul => Self::num_op(lv, rv, |a, b| a * b),
+            BinOp::Div => Self::num_op(lv, rv, |a, b| a / b),
+            BinOp::Mod => Self::num_op(lv, rv, |a, b| a % b),
+            BinOp::Lt => Self::cmp_op(lv, rv, |a, b| a < b),
+            BinOp::LtEq => Self::cmp_op(lv, rv, |a, b| a <= b),
+            BinOp::Gt => Self::cmp_op(lv, rv, |a, b| a > b),
+            BinOp::GtEq => Self::cmp_op(lv, rv, |a, b| a >= b),
+            BinOp::Eq | BinOp::Neq if self.strict_equality && Self::mixed_types(&lv, &rv) => Err(format!(
+                "Cannot compare {} with {} using '{}' at line {} (use '{}' for a check that allows it)",
+                lv.type_name(),
+                rv.type_name(),
+                op.symbol(),
+                self.line,
+                if *op == BinOp::Eq { "===" } else { "!==" }
+            )),
+            BinOp::Is => Ok(Value::Bool(Self::same_value(&lv, &rv))),
+            BinOp::IsNot => Ok(Value::Bool(!Self::same_value(&lv, &rv))),
+            BinOp::Eq | BinOp::StrictEq => Ok(Value::Bool(Self::values_equal(&lv, &rv))),
+            BinOp::Neq | BinOp::StrictNeq => Ok(Value::Bool(!Self::values_equal(&lv, &rv))),
+            BinOp::And | BinOp::Or => unreachable!(),
+        };
+        if self.strict_math
+            && let Ok(Value::Number(n)) = result
+            && n.is_nan()
+        {
+            return Err(format!("'{}' produced NaN at line {}", op.symbol(), self.line));
+        }
+        result
+    }
+
+    // eval_expr for a capability-gated builtin's argument, logged when auditing.
+    fn eval_audited(&mut self, expr: &Expr) -> Result<Value, String> {
+        let value = self.eval_expr(expr)?;
+        if let (Some(audit), Some(&i)) = (&mut self.audit, self.audit_open.last()) {
+            audit[i].args.push(value.deep_copy());
+        }
+        Ok(value)
+    }
+
+    // Array elements or call arguments, with each `...xs` expanded in place.
+    fn eval_items(&mut self, exprs: &[Expr]) -> Result<Vec<Value>, String> {
+        let mut vals = Vec::with_capacity(exprs.len());
+        for e in exprs {
+            match e {
+                Expr::Spread(inner) => match self.eval_expr(inner)? {
+                    Value::Array(items) => vals.extend(lock(&items).iter().cloned()),
+                    other => return Err(format!("Cannot spread a {}; '...' requires an array", other.type_name())),
+                },
+                e => vals.push(self.eval_expr(e)?),
+            }
+        }
+        Ok(vals)
+    }
+
+    // Kept out of eval_expr so its stack frame stays small for recursion.
+    fn is_builtin(&self, name: Symbol) -> bool {
+        self.natives.contains_key(&name) || self.builtins.contains_key(&name)
+    }
+
+    fn call_host_or_builtin(&mut self, name: Symbol, args: &[Expr]) -> Result<Value, String> {
+        if self.natives.contains_key(&name) {
+            let arg_vals = self.eval_items(args)?;
+            return (self.natives[&name])(&arg_vals);
+        }
+        if args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+            return Err(format!("{}() does not accept '...' arguments", name));
+        }
+        self.call_builtin(name, args)
+    }
+
+    fn call_builtin(&mut self, name: Symbol, args: &[Expr]) -> Result<Value, String> {
+        match self.builtins.get(&name).copied() {
+            Some(builtin) => builtin.call(self, args),
+            None => Err(format!("Unknown builtin '{}'", name)),
+        }
+    }
+
+    // call_value, timed under the callee's name while profiling.
+    fn call_profiled(&mut self, callee: &Expr, func: Value, arg_vals: Vec<Value>) -> Result<Value, String> {
+        let Some(profile) = &mut self.profile else {
+            return self.call_value(func, arg_vals);
+        };
+        profile.enter(match callee {
+            Expr::Ident(name) => *name,
+            _ => Symbol::intern("<anonymous>"),
+        });
+        let result = self.call_value(func, arg_vals);
+        if let Some(profile) = &mut self.profile {
+            profile.exit();
+        }
+        result
+    }
+
+    fn call_value(&mut self, func: Value, arg_vals: Vec<Value>) -> Result<Value, String> {
+        match func {
+            Value::Function { params, body } => {
+                if params.len() != arg_vals.len() {
+                    return Err(format!(
+                        "Expected {} arguments, got {}",
+                        params.len(),
+                        arg_vals.len()
+                    ));
+                }
+                self.push_scope();
+                self.frames.push(Frame {
+                    base: self.scopes.len() - 1,
+                    globals: Vec::new(),
+                });
+                for (p, v) in params.iter().zip(arg_vals) {
+                    self.define_var(*p, v);
+                }
+                let result = self.exec_body(&body);
+                self.frames.pop();
+                self.pop_scope();
+                match self.returning.take() {
+                    Some(error) => Ok(error),
+                    None => result,
                 }
             }
+            _ => Err("Attempted to call a non-function".to_string()),
         }
     }
 
+    fn exec_body(&mut self, body: &[Stmt]) -> Result<Value, String> {
+        for stmt in body {
+            if let Signal::Return(val) = self.exec_stmt(stmt)? {
+                return Ok(val);
+            }
+        }
+        Ok(Value::Null)
+    }
+
     fn is_truthy(val: &Value) -> bool {
         match val {
             Value::Bool(b) => *b,
             Value::Null => false,
             Value::Number(n) => *n != 0.0,
             Value::Str(s) => !s.is_empty(),
-            Value::Array(a) => !a.is_empty(),
-            Value::Function { .. } => true,
+            Value::Array(a) => !lock(a).is_empty(),
+            Value::Function { .. } | Value::Iterator(_) => true,
+            Value::Error(_) => false,
         }
     }
 
@@ -391,11 +1601,43 @@ impl Interpreter {
         }
     }
 
+    // Like ==, but arrays compare element by element.
+    fn structurally_equal(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Array(x), Value::Array(y)) => {
+                Arc::ptr_eq(x, y) || {
+                    // Copies, since an element may be `x` or `y` itself.
+                    let x = lock(x).to_vec();
+                    let y = lock(y).to_vec();
+                    x.len() == y.len() && x.iter().zip(y.iter()).all(|(a, b)| Self::structurally_equal(a, b))
+                }
+            }
+            _ => Self::values_equal(a, b),
+        }
+    }
+
+    // Operands of different types, neither of them null.
+    fn mixed_types(a: &Value, b: &Value) -> bool {
+        !matches!(a, Value::Null) && !matches!(b, Value::Null) && a.type_name() != b.type_name()
+    }
+
+    // Arrays and functions are the same only if they are one object; other
+    // values compare as with `===`.
+    fn same_value(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Array(x), Value::Array(y)) => Arc::ptr_eq(x, y),
+            (Value::Function { body: x, .. }, Value::Function { body: y, .. }) => Arc::ptr_eq(x, y),
+            (Value::Iterator(x), Value::Iterator(y)) => Arc::ptr_eq(x, y),
+            _ => Self::values_equal(a, b),
+        }
+    }
+
     fn values_equal(a: &Value, b: &Value) -> bool {
         match (a, b) {
             (Value::Number(x), Value::Number(y)) => x == y,
             (Value::Str(x), Value::Str(y)) => x == y,
             (Value::Bool(x), Value::Bool(y)) => x == y,
+            (Value::Error(x), Value::Error(y)) => x == y,
             (Value::Null, Value::Null) => true,
             _ => false,
         }
@@ -0,0 +1,680 @@
+use std::sync::{Arc, Mutex};
+
+use super::{AuditEntry, Interpreter, LazyIter, PPRINT_WIDTH, Value, lock};
+use crate::csv;
+use crate::datetime::{self, DateTime};
+use crate::locale::NumberLocale;
+use crate::parser::{Expr, docstring, parse_source};
+use crate::symbol::{self, Symbol};
+
+// Builtins get their arguments unevaluated, so that e.g. find() can time
+// its predicate under the predicate's name.
+type BuiltinFn = fn(&mut Interpreter, &[Expr]) -> Result<Value, String>;
+
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    // `name?` is optional and `name...` takes any number of arguments;
+    // the interpreter checks the argument count against these.
+    pub params: &'static [&'static str],
+    // The InterpreterConfig capability it needs, if any.
+    pub capability: Option<&'static str>,
+    run: BuiltinFn,
+}
+
+impl Builtin {
+    const fn new(name: &'static str, params: &'static [&'static str], run: BuiltinFn) -> Self {
+        Builtin {
+            name,
+            params,
+            capability: None,
+            run,
+        }
+    }
+
+    const fn needs(self, capability: &'static str) -> Self {
+        Builtin {
+            capability: Some(capability),
+            ..self
+        }
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.params.join(", "))
+    }
+
+    fn arity(&self) -> (usize, Option<usize>) {
+        let required = self.params.iter().filter(|p| !p.ends_with('?') && !p.ends_with("...")).count();
+        if self.params.iter().any(|p| p.ends_with("...")) {
+            (required, None)
+        } else {
+            (required, Some(self.params.len()))
+        }
+    }
+
+    fn check_arity(&self, count: usize) -> Result<(), String> {
+        let (min, max) = self.arity();
+        if count >= min && max.is_none_or(|max| count <= max) {
+            return Ok(());
+        }
+        let plural = |n: usize| if n == 1 { "argument" } else { "arguments" };
+        let expected = match max {
+            None => format!("at least {} {}", min, plural(min)),
+            Some(0) => "no arguments".to_string(),
+            Some(max) if max == min => format!("exactly {} {}", min, plural(min)),
+            Some(max) if min == 0 => format!("at most {} {}", max, plural(max)),
+            Some(max) => format!("{} or {} arguments", min, max),
+        };
+        Err(format!("{}() takes {}", self.name, expected))
+    }
+
+    pub(super) fn call(&self, interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+        let Some(capability) = self.capability else {
+            self.check_arity(args.len())?;
+            return (self.run)(interp, args);
+        };
+        let allowed = interp.config.allows(capability);
+        let line = interp.line;
+        if let Some(audit) = &mut interp.audit {
+            audit.push(AuditEntry {
+                builtin: self.name,
+                args: Vec::new(),
+                line,
+                allowed,
+            });
+        }
+        // Refused before its arguments are evaluated, audited or not.
+        interp.require(allowed, capability, self.name)?;
+        self.check_arity(args.len())?;
+        let Some(audit) = &interp.audit else {
+            return (self.run)(interp, args);
+        };
+        interp.audit_open.push(audit.len() - 1);
+        let result = (self.run)(interp, args);
+        interp.audit_open.pop();
+        result
+    }
+}
+
+pub const STANDARD: &[Builtin] = &[
+    Builtin::new("print", &["values..."], print),
+    Builtin::new("len", &["value"], len),
+    Builtin::new("format_number", &["n", "locale?"], format_number),
+    Builtin::new("args", &[], script_args).needs("env"),
+    Builtin::new("exit", &["code?"], exit).needs("process"),
+    Builtin::new("input", &[], input).needs("stdin"),
+    Builtin::new("index_of", &["array", "value"], index_of),
+    Builtin::new("find", &["array", "predicate"], find),
+    Builtin::new("push", &["array", "value"], push),
+    Builtin::new("slice", &["target", "start", "end?"], slice),
+    Builtin::new("bytes", &["s"], bytes),
+    Builtin::new("assert", &["condition", "message?"], assert),
+    Builtin::new("assert_eq", &["actual", "expected"], assert_eq),
+    Builtin::new("run", &["command", "args?"], run).needs("process"),
+    Builtin::new("now", &[], now),
+    Builtin::new("date", &["timestamp?"], date),
+    Builtin::new("format_time", &["timestamp", "pattern"], format_time),
+    Builtin::new("parse_time", &["s"], parse_time),
+    Builtin::new("csv_parse", &["text"], csv_parse),
+    Builtin::new("csv_stringify", &["rows"], csv_stringify),
+    Builtin::new("to_fixed", &["n", "digits"], to_fixed),
+    Builtin::new("repr", &["value"], repr),
+    Builtin::new("pprint", &["value"], pprint),
+    Builtin::new("sum", &["array"], sum),
+    Builtin::new("min", &["array"], min),
+    Builtin::new("max", &["array"], max),
+    Builtin::new("sorted", &["array"], sorted),
+    Builtin::new("reverse", &["array"], reverse),
+    Builtin::new("any", &["array", "predicate?"], any),
+    Builtin::new("all", &["array", "predicate?"], all),
+    Builtin::new("error", &["message"], error),
+    Builtin::new("is_error", &["value"], is_error),
+    Builtin::new("error_message", &["error"], error_message),
+    Builtin::new("panic", &["message"], panic),
+    Builtin::new("iter", &["source"], iter),
+    Builtin::new("take", &["source", "count"], take),
+    Builtin::new("skip", &["source", "count"], skip),
+    Builtin::new("map_lazy", &["source", "f"], map_lazy),
+    Builtin::new("collect", &["source"], collect),
+    Builtin::new("freeze", &["value"], freeze),
+    Builtin::new("is_frozen", &["value"], is_frozen),
+    Builtin::new("eval", &["source"], eval).needs("eval"),
+    Builtin::new("globals", &[], globals),
+    Builtin::new("locals", &[], locals),
+    Builtin::new("help", &["f"], help),
+    Builtin::new("builtin", &["name", "args..."], builtin),
+];
+
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    STANDARD.iter().find(|b| b.name == name)
+}
+
+fn print(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let mut vals = Vec::new();
+    for a in args {
+        vals.push(interp.eval_expr(a)?);
+    }
+    if let Some(v) = vals.first() {
+        let line = interp.locale.format_value(v);
+        interp.emit(line)?;
+    }
+    Ok(Value::Null)
+}
+
+fn format_number(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let n = match interp.eval_expr(&args[0])? {
+        Value::Number(n) => n,
+        _ => return Err("format_number() requires a number".to_string()),
+    };
+    let locale = match args.get(1) {
+        Some(a) => match interp.eval_expr(a)? {
+            Value::Str(name) => NumberLocale::from_name(&name).ok_or_else(|| format!("Unknown locale '{}'", name))?,
+            _ => return Err("format_number() locale must be a string".to_string()),
+        },
+        None => interp.locale,
+    };
+    Ok(Value::Str(locale.format_number(n)))
+}
+
+fn eval(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let Value::Str(source) = interp.eval_audited(&args[0])? else {
+        return Err("eval() requires a string".to_string());
+    };
+    let program = match parse_source(&source) {
+        Ok(program) => program,
+        Err(e) => return Ok(Value::Error(e)),
+    };
+    interp.eval_program(&program)
+}
+
+// Calls the builtin or host function `name` even when the script has
+// defined its own function with that name.
+fn builtin(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let target = match interp.eval_expr(&args[0])? {
+        Value::Str(target) => Symbol::intern(&target),
+        _ => return Err("builtin() requires the name of a builtin as a string".to_string()),
+    };
+    if target == "builtin" || !interp.is_builtin(target) {
+        return Err(format!("'{}' is not a builtin", target));
+    }
+    interp.call_host_or_builtin(target, &args[1..])
+}
+
+fn help(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    // A builtin the script has not replaced prints its signature.
+    if let Expr::Ident(name) = &args[0]
+        && !matches!(interp.lookup(*name), Some(Value::Function { .. }))
+        && let Some(b) = interp.builtins.get(name)
+    {
+        let text = format!("builtin {}", b.signature());
+        interp.emit(text)?;
+        return Ok(Value::Null);
+    }
+    let Value::Function { params, body } = interp.eval_expr(&args[0])? else {
+        return Err("help() requires a function".to_string());
+    };
+    let name = match &args[0] {
+        Expr::Ident(name) => name.as_str(),
+        _ => "<anonymous>",
+    };
+    let mut text = format!("fn {}({})", name, symbol::join(&params, ", "));
+    for line in docstring(&body).unwrap_or_default().lines() {
+        text.push('\n');
+        if !line.is_empty() {
+            text.push_str("  ");
+            text.push_str(line);
+        }
+    }
+    interp.emit(text)?;
+    Ok(Value::Null)
+}
+
+fn bindings(vars: Vec<(String, Value)>) -> Value {
+    let pairs = vars.into_iter().map(|(k, v)| Value::array(vec![Value::Str(k), v])).collect();
+    Value::array(pairs)
+}
+
+fn globals(interp: &mut Interpreter, _: &[Expr]) -> Result<Value, String> {
+    Ok(bindings(interp.variables()))
+}
+
+fn locals(interp: &mut Interpreter, _: &[Expr]) -> Result<Value, String> {
+    Ok(bindings(interp.locals()))
+}
+
+fn script_args(interp: &mut Interpreter, _: &[Expr]) -> Result<Value, String> {
+    let items = interp.script_args.iter().cloned().map(Value::Str).collect();
+    Ok(Value::array(items))
+}
+
+fn exit(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let code = match args.first() {
+        Some(a) => match interp.eval_audited(a)? {
+            Value::Number(n) => n as i32,
+            _ => return Err("exit() requires a number".to_string()),
+        },
+        None => 0,
+    };
+    // Unwinds like an error; run() turns it back into success.
+    interp.exit_code = Some(code);
+    Err(format!("exit({})", code))
+}
+
+fn input(interp: &mut Interpreter, _: &[Expr]) -> Result<Value, String> {
+    match interp.input.read_line() {
+        Ok(Some(line)) => Ok(Value::Str(line)),
+        Ok(None) => Ok(Value::Null),
+        Err(e) => Err(format!("Input error: {}", e)),
+    }
+}
+
+fn index_of(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let elems = match interp.eval_expr(&args[0])? {
+        Value::Array(elems) => elems,
+        _ => return Err("index_of() requires an array".to_string()),
+    };
+    let needle = interp.eval_expr(&args[1])?;
+    let elems = lock(&elems).to_vec();
+    let index = elems.iter().position(|e| Interpreter::values_equal(e, &needle));
+    Ok(Value::Number(index.map_or(-1.0, |i| i as f64)))
+}
+
+fn find(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    // A copy, since the predicate may modify the array.
+    let elems = match interp.eval_expr(&args[0])? {
+        Value::Array(elems) => lock(&elems).to_vec(),
+        _ => return Err("find() requires an array".to_string()),
+    };
+    let predicate = interp.eval_expr(&args[1])?;
+    if !matches!(predicate, Value::Function { .. }) {
+        return Err("find() requires a function".to_string());
+    }
+    for elem in elems {
+        if Interpreter::is_truthy(&interp.call_profiled(&args[1], predicate.clone(), vec![elem.clone()])?) {
+            return Ok(elem);
+        }
+    }
+    Ok(Value::Null)
+}
+
+// any() and all() stop at the first element that decides the answer.
+fn any_or_all(interp: &mut Interpreter, args: &[Expr], name: &str) -> Result<Value, String> {
+    let elems = match interp.eval_expr(&args[0])? {
+        Value::Array(elems) => lock(&elems).to_vec(),
+        _ => return Err(format!("{}() requires an array", name)),
+    };
+    let predicate = args.get(1).map(|a| interp.eval_expr(a)).transpose()?;
+    if predicate.as_ref().is_some_and(|p| !matches!(p, Value::Function { .. })) {
+        return Err(format!("{}() requires a function", name));
+    }
+    let want = name == "any";
+    for elem in elems {
+        let test = match &predicate {
+            Some(p) => interp.call_profiled(&args[1], p.clone(), vec![elem])?,
+            None => elem,
+        };
+        if Interpreter::is_truthy(&test) == want {
+            return Ok(Value::Bool(want));
+        }
+    }
+    Ok(Value::Bool(!want))
+}
+
+fn any(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    any_or_all(interp, args, "any")
+}
+
+fn all(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    any_or_all(interp, args, "all")
+}
+
+fn push(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let elems = match interp.eval_expr(&args[0])? {
+        Value::Array(elems) => elems,
+        _ => return Err("push() requires an array".to_string()),
+    };
+    let val = interp.eval_expr(&args[1])?;
+    if lock(&elems).frozen {
+        return Err("FrozenError: cannot push to a frozen array".to_string());
+    }
+    interp.check_alloc(|| {
+        Interpreter::heap_size(&lock(&elems)) + Interpreter::heap_size(std::slice::from_ref(&val))
+    })?;
+    lock(&elems).push(val);
+    Ok(Value::Null)
+}
+
+fn len(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    match interp.eval_expr(&args[0])? {
+        Value::Array(elems) => Ok(Value::Number(lock(&elems).len() as f64)),
+        Value::Str(s) => Ok(Value::Number(s.chars().count() as f64)),
+        _ => Err("len() requires array or string".to_string()),
+    }
+}
+
+fn slice(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let target = interp.eval_expr(&args[0])?;
+    let mut bounds = Vec::new();
+    for a in &args[1..] {
+        match interp.eval_expr(a)? {
+            // Negative bounds clamp to 0, like large ones to the length.
+            Value::Number(n) => bounds.push(n as usize),
+            _ => return Err("slice() bounds must be numbers".to_string()),
+        }
+    }
+    let start = bounds[0];
+    let end = bounds.get(1).copied().unwrap_or(usize::MAX);
+    match target {
+        Value::Array(elems) => {
+            let elems = lock(&elems);
+            let end = end.min(elems.len());
+            let part = elems[start.min(end)..end].to_vec();
+            interp.check_alloc(|| Interpreter::heap_size(&part))?;
+            Ok(Value::array(part))
+        }
+        Value::Str(s) => {
+            let part: String = s.chars().take(end).skip(start).collect();
+            Ok(Value::Str(part))
+        }
+        _ => Err("slice() requires array or string".to_string()),
+    }
+}
+
+fn panic(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let message = match interp.eval_expr(&args[0])? {
+        Value::Error(message) => message,
+        value => value.to_string(),
+    };
+    Err(format!("{} at line {}", message, interp.line))
+}
+
+fn assert(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    if Interpreter::is_truthy(&interp.eval_expr(&args[0])?) {
+        return Ok(Value::Null);
+    }
+    match args.get(1) {
+        Some(message) => Err(format!("Assertion failed: {}", interp.eval_expr(message)?)),
+        None => Err("Assertion failed".to_string()),
+    }
+}
+
+fn assert_eq(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let actual = interp.eval_expr(&args[0])?;
+    let expected = interp.eval_expr(&args[1])?;
+    if Interpreter::structurally_equal(&actual, &expected) {
+        return Ok(Value::Null);
+    }
+    Err(format!(
+        "Assertion failed: values differ\n  expected: {}\n    actual: {}",
+        expected.repr(),
+        actual.repr()
+    ))
+}
+
+fn bytes(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let s = match interp.eval_expr(&args[0])? {
+        Value::Str(s) => s,
+        _ => return Err("bytes() requires a string".to_string()),
+    };
+    let bytes: Vec<Value> = s.bytes().map(|b| Value::Number(b as f64)).collect();
+    interp.check_alloc(|| Interpreter::heap_size(&bytes))?;
+    Ok(Value::array(bytes))
+}
+
+fn run(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let cmd = match interp.eval_audited(&args[0])? {
+        Value::Str(s) => s,
+        _ => return Err("run() requires a command string".to_string()),
+    };
+    let cmd_args: Vec<String> = match args.get(1).map(|a| interp.eval_audited(a)).transpose()? {
+        None => Vec::new(),
+        Some(Value::Array(items)) => lock(&items)
+            .iter()
+            .map(|v| match v {
+                Value::Str(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect(),
+        Some(_) => return Err("run() requires an array of arguments".to_string()),
+    };
+    let output = std::process::Command::new(&cmd)
+        .args(&cmd_args)
+        .output()
+        .map_err(|e| format!("run(): cannot start '{}': {}", cmd, e))?;
+    let result = vec![
+        Value::Str(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Value::Str(String::from_utf8_lossy(&output.stderr).into_owned()),
+        // No exit code when the process was killed by a signal.
+        output.status.code().map_or(Value::Null, |c| Value::Number(c as f64)),
+    ];
+    interp.check_alloc(|| Interpreter::heap_size(&result))?;
+    Ok(Value::array(result))
+}
+
+fn now(_: &mut Interpreter, _: &[Expr]) -> Result<Value, String> {
+    Ok(Value::Number(datetime::now()))
+}
+
+fn date(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let ts = match args.first().map(|a| interp.eval_expr(a)).transpose()? {
+        None => datetime::now(),
+        Some(Value::Number(n)) => n,
+        Some(_) => return Err("date() requires a number".to_string()),
+    };
+    let d = DateTime::from_timestamp(ts);
+    let fields = [d.year as f64, d.month as f64, d.day as f64, d.hour as f64, d.minute as f64, d.second as f64, d.weekday as f64];
+    Ok(Value::array(fields.into_iter().map(Value::Number).collect()))
+}
+
+fn format_time(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    match (interp.eval_expr(&args[0])?, interp.eval_expr(&args[1])?) {
+        (Value::Number(ts), Value::Str(pattern)) => Ok(Value::Str(DateTime::from_timestamp(ts).format(&pattern)?)),
+        _ => Err("format_time() requires a number and a pattern string".to_string()),
+    }
+}
+
+fn parse_time(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    match interp.eval_expr(&args[0])? {
+        Value::Str(s) => Ok(datetime::parse_iso8601(&s).map_or(Value::Null, Value::Number)),
+        _ => Err("parse_time() requires a string".to_string()),
+    }
+}
+
+fn sum(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let Value::Array(items) = interp.eval_expr(&args[0])? else {
+        return Err("sum() requires an array".to_string());
+    };
+    let mut total = 0.0;
+    for item in lock(&items).iter() {
+        match item {
+            Value::Number(n) => total += n,
+            _ => return Err("sum() requires an array of numbers".to_string()),
+        }
+    }
+    Ok(Value::Number(total))
+}
+
+fn min_or_max(interp: &mut Interpreter, args: &[Expr], name: &str) -> Result<Value, String> {
+    let Value::Array(items) = interp.eval_expr(&args[0])? else {
+        return Err(format!("{}() requires an array", name));
+    };
+    let items = lock(&items);
+    let mut best: Option<&Value> = None;
+    for item in items.iter() {
+        let better = match (best, item) {
+            (None, Value::Number(_) | Value::Str(_)) => true,
+            (Some(Value::Number(b)), Value::Number(n)) => if name == "min" { n < b } else { n > b },
+            (Some(Value::Str(b)), Value::Str(s)) => if name == "min" { s < b } else { s > b },
+            _ => return Err(format!("{}() requires an array of numbers or of strings", name)),
+        };
+        if better {
+            best = Some(item);
+        }
+    }
+    Ok(best.cloned().unwrap_or(Value::Null))
+}
+
+fn min(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    min_or_max(interp, args, "min")
+}
+
+fn max(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    min_or_max(interp, args, "max")
+}
+
+fn sorted(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let Value::Array(items) = interp.eval_expr(&args[0])? else {
+        return Err("sorted() requires an array".to_string());
+    };
+    let mut items = lock(&items).to_vec();
+    if items.iter().all(|v| matches!(v, Value::Number(_))) {
+        items.sort_by(|a, b| match (a, b) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            _ => unreachable!(),
+        });
+    } else if items.iter().all(|v| matches!(v, Value::Str(_))) {
+        items.sort_by(|a, b| match (a, b) {
+            (Value::Str(a), Value::Str(b)) => a.cmp(b),
+            _ => unreachable!(),
+        });
+    } else {
+        return Err("sorted() requires an array of numbers or of strings".to_string());
+    }
+    Ok(Value::array(items))
+}
+
+fn reverse(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let Value::Array(items) = interp.eval_expr(&args[0])? else {
+        return Err("reverse() requires an array".to_string());
+    };
+    Ok(Value::array(lock(&items).iter().rev().cloned().collect()))
+}
+
+fn pprint(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let text = interp.eval_expr(&args[0])?.pretty(0, PPRINT_WIDTH);
+    interp.emit(text)?;
+    Ok(Value::Null)
+}
+
+fn error(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    match interp.eval_expr(&args[0])? {
+        Value::Str(message) => Ok(Value::Error(message)),
+        _ => Err("error() requires a string message".to_string()),
+    }
+}
+
+fn is_error(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(interp.eval_expr(&args[0])?, Value::Error(_))))
+}
+
+fn error_message(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    match interp.eval_expr(&args[0])? {
+        Value::Error(message) => Ok(Value::Str(message)),
+        _ => Err("error_message() requires an error".to_string()),
+    }
+}
+
+fn freeze(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    Ok(interp.eval_expr(&args[0])?.freeze())
+}
+
+fn is_frozen(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    Ok(Value::Bool(interp.eval_expr(&args[0])?.is_frozen()))
+}
+
+fn iter(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let source = interp.eval_expr(&args[0])?;
+    Ok(Value::Iterator(Interpreter::to_iter(source, "iter")?))
+}
+
+fn take_or_skip(interp: &mut Interpreter, args: &[Expr], name: &str) -> Result<Value, String> {
+    let inner = Interpreter::to_iter(interp.eval_expr(&args[0])?, name)?;
+    let n = match interp.eval_expr(&args[1])? {
+        Value::Number(n) if n >= 0.0 => n as usize,
+        _ => return Err(format!("{}() requires a non-negative count", name)),
+    };
+    let adapter = if name == "take" { LazyIter::Take(inner, n) } else { LazyIter::Skip(inner, n) };
+    Ok(Value::Iterator(Arc::new(Mutex::new(adapter))))
+}
+
+fn take(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    take_or_skip(interp, args, "take")
+}
+
+fn skip(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    take_or_skip(interp, args, "skip")
+}
+
+fn map_lazy(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let inner = Interpreter::to_iter(interp.eval_expr(&args[0])?, "map_lazy")?;
+    let func = interp.eval_expr(&args[1])?;
+    if !matches!(func, Value::Function { .. }) {
+        return Err("map_lazy() requires a function".to_string());
+    }
+    Ok(Value::Iterator(Arc::new(Mutex::new(LazyIter::Map(inner, func)))))
+}
+
+fn collect(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let it = Interpreter::to_iter(interp.eval_expr(&args[0])?, "collect")?;
+    let mut items = Vec::new();
+    // Kept as a running total so the check stays linear.
+    let mut size = 0;
+    while let Some(item) = interp.iter_next(&it)? {
+        size += Interpreter::heap_size(std::slice::from_ref(&item));
+        interp.check_alloc(|| size)?;
+        items.push(item);
+    }
+    Ok(Value::array(items))
+}
+
+fn repr(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    Ok(Value::Str(interp.eval_expr(&args[0])?.repr()))
+}
+
+fn to_fixed(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    match (interp.eval_expr(&args[0])?, interp.eval_expr(&args[1])?) {
+        (Value::Number(n), Value::Number(digits)) if (0.0..=100.0).contains(&digits) => {
+            Ok(Value::Str(format!("{:.*}", digits as usize, n)))
+        }
+        (Value::Number(_), Value::Number(_)) => Err("to_fixed() digits must be between 0 and 100".to_string()),
+        _ => Err("to_fixed() requires two numbers".to_string()),
+    }
+}
+
+fn csv_parse(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let text = match interp.eval_expr(&args[0])? {
+        Value::Str(s) => s,
+        _ => return Err("csv_parse() requires a string".to_string()),
+    };
+    let rows: Vec<Value> = csv::parse(&text)?
+        .into_iter()
+        .map(|row| Value::array(row.into_iter().map(Value::Str).collect()))
+        .collect();
+    interp.check_alloc(|| Interpreter::heap_size(&rows))?;
+    Ok(Value::array(rows))
+}
+
+fn csv_stringify(interp: &mut Interpreter, args: &[Expr]) -> Result<Value, String> {
+    let rows = match interp.eval_expr(&args[0])? {
+        Value::Array(rows) => rows,
+        _ => return Err("csv_stringify() requires an array of rows".to_string()),
+    };
+    let mut table = Vec::new();
+    let rows = lock(&rows).to_vec();
+    for row in rows.iter() {
+        let Value::Array(cells) = row else {
+            return Err("csv_stringify() rows must be arrays".to_string());
+        };
+        table.push(
+            lock(cells)
+                .iter()
+                .map(|cell| match cell {
+                    Value::Str(s) => s.clone(),
+                    Value::Null => String::new(),
+                    other => other.to_string(),
+                })
+                .collect(),
+        );
+    }
+    Ok(Value::Str(csv::stringify(&table)))
+}
@@ -1,3 +1,7 @@
+use std::fmt;
+
+use crate::symbol::Symbol;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     // Literals
@@ -5,7 +9,7 @@ pub enum Token {
     StringLit(String),
 
     // Identifier
-    Ident(String),
+    Ident(Symbol),
 
     // Keywords
     Let,
@@ -16,11 +20,15 @@ pub enum Token {
     For,
     In,
     Return,
+    Break,
+    Continue,
+    Global,
     True,
     False,
     And,
     Or,
     Not,
+    Is,
 
     // Operators
     Plus,
@@ -31,11 +39,21 @@ pub enum Token {
     Eq,
     EqEq,
     BangEq,
+    EqEqEq,
+    BangEqEq,
     Lt,
     LtEq,
     Gt,
     GtEq,
     DotDot,
+    Ellipsis,
+
+    // Trivia (only produced by Lexer::with_comments)
+    Comment(String),
+
+    // `## text`; the parser attaches it to the statement that follows.
+    // Lexer::with_comments returns these as plain comments.
+    DocComment(String),
 
     // Punctuation
     LParen,
@@ -45,192 +63,285 @@ pub enum Token {
     LBracket,
     RBracket,
     Comma,
+    Colon,
+    Question,
 
     // End of file
     Eof,
 }
 
-pub struct Lexer {
-    source: Vec<char>,
+pub const KEYWORDS: &[&str] = &[
+    "let", "fn", "if", "else", "while", "for", "in", "return", "true", "false", "and", "or",
+    "not", "is", "global", "break", "continue",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}", self.message, self.line)
+    }
+}
+
+impl From<LexError> for String {
+    fn from(e: LexError) -> String {
+        e.to_string()
+    }
+}
+
+// std has no XID_Start/XID_Continue tables; the Unicode Alphabetic and
+// Numeric properties they are derived from are close enough for names like
+// `café` or `αβ`.
+fn is_ident_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_'
+}
+
+fn is_ident_continue(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+// Walks the source by byte offset, so spans index the original `&str`
+// directly; `col` counts characters, not bytes.
+pub struct Lexer<'a> {
+    source: &'a str,
     pos: usize,
     line: usize,
+    col: usize,
+    keep_comments: bool,
+    // Skip past errors instead of stopping at the first one.
+    recover: bool,
+    // Set once Eof or an error has been produced.
+    finished: bool,
 }
 
-impl Lexer {
-    pub fn new(source: &str) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
         Lexer {
-            source: source.chars().collect(),
+            source,
             pos: 0,
             line: 1,
+            col: 1,
+            keep_comments: false,
+            recover: false,
+            finished: false,
+        }
+    }
+
+    pub fn with_comments(source: &'a str) -> Self {
+        Lexer {
+            keep_comments: true,
+            ..Lexer::new(source)
         }
     }
 
     pub fn tokenize(&mut self) -> Result<Vec<Token>, String> {
-        let mut tokens = Vec::new();
+        Ok(self
+            .tokenize_spanned()?
+            .into_iter()
+            .map(|t| t.token)
+            .collect())
+    }
 
-        loop {
-            self.skip_whitespace_and_comments();
+    pub fn tokenize_spanned(&mut self) -> Result<Vec<SpannedToken>, String> {
+        Ok(self.collect::<Result<Vec<_>, _>>()?)
+    }
 
-            if self.pos >= self.source.len() {
-                tokens.push(Token::Eof);
-                break;
+    // Lexes the whole source, skipping each bad character and collecting
+    // every error, for editors that want all problems at once.
+    pub fn tokenize_recovering(&mut self) -> (Vec<SpannedToken>, Vec<LexError>) {
+        self.recover = true;
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for item in self.by_ref() {
+            match item {
+                Ok(token) => tokens.push(token),
+                Err(e) => errors.push(e),
             }
+        }
+        (tokens, errors)
+    }
 
-            let ch = self.source[self.pos];
-
-            // Two-char tokens
-            if ch == '=' && self.peek_next() == Some('=') {
-                tokens.push(Token::EqEq);
-                self.pos += 2;
-            } else if ch == '!' && self.peek_next() == Some('=') {
-                tokens.push(Token::BangEq);
-                self.pos += 2;
-            } else if ch == '<' && self.peek_next() == Some('=') {
-                tokens.push(Token::LtEq);
-                self.pos += 2;
-            } else if ch == '>' && self.peek_next() == Some('=') {
-                tokens.push(Token::GtEq);
-                self.pos += 2;
-            } else if ch == '.' && self.peek_next() == Some('.') {
-                tokens.push(Token::DotDot);
-                self.pos += 2;
-            }
-            // Single-char tokens
-            else if ch == '=' {
-                tokens.push(Token::Eq);
-                self.pos += 1;
-            } else if ch == '+' {
-                tokens.push(Token::Plus);
-                self.pos += 1;
-            } else if ch == '-' {
-                tokens.push(Token::Minus);
-                self.pos += 1;
-            } else if ch == '*' {
-                tokens.push(Token::Star);
-                self.pos += 1;
-            } else if ch == '/' {
-                tokens.push(Token::Slash);
-                self.pos += 1;
-            } else if ch == '%' {
-                tokens.push(Token::Percent);
-                self.pos += 1;
-            } else if ch == '<' {
-                tokens.push(Token::Lt);
-                self.pos += 1;
-            } else if ch == '>' {
-                tokens.push(Token::Gt);
-                self.pos += 1;
-            } else if ch == '(' {
-                tokens.push(Token::LParen);
-                self.pos += 1;
-            } else if ch == ')' {
-                tokens.push(Token::RParen);
-                self.pos += 1;
-            } else if ch == '{' {
-                tokens.push(Token::LBrace);
-                self.pos += 1;
-            } else if ch == '}' {
-                tokens.push(Token::RBrace);
-                self.pos += 1;
-            } else if ch == '[' {
-                tokens.push(Token::LBracket);
-                self.pos += 1;
-            } else if ch == ']' {
-                tokens.push(Token::RBracket);
-                self.pos += 1;
-            } else if ch == ',' {
-                tokens.push(Token::Comma);
-                self.pos += 1;
-            }
-            // Number literals
-            else if ch.is_ascii_digit() {
-                tokens.push(self.read_number()?);
-            }
-            // String literals
-            else if ch == '"' {
-                tokens.push(self.read_string()?);
-            }
-            // Identifiers and keywords
-            else if ch.is_ascii_alphabetic() || ch == '_' {
-                tokens.push(self.read_ident());
-            } else {
-                return Err(format!("Unexpected character '{}' at line {}", ch, self.line));
-            }
+    fn error(&self, message: String) -> LexError {
+        LexError {
+            message,
+            line: self.line,
+            col: self.col,
         }
+    }
 
-        Ok(tokens)
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
     }
 
     fn peek_next(&self) -> Option<char> {
-        self.source.get(self.pos + 1).copied()
+        let mut chars = self.source[self.pos..].chars();
+        chars.next();
+        chars.next()
     }
 
-    fn skip_whitespace_and_comments(&mut self) {
-        while self.pos < self.source.len() {
-            let ch = self.source[self.pos];
+    // Consumes one character, keeping line and column up to date.
+    fn bump(&mut self) {
+        if let Some(ch) = self.peek() {
+            self.pos += ch.len_utf8();
             if ch == '\n' {
                 self.line += 1;
-                self.pos += 1;
-            } else if ch.is_ascii_whitespace() {
-                self.pos += 1;
-            } else if ch == '#' {
-                // Skip to end of line
-                while self.pos < self.source.len() && self.source[self.pos] != '\n' {
-                    self.pos += 1;
+                self.col = 1;
+            } else {
+                self.col += 1;
+            }
+        }
+    }
+
+    fn bump_while(&mut self, pred: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&pred) {
+            self.bump();
+        }
+    }
+
+    fn scan_token(&mut self, ch: char) -> Result<Token, LexError> {
+        // Three-char tokens
+        for (text, token) in [("===", Token::EqEqEq), ("!==", Token::BangEqEq), ("...", Token::Ellipsis)] {
+            if self.source[self.pos..].starts_with(text) {
+                self.bump();
+                self.bump();
+                self.bump();
+                return Ok(token);
+            }
+        }
+
+        // Two-char tokens
+        let two_char = match (ch, self.peek_next()) {
+            ('=', Some('=')) => Some(Token::EqEq),
+            ('!', Some('=')) => Some(Token::BangEq),
+            ('<', Some('=')) => Some(Token::LtEq),
+            ('>', Some('=')) => Some(Token::GtEq),
+            ('.', Some('.')) => Some(Token::DotDot),
+            _ => None,
+        };
+        if let Some(token) = two_char {
+            self.bump();
+            self.bump();
+            return Ok(token);
+        }
+
+        // Single-char tokens
+        let single = match ch {
+            '=' => Some(Token::Eq),
+            '+' => Some(Token::Plus),
+            '-' => Some(Token::Minus),
+            '*' => Some(Token::Star),
+            '/' => Some(Token::Slash),
+            '%' => Some(Token::Percent),
+            '<' => Some(Token::Lt),
+            '>' => Some(Token::Gt),
+            '(' => Some(Token::LParen),
+            ')' => Some(Token::RParen),
+            '{' => Some(Token::LBrace),
+            '}' => Some(Token::RBrace),
+            '[' => Some(Token::LBracket),
+            ']' => Some(Token::RBracket),
+            ',' => Some(Token::Comma),
+            ':' => Some(Token::Colon),
+            '?' => Some(Token::Question),
+            _ => None,
+        };
+        if let Some(token) = single {
+            self.bump();
+            return Ok(token);
+        }
+
+        // Comments (when kept as trivia)
+        if ch == '#' {
+            let start = self.pos + 1;
+            self.bump_while(|c| c != '\n');
+            let text = &self.source[start..self.pos];
+            return Ok(match text.strip_prefix('#') {
+                Some(doc) if !self.keep_comments => {
+                    Token::DocComment(doc.strip_prefix(' ').unwrap_or(doc).trim_end().to_string())
                 }
+                _ => Token::Comment(text.to_string()),
+            });
+        }
+        // Number literals
+        if ch.is_ascii_digit() {
+            self.read_number()
+        }
+        // String literals
+        else if ch == '"' {
+            self.read_string()
+        }
+        // Identifiers and keywords
+        else if is_ident_start(ch) {
+            Ok(self.read_ident())
+        } else {
+            Err(self.error(format!("Unexpected character '{}'", ch)))
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(ch) = self.peek() {
+            if ch.is_ascii_whitespace() {
+                self.bump();
+            } else if ch == '#' && !self.keep_comments && self.peek_next() != Some('#') {
+                // Skip to end of line
+                self.bump_while(|c| c != '\n');
             } else {
                 break;
             }
         }
     }
 
-    fn read_number(&mut self) -> Result<Token, String> {
+    fn read_number(&mut self) -> Result<Token, LexError> {
         let start = self.pos;
-        while self.pos < self.source.len() && self.source[self.pos].is_ascii_digit() {
-            self.pos += 1;
-        }
-        if self.pos < self.source.len() && self.source[self.pos] == '.' {
-            // Check it's not `..`
-            if self.peek_next() != Some('.') {
-                self.pos += 1; // consume '.'
-                while self.pos < self.source.len() && self.source[self.pos].is_ascii_digit() {
-                    self.pos += 1;
-                }
-            }
+        self.bump_while(|c| c.is_ascii_digit());
+        // Check it's not `..`
+        if self.peek() == Some('.') && self.peek_next() != Some('.') {
+            self.bump(); // consume '.'
+            self.bump_while(|c| c.is_ascii_digit());
         }
-        let text: String = self.source[start..self.pos].iter().collect();
+        let text = &self.source[start..self.pos];
         let num: f64 = text
             .parse()
-            .map_err(|_| format!("Invalid number '{}' at line {}", text, self.line))?;
+            .map_err(|_| self.error(format!("Invalid number '{}'", text)))?;
         Ok(Token::Number(num))
     }
 
-    fn read_string(&mut self) -> Result<Token, String> {
-        self.pos += 1; // skip opening quote
-        let mut s = String::new();
-        while self.pos < self.source.len() && self.source[self.pos] != '"' {
-            if self.source[self.pos] == '\n' {
-                self.line += 1;
-            }
-            s.push(self.source[self.pos]);
-            self.pos += 1;
-        }
-        if self.pos >= self.source.len() {
-            return Err(format!("Unterminated string at line {}", self.line));
+    fn read_string(&mut self) -> Result<Token, LexError> {
+        self.bump(); // skip opening quote
+        let start = self.pos;
+        self.bump_while(|c| c != '"');
+        if self.peek().is_none() {
+            return Err(self.error("Unterminated string".to_string()));
         }
-        self.pos += 1; // skip closing quote
+        let s = self.source[start..self.pos].to_string();
+        self.bump(); // skip closing quote
         Ok(Token::StringLit(s))
     }
 
     fn read_ident(&mut self) -> Token {
         let start = self.pos;
-        while self.pos < self.source.len()
-            && (self.source[self.pos].is_ascii_alphanumeric() || self.source[self.pos] == '_')
-        {
-            self.pos += 1;
-        }
-        let text: String = self.source[start..self.pos].iter().collect();
-        match text.as_str() {
+        self.bump_while(is_ident_continue);
+        let text = &self.source[start..self.pos];
+        match text {
             "let" => Token::Let,
             "fn" => Token::Fn,
             "if" => Token::If,
@@ -239,12 +350,56 @@ impl Lexer {
             "for" => Token::For,
             "in" => Token::In,
             "return" => Token::Return,
+            "global" => Token::Global,
+            "break" => Token::Break,
+            "continue" => Token::Continue,
             "true" => Token::True,
             "false" => Token::False,
             "and" => Token::And,
             "or" => Token::Or,
             "not" => Token::Not,
-            _ => Token::Ident(text),
+            "is" => Token::Is,
+            _ => Token::Ident(Symbol::intern(text)),
+        }
+    }
+}
+
+// Produces tokens one at a time, ending with Eof; after an error, nothing more.
+impl Iterator for Lexer<'_> {
+    type Item = Result<SpannedToken, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
         }
+        self.skip_whitespace_and_comments();
+
+        let start = self.pos;
+        let line = self.line;
+        let col = self.col;
+
+        let token = match self.peek() {
+            None => Token::Eof,
+            Some(ch) => match self.scan_token(ch) {
+                Ok(token) => token,
+                Err(e) => {
+                    if self.recover && self.pos == start {
+                        self.bump();
+                    }
+                    self.finished = !self.recover;
+                    return Some(Err(e));
+                }
+            },
+        };
+        self.finished = token == Token::Eof;
+        Some(Ok(SpannedToken {
+            token,
+            span: Span {
+                start,
+                end: self.pos,
+                line,
+                col,
+            },
+        }))
     }
 }
@@ -1,3 +1,34 @@
+pub mod symbol;
 pub mod lexer;
 pub mod parser;
+pub mod program;
+pub mod cache;
+pub mod ast;
 pub mod interpreter;
+pub mod dump;
+pub mod pool;
+pub mod formatter;
+pub mod locale;
+pub mod datetime;
+pub mod diagnostics;
+pub mod lint;
+pub mod repl;
+pub mod json;
+pub mod csv;
+pub mod ast_json;
+pub mod codegen;
+pub mod server;
+pub mod lsp;
+pub mod readline;
+pub mod style;
+pub mod completion;
+pub mod highlight;
+pub mod suggest;
+pub mod state;
+pub mod debugger;
+pub mod profile;
+pub mod testing;
+pub mod docgen;
+pub mod project;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use crate::ast::{Visitor, walk_expr, walk_stmt};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::parser::{BinOp, Expr, Stmt, StmtKind, parse_source};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "shadowed-variable",
+        description: "a `let`, loop variable or parameter hides a binding from an outer scope",
+    },
+    Rule {
+        id: "bool-comparison",
+        description: "comparison against a `true`/`false` literal",
+    },
+    Rule {
+        id: "empty-block",
+        description: "an `if`, `else`, loop or function body with no statements",
+    },
+    Rule {
+        id: "constant-condition",
+        description: "an `if` or `while` condition that is a literal",
+    },
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<&'static str, Level>,
+}
+
+impl LintConfig {
+    pub fn set(&mut self, rule: &str, level: Level) -> Result<(), String> {
+        match RULES.iter().find(|r| r.id == rule) {
+            Some(r) => {
+                self.levels.insert(r.id, level);
+                Ok(())
+            }
+            None => Err(format!("Unknown lint rule '{}'", rule)),
+        }
+    }
+
+    // Every rule but `rule` allowed, for running one check on its own.
+    pub fn only(rule: &str) -> Result<LintConfig, String> {
+        let mut config = LintConfig::default();
+        for r in RULES.iter().filter(|r| r.id != rule) {
+            config.set(r.id, Level::Allow)?;
+        }
+        config.set(rule, Level::Warn)?;
+        Ok(config)
+    }
+
+    pub fn level(&self, rule: &str) -> Level {
+        self.levels.get(rule).copied().unwrap_or(Level::Warn)
+    }
+}
+
+pub fn lint_source(source: &str, config: &LintConfig) -> Result<Vec<Diagnostic>, String> {
+    let program = parse_source(source)?;
+    Ok(lint(&program, config))
+}
+
+pub fn lint(program: &[Stmt], config: &LintConfig) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        config,
+        scopes: vec![Vec::new()],
+        line: 0,
+        diagnostics: Vec::new(),
+    };
+    linter.visit_block(program);
+    linter.diagnostics
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    // Names declared in each enclosing scope, with the line of each.
+    scopes: Vec<Vec<(String, usize)>>,
+    // Line of the statement being visited; expressions report against it.
+    line: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, code: &'static str, message: String) {
+        let severity = match self.config.level(code) {
+            Level::Allow => return,
+            Level::Warn => Severity::Warning,
+            Level::Deny => Severity::Error,
+        };
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code,
+            message,
+            line: self.line,
+        });
+    }
+
+    fn declare(&mut self, name: &str) {
+        let (current, outer) = self.scopes.split_last().unwrap();
+        let shadowed = outer.iter().rev().flatten().find(|(n, _)| n == name).map(|(_, line)| *line);
+        if let Some(line) = shadowed.filter(|_| !current.iter().any(|(n, _)| n == name)) {
+            self.report(
+                "shadowed-variable",
+                format!("'{}' shadows the variable declared at line {}, which keeps its own value", name, line),
+            );
+        }
+        self.scopes.last_mut().unwrap().push((name.to_string(), self.line));
+    }
+
+    fn block(&mut self, stmts: &[Stmt], what: &str, line: usize) {
+        if stmts.is_empty() {
+            self.line = line;
+            self.report("empty-block", format!("empty {}", what));
+        }
+        self.scopes.push(Vec::new());
+        self.visit_block(stmts);
+        self.scopes.pop();
+    }
+
+    fn condition(&mut self, cond: &Expr, keyword: &str) {
+        let constant = match cond {
+            // `while true` is the idiomatic infinite loop
+            Expr::Bool(true) => keyword != "while",
+            Expr::Bool(_) | Expr::Number(_) | Expr::StringLit(_) => true,
+            _ => false,
+        };
+        if constant {
+            self.report(
+                "constant-condition",
+                format!("'{}' condition is a constant", keyword),
+            );
+        }
+        self.visit_expr(cond);
+    }
+}
+
+impl Visitor for Linter<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let line = stmt.line;
+        self.line = line;
+        match &stmt.kind {
+            StmtKind::Let(name, expr) => {
+                self.visit_expr(expr);
+                self.declare(name);
+            }
+            StmtKind::LetArray(names, rest, expr) => {
+                self.visit_expr(expr);
+                for name in names.iter().chain(rest) {
+                    self.declare(name);
+                }
+            }
+            StmtKind::If(cond, body, else_body) => {
+                self.condition(cond, "if");
+                self.block(body, "'if' block", line);
+                if let Some(else_b) = else_body {
+                    self.block(else_b, "'else' block", line);
+                }
+            }
+            StmtKind::While(cond, body) => {
+                self.condition(cond, "while");
+                self.block(body, "'while' body", line);
+            }
+            StmtKind::For(var, start, end, body) => {
+                self.visit_expr(start);
+                self.visit_expr(end);
+                self.scopes.push(Vec::new());
+                self.declare(var);
+                self.block(body, "'for' body", line);
+                self.scopes.pop();
+            }
+            StmtKind::ForEach(var, iterable, body) => {
+                self.visit_expr(iterable);
+                self.scopes.push(Vec::new());
+                self.declare(var);
+                self.block(body, "'for' body", line);
+                self.scopes.pop();
+            }
+            StmtKind::Fn(name, params, body) => {
+                self.declare(name);
+                self.scopes.push(Vec::new());
+                for p in params {
+                    self.declare(p);
+                }
+                self.block(body, &format!("body in function '{}'", name), line);
+                self.scopes.pop();
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Binary(left, op, right) = expr
+            && matches!(op, BinOp::Eq | BinOp::Neq)
+            && (matches!(**left, Expr::Bool(_)) || matches!(**right, Expr::Bool(_)))
+        {
+            self.report(
+                "bool-comparison",
+                "comparison to a boolean literal; use the value (or 'not') directly".to_string(),
+            );
+        }
+        walk_expr(self, expr);
+    }
+}
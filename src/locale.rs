@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+
+use crate::interpreter::{Elements, Value, lock};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberLocale {
+    pub decimal_separator: char,
+    pub thousands_separator: Option<char>,
+}
+
+impl Default for NumberLocale {
+    // Plain formatting, identical to Value's Display impl.
+    fn default() -> Self {
+        NumberLocale {
+            decimal_separator: '.',
+            thousands_separator: None,
+        }
+    }
+}
+
+impl NumberLocale {
+    // Accepts a language tag such as "de" or "de-AT"; only the language part
+    // is used.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let lang = name.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        let (decimal, thousands) = match lang.as_str() {
+            "c" | "plain" => ('.', None),
+            "en" | "ja" | "zh" | "ko" => ('.', Some(',')),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" => (',', Some('.')),
+            "fr" | "ru" | "sv" | "pl" | "cs" | "fi" | "nb" | "uk" => (',', Some(' ')),
+            "gsw" | "rm" => ('.', Some('\'')),
+            _ => return None,
+        };
+        Some(NumberLocale {
+            decimal_separator: decimal,
+            thousands_separator: thousands,
+        })
+    }
+
+    pub fn format_number(&self, n: f64) -> String {
+        let plain = Value::Number(n).to_string();
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain.as_str()),
+        };
+        if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+            // inf / NaN
+            return plain;
+        }
+        if digits.contains('e') {
+            return plain.replace('.', &self.decimal_separator.to_string());
+        }
+        let (int_part, frac_part) = match digits.split_once('.') {
+            Some((i, f)) => (i, Some(f)),
+            None => (digits, None),
+        };
+
+        let mut out = String::from(sign);
+        for (i, c) in int_part.chars().enumerate() {
+            let remaining = int_part.len() - i;
+            if i > 0
+                && remaining % 3 == 0
+                && let Some(sep) = self.thousands_separator
+            {
+                out.push(sep);
+            }
+            out.push(c);
+        }
+        if let Some(frac) = frac_part {
+            out.push(self.decimal_separator);
+            out.push_str(frac);
+        }
+        out
+    }
+
+    pub fn format_value(&self, value: &Value) -> String {
+        self.format_nested(value, &mut Vec::new())
+    }
+
+    // `open` holds the arrays being printed, to show cycles as `[...]`.
+    fn format_nested(&self, value: &Value, open: &mut Vec<*const Mutex<Elements>>) -> String {
+        match value {
+            Value::Number(n) => self.format_number(*n),
+            Value::Array(elems) if open.contains(&Arc::as_ptr(elems)) => "[...]".to_string(),
+            Value::Array(elems) => {
+                open.push(Arc::as_ptr(elems));
+                let parts: Vec<String> = lock(elems)
+                    .iter()
+                    .map(|v| match v {
+                        Value::Str(_) | Value::Error(_) => v.repr(),
+                        v => self.format_nested(v, open),
+                    })
+                    .collect();
+                open.pop();
+                format!("[{}]", parts.join(", "))
+            }
+            _ => value.to_string(),
+        }
+    }
+}
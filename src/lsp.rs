@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::json::{self, Json};
+use crate::lexer::{Lexer, SpannedToken, Token};
+use crate::lint::{self, LintConfig};
+use crate::parser::{BinOp, Expr, Stmt, StmtKind, parse_source};
+use crate::symbol::{self, Symbol};
+
+// Positions are counted in chars rather than UTF-16 code units, which only
+// differs for characters outside the Basic Multilingual Plane.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DeclKind {
+    Let,
+    Fn,
+    Param,
+    For,
+}
+
+#[derive(Debug, Clone)]
+struct Decl {
+    name: Symbol,
+    kind: DeclKind,
+    token: SpannedToken,
+}
+
+fn obj(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+fn string(s: &str) -> Json {
+    Json::String(s.to_string())
+}
+
+fn position(line: usize, character: usize) -> Json {
+    obj(vec![
+        ("line", Json::Number(line as f64)),
+        ("character", Json::Number(character as f64)),
+    ])
+}
+
+// LSP range of a token; `span.line`/`col` are 1-based and, like LSP
+// characters here, count chars rather than bytes.
+fn token_range(source: &str, t: &SpannedToken) -> Json {
+    let len = source[t.span.start..t.span.end].chars().count();
+    obj(vec![
+        ("start", position(t.span.line - 1, t.span.col - 1)),
+        ("end", position(t.span.line - 1, t.span.col - 1 + len)),
+    ])
+}
+
+fn line_range(source: &str, line: usize) -> Json {
+    let index = line.saturating_sub(1);
+    let len = source.lines().nth(index).map_or(0, |l| l.chars().count());
+    obj(vec![
+        ("start", position(index, 0)),
+        ("end", position(index, len)),
+    ])
+}
+
+// Byte offset of an LSP position.
+fn offset_of(source: &str, line: usize, character: usize) -> usize {
+    let mut offset = 0;
+    for (i, text) in source.split('\n').enumerate() {
+        if i == line {
+            return offset + text.char_indices().nth(character).map_or(text.len(), |(at, _)| at);
+        }
+        offset += text.len() + 1;
+    }
+    offset
+}
+
+fn declarations(tokens: &[SpannedToken]) -> Vec<Decl> {
+    let mut decls = Vec::new();
+    let mut i = 0;
+    while i + 1 < tokens.len() {
+        let kind = match tokens[i].token {
+            Token::Let => Some(DeclKind::Let),
+            Token::Fn => Some(DeclKind::Fn),
+            Token::For => Some(DeclKind::For),
+            _ => None,
+        };
+        // `let [a, ...rest] = ...` declares every name in the brackets.
+        if kind == Some(DeclKind::Let) && tokens[i + 1].token == Token::LBracket {
+            i += 2;
+            while let Some(t) = tokens.get(i) {
+                match &t.token {
+                    Token::Ident(name) => decls.push(Decl {
+                        name: *name,
+                        kind: DeclKind::Let,
+                        token: t.clone(),
+                    }),
+                    Token::Comma | Token::Ellipsis => {}
+                    _ => break,
+                }
+                i += 1;
+            }
+            continue;
+        }
+        if let (Some(kind), Token::Ident(name)) = (kind, &tokens[i + 1].token) {
+            decls.push(Decl {
+                name: *name,
+                kind,
+                token: tokens[i + 1].clone(),
+            });
+            if kind == DeclKind::Fn && tokens.get(i + 2).is_some_and(|t| t.token == Token::LParen) {
+                i += 3;
+                while let Some(t) = tokens.get(i) {
+                    match &t.token {
+                        Token::Ident(param) => decls.push(Decl {
+                            name: *param,
+                            kind: DeclKind::Param,
+                            token: t.clone(),
+                        }),
+                        Token::Comma => {}
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+        }
+        i += 1;
+    }
+    decls
+}
+
+fn infer(expr: &Expr) -> &'static str {
+    match expr {
+        Expr::Number(_) | Expr::Unary(crate::parser::UnaryOp::Neg, _) => "number",
+        Expr::StringLit(_) => "string",
+        Expr::Bool(_) | Expr::Unary(crate::parser::UnaryOp::Not, _) => "bool",
+        Expr::Array(_) => "array",
+        Expr::Binary(_, op, _) => match op {
+            BinOp::Eq
+            | BinOp::Neq
+            | BinOp::StrictEq
+            | BinOp::StrictNeq
+            | BinOp::Is
+            | BinOp::IsNot
+            | BinOp::Lt
+            | BinOp::LtEq
+            | BinOp::Gt
+            | BinOp::GtEq => "bool",
+            BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod => "number",
+            _ => "unknown",
+        },
+        _ => "unknown",
+    }
+}
+
+fn find_stmt(stmts: &[Stmt], line: usize, name: Symbol) -> Option<&Stmt> {
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::Let(n, _)
+            | StmtKind::Fn(n, _, _)
+            | StmtKind::For(n, _, _, _)
+            | StmtKind::ForEach(n, _, _)
+                if stmt.line == line && *n == name =>
+            {
+                return Some(stmt);
+            }
+            _ => {}
+        }
+        let found = match &stmt.kind {
+            StmtKind::If(_, body, else_body) => {
+                find_stmt(body, line, name).or_else(|| else_body.as_deref().and_then(|b| find_stmt(b, line, name)))
+            }
+            StmtKind::While(_, body) | StmtKind::For(_, _, _, body) | StmtKind::ForEach(_, _, body) => {
+                find_stmt(body, line, name)
+            }
+            StmtKind::Fn(_, _, body) => find_stmt(body, line, name),
+            StmtKind::Labeled(_, inner) => find_stmt(std::slice::from_ref(inner), line, name),
+            _ => None,
+        };
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server::default()
+    }
+
+    // Handles one JSON-RPC message and returns the messages to send back.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        let result = match method {
+            "initialize" => obj(vec![
+                (
+                    "capabilities",
+                    obj(vec![
+                        ("textDocumentSync", Json::Number(1.0)),
+                        ("definitionProvider", Json::Bool(true)),
+                        ("hoverProvider", Json::Bool(true)),
+                    ]),
+                ),
+                ("serverInfo", obj(vec![("name", string("minilang"))])),
+            ]),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|d| d.get("text"))
+                    .or_else(|| match params.get("contentChanges") {
+                        Some(Json::Array(changes)) => changes.last().and_then(|c| c.get("text")),
+                        _ => None,
+                    })
+                    .and_then(Json::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                self.documents.insert(uri.clone(), text);
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return Vec::new();
+            }
+            "textDocument/definition" => self.definition(&uri, &params),
+            "textDocument/hover" => self.hover(&uri, &params),
+            "shutdown" => Json::Null,
+            _ => {
+                // Unknown notifications are ignored; unknown requests get an error.
+                return match id {
+                    Some(id) => vec![obj(vec![
+                        ("jsonrpc", string("2.0")),
+                        ("id", id),
+                        (
+                            "error",
+                            obj(vec![
+                                ("code", Json::Number(-32601.0)),
+                                ("message", string(&format!("Method not found: {}", method))),
+                            ]),
+                        ),
+                    ])],
+                    None => Vec::new(),
+                };
+            }
+        };
+        match id {
+            Some(id) => vec![obj(vec![("jsonrpc", string("2.0")), ("id", id), ("result", result)])],
+            None => Vec::new(),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Json {
+        let source = self.documents.get(uri).map(String::as_str).unwrap_or("");
+        let (_, lex_errors) = Lexer::new(source).tokenize_recovering();
+        let diagnostics = if !lex_errors.is_empty() {
+            lex_errors.iter().map(|e| Diagnostic::from_error(&e.to_string())).collect()
+        } else {
+            match parse_source(source) {
+                Ok(program) => lint::lint(&program, &LintConfig::default()),
+                Err(e) => vec![Diagnostic::from_error(&e)],
+            }
+        };
+        let items = diagnostics
+            .iter()
+            .map(|d| {
+                obj(vec![
+                    ("range", line_range(source, d.line)),
+                    (
+                        "severity",
+                        Json::Number(match d.severity {
+                            Severity::Error => 1.0,
+                            Severity::Warning => 2.0,
+                        }),
+                    ),
+                    ("code", string(d.code)),
+                    ("source", string("minilang")),
+                    ("message", string(&d.message)),
+                ])
+            })
+            .collect();
+        obj(vec![
+            ("jsonrpc", string("2.0")),
+            ("method", string("textDocument/publishDiagnostics")),
+            (
+                "params",
+                obj(vec![("uri", string(uri)), ("diagnostics", Json::Array(items))]),
+            ),
+        ])
+    }
+
+    // The identifier under the cursor and the declaration it refers to: the
+    // closest one before it, or else the first one in the file.
+    fn resolve(&self, uri: &str, params: &Json) -> Option<(String, Decl)> {
+        let source = self.documents.get(uri)?;
+        let pos = params.get("position")?;
+        let line = match pos.get("line")? {
+            Json::Number(n) => *n as usize,
+            _ => return None,
+        };
+        let character = match pos.get("character")? {
+            Json::Number(n) => *n as usize,
+            _ => return None,
+        };
+        let offset = offset_of(source, line, character);
+        let tokens = Lexer::new(source).tokenize_spanned().ok()?;
+        let name = tokens.iter().find_map(|t| match &t.token {
+            Token::Ident(name) if t.span.start <= offset && offset <= t.span.end => Some(*name),
+            _ => None,
+        })?;
+        let decls: Vec<Decl> = declarations(&tokens).into_iter().filter(|d| d.name == name).collect();
+        let decl = decls
+            .iter()
+            .rev()
+            .find(|d| d.token.span.start <= offset)
+            .or_else(|| decls.first())?
+            .clone();
+        Some((source.clone(), decl))
+    }
+
+    fn definition(&self, uri: &str, params: &Json) -> Json {
+        match self.resolve(uri, params) {
+            Some((source, decl)) => {
+                obj(vec![("uri", string(uri)), ("range", token_range(&source, &decl.token))])
+            }
+            None => Json::Null,
+        }
+    }
+
+    fn hover(&self, uri: &str, params: &Json) -> Json {
+        let (source, decl) = match self.resolve(uri, params) {
+            Some(found) => found,
+            None => return Json::Null,
+        };
+        let stmt = parse_source(&source)
+            .ok()
+            .and_then(|program| find_stmt(&program, decl.token.span.line, decl.name).cloned());
+        let text = match (decl.kind, stmt.map(|s| s.kind)) {
+            (DeclKind::Fn, Some(StmtKind::Fn(name, params, _))) => {
+                format!("fn {}({})", name, symbol::join(&params, ", "))
+            }
+            (DeclKind::Let, Some(StmtKind::Let(name, expr))) => format!("let {}: {}", name, infer(&expr)),
+            (DeclKind::For, Some(StmtKind::ForEach(name, iterable, _))) => {
+                let kind = if infer(&iterable) == "string" { "string" } else { "unknown" };
+                format!("{}: {} (loop variable)", name, kind)
+            }
+            (DeclKind::For, _) => format!("{}: number (loop variable)", decl.name),
+            (DeclKind::Param, _) => format!("{}: parameter", decl.name),
+            _ => decl.name.to_string(),
+        };
+        obj(vec![(
+            "contents",
+            obj(vec![
+                ("kind", string("markdown")),
+                ("value", string(&format!("```\n{}\n```", text))),
+            ]),
+        )])
+    }
+}
+
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+// Serves LSP over the given streams until `exit` or end of input.
+pub fn run(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut server = Server::new();
+    while let Some(body) = read_message(input)? {
+        let message = match json::parse(&body) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        if message.get("method").and_then(Json::as_str) == Some("exit") {
+            break;
+        }
+        for reply in server.handle(&message) {
+            write_message(output, &reply)?;
+        }
+    }
+    Ok(())
+}
@@ -1,104 +1,813 @@
-use std::io::{self, Write};
-
-use minilang::interpreter::Interpreter;
+use minilang::cache::ProgramCache;
+use minilang::codegen;
+use minilang::debugger;
+use minilang::docgen;
+use minilang::dump;
+use minilang::diagnostics::Severity;
+use minilang::formatter;
+use minilang::lint::{self, LintConfig};
+use minilang::interpreter::{Interpreter, InterpreterConfig, Output};
 use minilang::lexer::Lexer;
-use minilang::parser::Parser;
+use minilang::parser::{self, Parser, parse_source};
+use minilang::pool::InterpreterPool;
+use minilang::program::Program;
+use minilang::project::Project;
+use minilang::server;
+use minilang::repl;
+use minilang::style;
+use minilang::testing;
+
+use std::path::{Path, PathBuf};
+
+enum Mode {
+    Run,
+    Check,
+    Tokens,
+    AstTree,
+    AstSexp,
+    AstJson,
+}
+
+const USAGE: &str = "\
+Usage: minilang [command] [options]
+
+Commands:
+  run <file.ml> [args...]   run a script (the default when given a file)
+  run [<alias>] [args...]   in a directory with a minilang.toml, run its entry point or
+                            a script alias after loading its source dirs
+  repl [--init <file>]      start the interactive REPL (the default with no arguments),
+                            replaying a session saved with :save first
+  check <file.ml>...        lex and parse only; exit status 1 on errors
+  test <file.ml>...         run the test_* functions in each file; exit status 1 on failures
+  fmt [--check | --write] <file.ml>...
+  lint [--allow | --warn | --deny <rule>]... <file.ml>...
+  transpile [--target python] <file.ml>
+  doc [--html] <file.ml>    print the file's functions and their ## comments as Markdown or HTML
+  serve [--host <addr>] [--port <port>] [--step-limit <n>] [--memory-limit <bytes>]
+                            [--timeout <ms>]
+  lsp                       run a language server on stdin/stdout
+  debug <file.ml> [args...] run a script with breakpoints and stepping (type help at the prompt)
+  watch <file.ml> [args...] re-run a script (and its project's source dirs) whenever they change
+
+Run options:
+  -e, --eval <code>   run <code> instead of a file
+  --tokens            print the token stream
+  --ast[=sexp|=json]  print the parsed AST
+  --check             lex and parse only
+  --cache-dir <dir>   keep parsed copies of the script and its project's source
+                      files in <dir>, and reuse them while the source is unchanged
+  -i, --interactive   start the REPL after the script runs, with its definitions loaded
+  --strict-math       make division or modulo by zero and NaN results runtime errors
+  --strict-eq         make == and != between different types (except null) runtime errors
+  --strict-vars       make assigning a name that was never declared with let a runtime error
+  --profile[=folded]  print time spent per function to stderr, as a table or
+                      as folded stacks for flame graph tools
+
+Global options:
+  --no-color          disable colored output
+  -h, --help          show this help
+  -V, --version       show the version";
+
+// The AST printers, the linter, the transpiler and the cache all recurse
+// once per level of the tree, and the parser allows trees thousands of
+// levels deep (20000 chained operators), which is more than the default
+// main thread stack holds. The memory is only used when a script needs it.
+const MAIN_STACK: usize = 256 << 20;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        repl();
-        return;
+    let status = std::thread::Builder::new()
+        .stack_size(MAIN_STACK)
+        .spawn(run_main)
+        .expect("cannot start the main thread")
+        .join()
+        .unwrap_or(101);
+    std::process::exit(status);
+}
+
+fn run_main() -> i32 {
+    let mut args: Vec<String> = std::env::args().collect();
+    let script = script_args_start(&args);
+    let count = args.len();
+    let mut index = 0;
+    args.retain(|a| {
+        index += 1;
+        index > script || a != "--no-color"
+    });
+    if args.len() != count {
+        style::disable();
+    }
+    match args.get(1).map(String::as_str) {
+        Some("run") => run_command(&args[2..]),
+        Some("repl") => repl_command(&args[2..]),
+        Some("check") => check_command(&args[2..]),
+        Some("fmt") => fmt_command(&args[2..]),
+        Some("lint") => lint_command(&args[2..]),
+        Some("serve") => serve_command(&args[2..]),
+        Some("transpile") => transpile_command(&args[2..]),
+        Some("debug") => debug_command(&args[2..]),
+        Some("watch") => watch_command(&args[2..]),
+        Some("test") => test_command(&args[2..]),
+        Some("doc") => doc_command(&args[2..]),
+        Some("lsp") => {
+            let stdin = std::io::stdin();
+            match minilang::lsp::run(&mut stdin.lock(), &mut std::io::stdout()) {
+                Ok(()) => 0,
+                Err(e) => {
+                    eprintln!("{}", style::error(&format!("lsp: {}", e)));
+                    1
+                }
+            }
+        }
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            0
+        }
+        Some("-V" | "--version") => {
+            println!("minilang {}", env!("CARGO_PKG_VERSION"));
+            0
+        }
+        None => {
+            repl::run(None);
+            0
+        }
+        // `minilang <file.ml>` and `minilang -e <code>` keep working without `run`.
+        Some(_) => run_command(&args[1..]),
+    }
+}
+
+// Where the script's own arguments begin: after the program path (or
+// `-e <code>`) of `run`, `debug`, `watch` or a bare `minilang <file.ml>`.
+// Global flags such as `--no-color` are only looked for before that.
+fn script_args_start(args: &[String]) -> usize {
+    let mut runs_script = false;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--no-color" => {}
+            "run" | "debug" | "watch" if !runs_script => runs_script = true,
+            "repl" | "check" | "fmt" | "lint" | "serve" | "transpile" | "test" | "doc" | "lsp" | "help"
+                if !runs_script =>
+            {
+                break;
+            }
+            "-e" | "--eval" => return i + 2,
+            "--cache-dir" => i += 1,
+            arg if !arg.starts_with('-') => return i + 1,
+            _ => runs_script = true,
+        }
+        i += 1;
+    }
+    args.len()
+}
+
+fn run_command(args: &[String]) -> i32 {
+    let mut mode = Mode::Run;
+    let mut path = None;
+    let mut eval = None;
+    let mut script_args = Vec::new();
+    let mut profile = None;
+    let mut interactive = false;
+    let mut strict_math = false;
+    let mut strict_equality = false;
+    let mut strict_vars = false;
+    let mut cache = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            // Everything after the program belongs to the script.
+            _ if path.is_some() || eval.is_some() => script_args.push(arg.clone()),
+            "-e" | "--eval" => match iter.next() {
+                Some(code) => eval = Some(code.clone()),
+                None => {
+                    eprintln!("Option '{}' requires a program", arg);
+                    return 1;
+                }
+            },
+            "--check" => mode = Mode::Check,
+            "--cache-dir" => match iter.next() {
+                Some(dir) => cache = Some(ProgramCache::with_dir(dir)),
+                None => {
+                    eprintln!("Option '{}' requires a directory", arg);
+                    return 1;
+                }
+            },
+            "-i" | "--interactive" => interactive = true,
+            "--strict-math" => strict_math = true,
+            "--strict-eq" => strict_equality = true,
+            "--strict-vars" => strict_vars = true,
+            "--tokens" => mode = Mode::Tokens,
+            "--ast" => mode = Mode::AstTree,
+            "--ast=sexp" => mode = Mode::AstSexp,
+            "--ast=json" => mode = Mode::AstJson,
+            "--profile" | "--profile=table" | "--profile=folded" => profile = Some(arg.ends_with("folded")),
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option '{}'", arg);
+                return 1;
+            }
+            _ => path = Some(arg.clone()),
+        }
+    }
+
+    let mut libraries = Vec::new();
+    if eval.is_none() {
+        match project_target(path.as_deref()) {
+            Ok(Some((target, files))) => {
+                path = Some(target);
+                libraries = files;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("{}", style::error(&e));
+                return 1;
+            }
+        }
+    }
+
+    let source = match (eval, &path) {
+        (Some(code), _) => code,
+        (None, Some(path)) => match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("{}", style::error(&format!("Error reading file '{}': {}", path, e)));
+                return 1;
+            }
+        },
+        (None, None) => {
+            eprintln!("Usage: minilang run <file.ml> [args...]");
+            return 1;
+        }
+    };
+
+    let cached = match (&mode, &mut cache) {
+        (Mode::Run, Some(cache)) => cache.get(&source),
+        _ => None,
+    };
+    let program = match cached {
+        Some(program) => program,
+        None => {
+            let mut lexer = Lexer::new(&source);
+            let tokens = match lexer.tokenize_spanned() {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", style::error(&format!("Lexer error: {}", e)));
+                    return 1;
+                }
+            };
+
+            if let Mode::Tokens = mode {
+                print!("{}", dump::tokens(&tokens));
+                return 0;
+            }
+
+            let mut parser = Parser::with_spans(tokens);
+            let program = match parser.parse_program() {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{}", style::error(&format!("Parse error: {}", e)));
+                    return 1;
+                }
+            };
+
+            match mode {
+                Mode::Check => return 0,
+                Mode::AstTree => {
+                    print!("{}", dump::ast_tree(&program));
+                    return 0;
+                }
+                Mode::AstSexp => {
+                    print!("{}", dump::ast_sexp(&program));
+                    return 0;
+                }
+                Mode::AstJson => {
+                    println!("{}", parser::to_json(&program));
+                    return 0;
+                }
+                Mode::Run | Mode::Tokens => {}
+            }
+
+            let program = Program::new(program);
+            if let Some(cache) = &mut cache {
+                cache.insert(&source, &program);
+            }
+            program
+        }
+    };
+
+    // A `let` that hides an outer variable is a common surprise for people
+    // learning the language, so `run` warns about it before running.
+    let shadowing = LintConfig::only("shadowed-variable").expect("rule exists");
+    for d in lint::lint(program.stmts(), &shadowing) {
+        eprintln!("{}", style::warning(&format!("{}: {}", path.as_deref().unwrap_or("-e"), d)));
+    }
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Output::Stdout);
+    interpreter.set_args(script_args);
+    interpreter.set_strict_math(strict_math);
+    interpreter.set_strict_equality(strict_equality);
+    interpreter.set_strict_vars(strict_vars);
+    if profile.is_some() {
+        interpreter.enable_profiling();
+    }
+    if let Err(e) = load_libraries(&mut interpreter, &libraries, cache.as_mut()) {
+        eprintln!("{}", style::error(&e));
+        return 1;
+    }
+    let result = interpreter.run_program(&program);
+    // Reported even when the script fails, since slow code may be why.
+    if let (Some(folded), Some(report)) = (profile, interpreter.profile()) {
+        eprint!("{}", if folded { report.folded() } else { report.table() });
+    }
+    if let Err(e) = &result {
+        eprintln!("{}", style::error(&format!("Runtime error: {}", e)));
+    }
+    // Like `python -i`, a failed script still leaves its definitions to
+    // inspect; only an explicit exit() skips the REPL.
+    if interactive && interpreter.exit_code().is_none() {
+        repl::run_with(interpreter, None);
+        return 0;
+    }
+    if result.is_err() {
+        return 1;
     }
+    interpreter.exit_code().unwrap_or(0)
+}
 
-    let source = match std::fs::read_to_string(&args[1]) {
+// In a project, `minilang run` runs the entry point and `minilang run
+// <alias>` one of its scripts, after every file in the source dirs. Returns
+// the file to run and those source files, or None outside a project or when
+// `path` is an existing file.
+fn project_target(path: Option<&str>) -> Result<Option<(String, Vec<PathBuf>)>, String> {
+    if path.is_some_and(|p| Path::new(p).is_file()) {
+        return Ok(None);
+    }
+    let dir = std::env::current_dir().map_err(|e| e.to_string())?;
+    let Some(project) = Project::find(&dir)? else {
+        return Ok(None);
+    };
+    let Some(target) = project.target(path) else {
+        return Ok(None);
+    };
+    Ok(Some((target.display().to_string(), project.source_files(target)?)))
+}
+
+fn load_libraries(
+    interpreter: &mut Interpreter,
+    files: &[PathBuf],
+    mut cache: Option<&mut ProgramCache>,
+) -> Result<(), String> {
+    for file in files {
+        let source = std::fs::read_to_string(file)
+            .map_err(|e| format!("Error reading file '{}': {}", file.display(), e))?;
+        let program = match cache.as_deref_mut() {
+            Some(cache) => cache.parse(&source),
+            None => Program::parse(&source),
+        }
+        .map_err(|e| format!("Parse error: {}: {}", file.display(), e))?;
+        interpreter
+            .run_program(&program)
+            .map_err(|e| format!("Runtime error: {}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+fn watch_command(args: &[String]) -> i32 {
+    let Some(path) = args.first().filter(|a| !a.starts_with('-')) else {
+        eprintln!("Usage: minilang watch <file.ml> [args...]");
+        return 1;
+    };
+    let mut last = None;
+    loop {
+        let (target, libraries) = match project_target(Some(path)) {
+            Ok(found) => found.unwrap_or_else(|| (path.clone(), Vec::new())),
+            Err(e) => {
+                eprintln!("{}", style::error(&e));
+                return 1;
+            }
+        };
+        let mut files = libraries.clone();
+        files.push(PathBuf::from(&target));
+        // Modification times of every file, so a save anywhere re-runs.
+        let stamps: Vec<_> = files
+            .iter()
+            .map(|f| (f.clone(), std::fs::metadata(f).and_then(|m| m.modified()).ok()))
+            .collect();
+        if last.as_ref() != Some(&stamps) {
+            last = Some(stamps);
+            watch_run(&target, &libraries, &args[1..]);
+            println!("[watching {} for changes, Ctrl+C to stop]", target);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+}
+
+fn watch_run(path: &str, libraries: &[PathBuf], args: &[String]) {
+    if std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+        print!("\x1b[2J\x1b[H");
+    }
+    let source = match std::fs::read_to_string(path) {
         Ok(s) => s,
         Err(e) => {
-            eprintln!("Error reading file '{}': {}", args[1], e);
-            std::process::exit(1);
+            eprintln!("{}", style::error(&format!("Error reading file '{}': {}", path, e)));
+            return;
         }
     };
-
-    let mut lexer = Lexer::new(&source);
-    let tokens = match lexer.tokenize() {
-        Ok(t) => t,
+    let program = match parse_source(&source) {
+        Ok(p) => p,
         Err(e) => {
-            eprintln!("Lexer error: {}", e);
-            std::process::exit(1);
+            eprintln!("{}", style::error(&format!("Parse error: {}", e)));
+            return;
         }
     };
+    for d in lint::lint(&program, &LintConfig::default()) {
+        let text = format!("{}: {}", path, d);
+        if d.severity == Severity::Error {
+            eprintln!("{}", style::error(&text));
+        } else {
+            eprintln!("{}", style::warning(&text));
+        }
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Output::Stdout);
+    interpreter.set_args(args.to_vec());
+    let result = load_libraries(&mut interpreter, libraries, None)
+        .and_then(|()| interpreter.run(&program).map_err(|e| format!("Runtime error: {}", e)));
+    if let Err(e) = result {
+        eprintln!("{}", style::error(&e));
+    }
+}
 
-    let mut parser = Parser::new(tokens);
-    let program = match parser.parse_program() {
+fn debug_command(args: &[String]) -> i32 {
+    let Some(path) = args.first().filter(|a| !a.starts_with('-')) else {
+        eprintln!("Usage: minilang debug <file.ml> [args...]");
+        return 1;
+    };
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", style::error(&format!("Error reading file '{}': {}", path, e)));
+            return 1;
+        }
+    };
+    let program = match parse_source(&source) {
         Ok(p) => p,
         Err(e) => {
-            eprintln!("Parse error: {}", e);
-            std::process::exit(1);
+            eprintln!("{}", style::error(&format!("Parse error: {}", e)));
+            return 1;
         }
     };
 
     let mut interpreter = Interpreter::new();
-    if let Err(e) = interpreter.run(&program) {
-        eprintln!("Runtime error: {}", e);
-        std::process::exit(1);
+    interpreter.set_output(Output::Stdout);
+    interpreter.set_args(args[1..].to_vec());
+    let input = Box::new(std::io::BufReader::new(std::io::stdin()));
+    if let Err(e) = debugger::run(&mut interpreter, &program, &source, input, Box::new(std::io::stdout())) {
+        eprintln!("{}", style::error(&format!("Runtime error: {}", e)));
+        return 1;
     }
+    interpreter.exit_code().unwrap_or(0)
+}
 
-    for line in &interpreter.output {
-        println!("{}", line);
+fn doc_command(args: &[String]) -> i32 {
+    let (html, path) = match args {
+        [flag, path] if flag == "--html" => (true, path),
+        [path] if !path.starts_with('-') => (false, path),
+        _ => {
+            eprintln!("Usage: minilang doc [--html] <file.ml>");
+            return 1;
+        }
+    };
+    let program = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading file '{}': {}", path, e))
+        .and_then(|source| parse_source(&source).map_err(|e| format!("{}: {}", path, e)));
+    match program {
+        Ok(program) if html => print!("{}", docgen::html(path, &program)),
+        Ok(program) => print!("{}", docgen::markdown(path, &program)),
+        Err(e) => {
+            eprintln!("{}", style::error(&e));
+            return 1;
+        }
     }
+    0
 }
 
-fn repl() {
-    println!("minilang REPL (Ctrl+Z to exit)");
-    let stdin = io::stdin();
-    let mut interpreter = Interpreter::new();
-    let mut line = String::new();
-
-    loop {
-        print!(">> ");
-        io::stdout().flush().unwrap();
-
-        line.clear();
-        match stdin.read_line(&mut line) {
-            Ok(0) => break,
-            Ok(_) => {}
+fn test_command(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("Usage: minilang test <file.ml>...");
+        return 1;
+    }
+    let (mut passed, mut failed) = (0, 0);
+    for path in args {
+        let results = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading file '{}': {}", path, e))
+            .and_then(|source| testing::run_tests(&source).map_err(|e| format!("{}: {}", path, e)));
+        let results = match results {
+            Ok(r) => r,
             Err(e) => {
-                eprintln!("Read error: {}", e);
-                break;
+                eprintln!("{}", style::error(&e));
+                failed += 1;
+                continue;
+            }
+        };
+        for test in results {
+            match test.error {
+                None => {
+                    println!("test {}::{} ... ok", path, test.name);
+                    passed += 1;
+                }
+                Some(e) => {
+                    println!("test {}::{} ... {}", path, test.name, style::error("FAILED"));
+                    for line in e.lines() {
+                        println!("    {}", line);
+                    }
+                    for line in &test.output {
+                        println!("    | {}", line);
+                    }
+                    failed += 1;
+                }
             }
         }
+    }
+    println!("\n{} passed, {} failed", passed, failed);
+    if failed > 0 { 1 } else { 0 }
+}
 
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+fn check_command(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("Usage: minilang check <file.ml>...");
+        return 1;
+    }
+    let mut status = 0;
+    for path in args {
+        if path.starts_with('-') {
+            eprintln!("Unknown option '{}'", path);
+            return 1;
+        }
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading file '{}': {}", path, e))
+            .and_then(|source| parse_source(&source).map_err(|e| format!("{}: {}", path, e)));
+        if let Err(e) = result {
+            eprintln!("{}", style::error(&e));
+            status = 1;
         }
+    }
+    status
+}
 
-        let mut lexer = Lexer::new(trimmed);
-        let tokens = match lexer.tokenize() {
-            Ok(t) => t,
+fn fmt_command(args: &[String]) -> i32 {
+    let mut check = false;
+    let mut write = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--write" | "-w" => write = true,
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option '{}'", arg);
+                return 1;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("Usage: minilang fmt [--check | --write] <file.ml>...");
+        return 1;
+    }
+
+    let mut status = 0;
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(s) => s,
             Err(e) => {
-                eprintln!("Lexer error: {}", e);
+                eprintln!("{}", style::error(&format!("Error reading file '{}': {}", path, e)));
+                status = 1;
+                continue;
+            }
+        };
+        let formatted = match formatter::format_source(&source) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                status = 1;
+                continue;
+            }
+        };
+        if check {
+            if formatted != source {
+                eprintln!("{}: not formatted", path);
+                status = 1;
+            }
+        } else if write {
+            if formatted != source
+                && let Err(e) = std::fs::write(path, &formatted)
+            {
+                eprintln!("Error writing file '{}': {}", path, e);
+                status = 1;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    status
+}
+
+fn lint_command(args: &[String]) -> i32 {
+    let mut config = LintConfig::default();
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let level = match arg.as_str() {
+            "--allow" => lint::Level::Allow,
+            "--warn" => lint::Level::Warn,
+            "--deny" => lint::Level::Deny,
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option '{}'", arg);
+                return 1;
+            }
+            _ => {
+                paths.push(arg);
                 continue;
             }
         };
+        let rule = match iter.next() {
+            Some(r) => r,
+            None => {
+                eprintln!("Option '{}' requires a rule name", arg);
+                return 1;
+            }
+        };
+        if let Err(e) = config.set(rule, level) {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("Usage: minilang lint [--allow | --warn | --deny <rule>]... <file.ml>...");
+        return 1;
+    }
 
-        let mut parser = Parser::new(tokens);
-        let stmts = match parser.parse_program() {
-            Ok(p) => p,
+    let mut status = 0;
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(s) => s,
             Err(e) => {
-                eprintln!("Parse error: {}", e);
+                eprintln!("{}", style::error(&format!("Error reading file '{}': {}", path, e)));
+                status = 1;
                 continue;
             }
         };
+        match lint::lint_source(&source, &config) {
+            Ok(diagnostics) => {
+                for d in &diagnostics {
+                    let text = format!("{}: {}", path, d);
+                    if d.severity == Severity::Error {
+                        eprintln!("{}", style::error(&text));
+                        status = 1;
+                    } else {
+                        eprintln!("{}", style::warning(&text));
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                status = 1;
+            }
+        }
+    }
+    status
+}
 
-        let prev_len = interpreter.output.len();
-        if let Err(e) = interpreter.run(&stmts) {
-            eprintln!("Runtime error: {}", e);
-            continue;
+fn transpile_command(args: &[String]) -> i32 {
+    let mut target = "python".to_string();
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--target" => match iter.next() {
+                Some(t) => target = t.clone(),
+                None => {
+                    eprintln!("Option '--target' requires a value");
+                    return 1;
+                }
+            },
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option '{}'", arg);
+                return 1;
+            }
+            _ => path = Some(arg),
         }
+    }
+    let path = match path {
+        Some(p) => p,
+        None => {
+            eprintln!("Usage: minilang transpile [--target python] <file.ml>");
+            return 1;
+        }
+    };
 
-        for line in &interpreter.output[prev_len..] {
-            println!("{}", line);
+    let result = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading file '{}': {}", path, e))
+        .and_then(|source| parse_source(&source))
+        .and_then(|program| match target.as_str() {
+            "python" => codegen::python::transpile(&program),
+            _ => Err(format!("Unknown target '{}'", target)),
+        });
+    match result {
+        Ok(code) => {
+            print!("{}", code);
+            0
         }
+        Err(e) => {
+            eprintln!("{}", style::error(&e));
+            1
+        }
+    }
+}
+
+fn repl_command(args: &[String]) -> i32 {
+    match args {
+        [] => repl::run(None),
+        [flag, path] if flag == "--init" => repl::run(Some(path)),
+        [flag] if flag == "--init" => {
+            eprintln!("--init requires a file");
+            return 1;
+        }
+        _ => {
+            eprintln!("Unexpected argument '{}'", args[0]);
+            return 1;
+        }
+    }
+    0
+}
+
+fn serve_command(args: &[String]) -> i32 {
+    let mut host = "127.0.0.1".to_string();
+    let mut port = "8080".to_string();
+    let mut step_limit = String::new();
+    let mut memory_limit = String::new();
+    let mut timeout = String::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let target = match arg.as_str() {
+            "--host" => &mut host,
+            "--port" => &mut port,
+            "--step-limit" => &mut step_limit,
+            "--memory-limit" => &mut memory_limit,
+            "--timeout" => &mut timeout,
+            _ => {
+                eprintln!("Unknown option '{}'", arg);
+                return 1;
+            }
+        };
+        match iter.next() {
+            Some(v) => *target = v.clone(),
+            None => {
+                eprintln!("Option '{}' requires a value", arg);
+                return 1;
+            }
+        }
+    }
+
+    let limits = [&step_limit, &memory_limit, &timeout].map(|v| parse_limit(v));
+    let (step_limit, memory_limit, timeout) = match limits {
+        [Ok(steps), Ok(bytes), Ok(ms)] => (steps, bytes, ms),
+        _ => {
+            for e in limits.into_iter().filter_map(Result::err) {
+                eprintln!("{}", e);
+            }
+            return 1;
+        }
+    };
+
+    let addr = format!("{}:{}", host, port);
+    let listener = match std::net::TcpListener::bind(&addr) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Cannot listen on {}: {}", addr, e);
+            return 1;
+        }
+    };
+    // The actual address, in case the port was 0.
+    let addr = listener.local_addr().map_or(addr, |a| a.to_string());
+    eprintln!("Listening on http://{}", addr);
+    let mut pool = InterpreterPool::new(4);
+    pool.set_config(InterpreterConfig::sandboxed());
+    pool.set_step_limit(step_limit.unwrap_or(server::DEFAULT_STEP_LIMIT));
+    pool.set_memory_limit(memory_limit.map_or(server::DEFAULT_MEMORY_LIMIT, |bytes| bytes as usize));
+    pool.set_timeout(timeout.map_or(server::DEFAULT_TIMEOUT, std::time::Duration::from_millis));
+    if let Err(e) = server::serve(listener, &pool) {
+        eprintln!("Server error: {}", e);
+        return 1;
+    }
+    0
+}
+
+fn parse_limit(value: &str) -> Result<Option<u64>, String> {
+    if value.is_empty() {
+        return Ok(None);
     }
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("Invalid limit '{}'", value))
 }
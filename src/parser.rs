@@ -1,25 +1,32 @@
-use crate::lexer::Token;
+use std::sync::Arc;
 
-#[derive(Debug, Clone)]
+use crate::lexer::{LexError, Lexer, Span, SpannedToken, Token};
+use crate::symbol::Symbol;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Number(f64),
     StringLit(String),
     Bool(bool),
-    Ident(String),
+    Ident(Symbol),
     Array(Vec<Expr>),
     Index(Box<Expr>, Box<Expr>),
     Call(Box<Expr>, Vec<Expr>),
     Unary(UnaryOp, Box<Expr>),
     Binary(Box<Expr>, BinOp, Box<Expr>),
+    // `...xs`, only as an array literal element or call argument.
+    Spread(Box<Expr>),
+    // `expr?`: an error value returns from the enclosing function.
+    Try(Box<Expr>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum UnaryOp {
     Neg,
     Not,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BinOp {
     Add,
     Sub,
@@ -28,6 +35,10 @@ pub enum BinOp {
     Mod,
     Eq,
     Neq,
+    StrictEq,
+    StrictNeq,
+    Is,
+    IsNot,
     Lt,
     LtEq,
     Gt,
@@ -36,37 +47,264 @@ pub enum BinOp {
     Or,
 }
 
-#[derive(Debug, Clone)]
-pub enum Stmt {
-    Let(String, Expr),
-    Assign(String, Expr),
-    IndexAssign(String, Expr, Expr),
+const UNARY_SYMBOLS: &[(UnaryOp, &str)] = &[(UnaryOp::Neg, "-"), (UnaryOp::Not, "not")];
+
+const BIN_SYMBOLS: &[(BinOp, &str)] = &[
+    (BinOp::Add, "+"),
+    (BinOp::Sub, "-"),
+    (BinOp::Mul, "*"),
+    (BinOp::Div, "/"),
+    (BinOp::Mod, "%"),
+    (BinOp::Eq, "=="),
+    (BinOp::Neq, "!="),
+    (BinOp::StrictEq, "==="),
+    (BinOp::StrictNeq, "!=="),
+    (BinOp::Is, "is"),
+    (BinOp::IsNot, "is not"),
+    (BinOp::Lt, "<"),
+    (BinOp::LtEq, "<="),
+    (BinOp::Gt, ">"),
+    (BinOp::GtEq, ">="),
+    (BinOp::And, "and"),
+    (BinOp::Or, "or"),
+];
+
+impl UnaryOp {
+    pub fn symbol(&self) -> &'static str {
+        UNARY_SYMBOLS.iter().find(|(op, _)| op == self).unwrap().1
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<UnaryOp> {
+        UNARY_SYMBOLS.iter().find(|(_, s)| *s == symbol).map(|(op, _)| op.clone())
+    }
+}
+
+impl BinOp {
+    pub fn symbol(&self) -> &'static str {
+        BIN_SYMBOLS.iter().find(|(op, _)| op == self).unwrap().1
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<BinOp> {
+        BIN_SYMBOLS.iter().find(|(_, s)| *s == symbol).map(|(op, _)| op.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub line: usize,
+    // The `##` comment lines just before the statement, joined with newlines.
+    pub doc: Option<String>,
+}
+
+// A string literal opening a function body is its docstring. Lines are
+// trimmed, since continuation lines are usually indented with the body.
+pub fn docstring(body: &[Stmt]) -> Option<String> {
+    match body.first().map(|stmt| &stmt.kind) {
+        Some(StmtKind::ExprStmt(Expr::StringLit(s))) => {
+            Some(s.trim().lines().map(str::trim).collect::<Vec<_>>().join("\n"))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    Let(Symbol, Expr),
+    // `let [a, b, ...rest] = expr`
+    LetArray(Vec<Symbol>, Option<Symbol>, Expr),
+    Assign(Symbol, Expr),
+    // `a, b = b, a`: every value is evaluated before any name is assigned.
+    MultiAssign(Vec<Symbol>, Vec<Expr>),
+    IndexAssign(Symbol, Expr, Expr),
     If(Expr, Vec<Stmt>, Option<Vec<Stmt>>),
     While(Expr, Vec<Stmt>),
-    For(String, Expr, Expr, Vec<Stmt>),
-    Fn(String, Vec<String>, Vec<Stmt>),
+    For(Symbol, Expr, Expr, Vec<Stmt>),
+    ForEach(Symbol, Expr, Vec<Stmt>),
+    // The body is shared with the function values created from it.
+    Fn(Symbol, Vec<Symbol>, Arc<[Stmt]>),
     Return(Option<Expr>),
+    // `label: while ...` or `label: for ...`; the statement is always a loop.
+    Labeled(Symbol, Box<Stmt>),
+    // Both carry the label of the loop they target, if one was named.
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
+    // `global x, y` inside a function: those names refer to the top-level
+    // bindings for the rest of the call.
+    Global(Vec<Symbol>),
     ExprStmt(Expr),
 }
 
-pub struct Parser {
-    tokens: Vec<Token>,
-    pos: usize,
+pub use crate::ast_json::{from_json, to_json};
+
+pub fn parse_source(source: &str) -> Result<Vec<Stmt>, String> {
+    Parser::from_lexer(Lexer::new(source)).parse_program()
 }
 
-impl Parser {
+type TokenStream<'a> = Box<dyn Iterator<Item = Result<SpannedToken, LexError>> + 'a>;
+
+pub struct Parser<'a> {
+    tokens: TokenStream<'a>,
+    current: SpannedToken,
+    // False for Parser::new, whose tokens have no positions to report.
+    spanned: bool,
+    // A lexer error ends the token stream; it is reported in place of the
+    // parse error its Eof would otherwise cause.
+    lex_error: Option<LexError>,
+    // Doc comment lines seen just before the current token.
+    doc: Option<String>,
+    depth: usize,
+    max_depth: usize,
+    // Operators folded into chains like `a + b + c` or `f()[0]` along the
+    // current path; each one makes the tree a level deeper.
+    links: usize,
+    max_chain: usize,
+    // Labels of the loops enclosing the current statement, innermost last;
+    // a function body starts with none.
+    loops: Vec<Option<Symbol>>,
+}
+
+// How deeply blocks, parentheses, brackets and unary operators may nest
+// before parsing fails instead of overflowing the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 200;
+
+// How many binary, call, index and `?` operators may be chained along one
+// path through an expression. Chains do not use the parser's stack, but
+// everything that walks the tree afterwards recurses once per operator.
+pub const DEFAULT_MAX_CHAIN: usize = 20_000;
+
+impl<'a> Parser<'a> {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, pos: 0 }
+        let tokens = tokens.into_iter().map(|token| {
+            Ok(SpannedToken {
+                token,
+                span: Span::default(),
+            })
+        });
+        Parser::from_stream(Box::new(tokens), false)
+    }
+
+    pub fn with_spans(tokens: Vec<SpannedToken>) -> Self {
+        Parser::from_stream(Box::new(tokens.into_iter().map(Ok)), true)
+    }
+
+    // Pulls tokens from the lexer as the parser needs them.
+    pub fn from_lexer(lexer: Lexer<'a>) -> Self {
+        Parser::from_stream(Box::new(lexer), true)
+    }
+
+    fn from_stream(tokens: TokenStream<'a>, spanned: bool) -> Self {
+        let mut parser = Parser {
+            tokens,
+            current: SpannedToken {
+                token: Token::Eof,
+                span: Span::default(),
+            },
+            spanned,
+            lex_error: None,
+            doc: None,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            links: 0,
+            max_chain: DEFAULT_MAX_CHAIN,
+            loops: Vec::new(),
+        };
+        parser.advance();
+        parser
+    }
+
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    pub fn set_max_chain(&mut self, operators: usize) {
+        self.max_chain = operators;
+    }
+
+    // Operators chained inside the nested part end with it.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth >= self.max_depth {
+            return Err(self.error(format!("Nesting exceeds the limit of {} levels", self.max_depth)));
+        }
+        self.depth += 1;
+        let links = self.links;
+        let result = parse(self);
+        self.links = links;
+        self.depth -= 1;
+        result
+    }
+
+    fn link(&mut self) -> Result<(), String> {
+        if self.links >= self.max_chain {
+            return Err(self.error(format!("Expression exceeds the limit of {} chained operators", self.max_chain)));
+        }
+        self.links += 1;
+        Ok(())
+    }
+
+    // Appends the line of the current token when spans are available.
+    fn error(&self, msg: String) -> String {
+        self.error_at(self.current.span, msg)
+    }
+
+    fn error_at(&self, span: Span, msg: String) -> String {
+        if let Some(e) = &self.lex_error {
+            return e.to_string();
+        }
+        if self.spanned {
+            format!("{} at line {}", msg, span.line)
+        } else {
+            msg
+        }
     }
 
     fn peek(&self) -> &Token {
-        &self.tokens[self.pos]
+        &self.current.token
+    }
+
+    // Pulls the next token, gathering the doc comments in front of it into
+    // `self.doc`.
+    fn next_token(&mut self) -> SpannedToken {
+        let eof = |span| SpannedToken {
+            token: Token::Eof,
+            span,
+        };
+        let mut doc: Option<String> = None;
+        let next = loop {
+            match self.tokens.next() {
+                Some(Ok(SpannedToken {
+                    token: Token::DocComment(text),
+                    ..
+                })) => match &mut doc {
+                    Some(lines) => {
+                        lines.push('\n');
+                        lines.push_str(&text);
+                    }
+                    None => doc = Some(text),
+                },
+                other => break other,
+            }
+        };
+        self.doc = doc;
+        match next {
+            Some(Ok(token)) => token,
+            Some(Err(e)) => {
+                let span = Span {
+                    line: e.line,
+                    col: e.col,
+                    ..Span::default()
+                };
+                self.lex_error = Some(e);
+                eof(span)
+            }
+            None => eof(self.current.span),
+        }
     }
 
+    // Moves the current token out and pulls the next one from the stream.
     fn advance(&mut self) -> Token {
-        let tok = self.tokens[self.pos].clone();
-        self.pos += 1;
-        tok
+        let next = self.next_token();
+        std::mem::replace(&mut self.current, next).token
     }
 
     fn expect(&mut self, expected: &Token) -> Result<(), String> {
@@ -74,7 +312,18 @@ impl Parser {
             self.advance();
             Ok(())
         } else {
-            Err(format!("Expected {:?}, got {:?}", expected, self.peek()))
+            Err(self.error(format!("Expected {:?}, got {:?}", expected, self.peek())))
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> Result<Symbol, String> {
+        match self.peek() {
+            Token::Ident(n) => {
+                let n = *n;
+                self.advance();
+                Ok(n)
+            }
+            t => Err(self.error(format!("Expected {}, got {:?}", what, t))),
         }
     }
 
@@ -83,84 +332,125 @@ impl Parser {
         while *self.peek() != Token::Eof {
             stmts.push(self.parse_stmt()?);
         }
-        Ok(stmts)
+        match self.lex_error.take() {
+            Some(e) => Err(e.into()),
+            None => Ok(stmts),
+        }
+    }
+
+    fn current_line(&self) -> usize {
+        self.current.span.line
     }
 
     fn parse_stmt(&mut self) -> Result<Stmt, String> {
-        match self.peek() {
+        let line = self.current_line();
+        let doc = self.doc.take();
+        let kind = match self.peek() {
             Token::Let => self.parse_let(),
             Token::If => self.parse_if(),
-            Token::While => self.parse_while(),
-            Token::For => self.parse_for(),
+            Token::While => self.parse_while(None),
+            Token::For => self.parse_for(None),
             Token::Fn => self.parse_fn(),
             Token::Return => self.parse_return(),
+            Token::Global => self.parse_global(),
+            Token::Break | Token::Continue => self.parse_jump(),
             Token::Ident(_) => {
                 // Could be assign, index assign, or expr stmt
                 self.parse_assign_or_expr()
             }
             _ => {
                 let expr = self.parse_expr()?;
-                Ok(Stmt::ExprStmt(expr))
+                Ok(StmtKind::ExprStmt(expr))
             }
-        }
+        }?;
+        Ok(Stmt { kind, line, doc })
     }
 
-    fn parse_let(&mut self) -> Result<Stmt, String> {
+    fn parse_let(&mut self) -> Result<StmtKind, String> {
         self.advance(); // consume 'let'
-        let name = match self.advance() {
-            Token::Ident(n) => n,
-            t => return Err(format!("Expected identifier after 'let', got {:?}", t)),
-        };
+        if *self.peek() == Token::LBracket {
+            return self.parse_let_array();
+        }
+        let name = self.expect_ident("identifier after 'let'")?;
         self.expect(&Token::Eq)?;
         let expr = self.parse_expr()?;
-        Ok(Stmt::Let(name, expr))
+        Ok(StmtKind::Let(name, expr))
     }
 
-    fn parse_assign_or_expr(&mut self) -> Result<Stmt, String> {
-        let name = if let Token::Ident(n) = self.peek() {
-            n.clone()
-        } else {
-            let expr = self.parse_expr()?;
-            return Ok(Stmt::ExprStmt(expr));
-        };
+    fn parse_let_array(&mut self) -> Result<StmtKind, String> {
+        self.advance(); // consume '['
+        let mut names = Vec::new();
+        let mut rest = None;
+        loop {
+            if *self.peek() == Token::Ellipsis {
+                self.advance();
+                rest = Some(self.expect_ident("name after '...'")?);
+                break;
+            }
+            names.push(self.expect_ident("name in 'let [...]'")?);
+            if *self.peek() != Token::Comma {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(&Token::RBracket)?;
+        self.expect(&Token::Eq)?;
+        let expr = self.parse_expr()?;
+        Ok(StmtKind::LetArray(names, rest, expr))
+    }
 
-        // Look ahead for `=` or `[`
-        match &self.tokens[self.pos + 1] {
-            Token::Eq => {
-                self.advance(); // consume ident
+    // Parses an expression, then turns it into an assignment if `=` follows
+    // and the expression is `name` or `name[index]`.
+    fn parse_assign_or_expr(&mut self) -> Result<StmtKind, String> {
+        let target = self.parse_expr()?;
+        if *self.peek() == Token::Comma {
+            return self.parse_multi_assign(target);
+        }
+        if let (Expr::Ident(label), Token::Colon) = (&target, self.peek()) {
+            let label = *label;
+            return self.parse_labeled(label);
+        }
+        if *self.peek() != Token::Eq {
+            return Ok(StmtKind::ExprStmt(target));
+        }
+        match target {
+            Expr::Ident(name) => {
                 self.advance(); // consume '='
-                let expr = self.parse_expr()?;
-                Ok(Stmt::Assign(name, expr))
+                Ok(StmtKind::Assign(name, self.parse_expr()?))
             }
-            Token::LBracket => {
-                // Check if it's index assign: ident '[' expr ']' '='
-                // We need to check further ahead, but let's try parsing as index assign
-                // and fall back to expr stmt
-                let saved = self.pos;
-                self.advance(); // consume ident
-                self.advance(); // consume '['
-                let index_expr = self.parse_expr()?;
-                if *self.peek() == Token::RBracket {
-                    self.advance(); // consume ']'
-                    if *self.peek() == Token::Eq {
-                        self.advance(); // consume '='
-                        let value = self.parse_expr()?;
-                        return Ok(Stmt::IndexAssign(name, index_expr, value));
-                    }
+            Expr::Index(array, index) => match *array {
+                Expr::Ident(name) => {
+                    self.advance(); // consume '='
+                    Ok(StmtKind::IndexAssign(name, *index, self.parse_expr()?))
                 }
-                // Not an index assign, backtrack and parse as expr stmt
-                self.pos = saved;
-                let expr = self.parse_expr()?;
-                Ok(Stmt::ExprStmt(expr))
-            }
-            _ => {
-                let expr = self.parse_expr()?;
-                Ok(Stmt::ExprStmt(expr))
-            }
+                _ => Err(self.error("Invalid assignment target".to_string())),
+            },
+            _ => Err(self.error("Invalid assignment target".to_string())),
+        }
+    }
+
+    fn parse_multi_assign(&mut self, first: Expr) -> Result<StmtKind, String> {
+        let Expr::Ident(first) = first else {
+            return Err(self.error("Only names can be assigned together with ','".to_string()));
+        };
+        let mut names = vec![first];
+        while *self.peek() == Token::Comma {
+            self.advance();
+            names.push(self.expect_ident("name to assign")?);
+        }
+        self.expect(&Token::Eq)?;
+        let mut values = vec![self.parse_expr()?];
+        while *self.peek() == Token::Comma {
+            self.advance();
+            values.push(self.parse_expr()?);
+        }
+        if values.len() != names.len() {
+            return Err(self.error(format!("Expected {} values after '=', got {}", names.len(), values.len())));
         }
+        Ok(StmtKind::MultiAssign(names, values))
     }
 
-    fn parse_if(&mut self) -> Result<Stmt, String> {
+    fn parse_if(&mut self) -> Result<StmtKind, String> {
         self.advance(); // consume 'if'
         let cond = self.parse_expr()?;
         let body = self.parse_block()?;
@@ -170,78 +460,127 @@ impl Parser {
         } else {
             None
         };
-        Ok(Stmt::If(cond, body, else_body))
+        Ok(StmtKind::If(cond, body, else_body))
+    }
+
+    fn parse_labeled(&mut self, label: Symbol) -> Result<StmtKind, String> {
+        self.advance(); // consume ':'
+        if self.loops.contains(&Some(label)) {
+            return Err(self.error(format!("Label '{}' is already used by an enclosing loop", label)));
+        }
+        let line = self.current_line();
+        let kind = match self.peek() {
+            Token::While => self.parse_while(Some(label))?,
+            Token::For => self.parse_for(Some(label))?,
+            t => return Err(self.error(format!("Expected a loop after label '{}:', got {:?}", label, t))),
+        };
+        Ok(StmtKind::Labeled(label, Box::new(Stmt { kind, line, doc: None })))
+    }
+
+    // `break` or `continue`, with an optional label on the same line.
+    fn parse_jump(&mut self) -> Result<StmtKind, String> {
+        let line = self.current_line();
+        let is_break = self.advance() == Token::Break;
+        let keyword = if is_break { "break" } else { "continue" };
+        let label = match self.peek() {
+            Token::Ident(label) if self.current_line() == line => {
+                let label = *label;
+                self.advance();
+                Some(label)
+            }
+            _ => None,
+        };
+        match label {
+            None if self.loops.is_empty() => return Err(self.error(format!("'{}' outside a loop", keyword))),
+            Some(l) if !self.loops.contains(&label) => {
+                return Err(self.error(format!("Unknown loop label '{}' in '{}'", l, keyword)));
+            }
+            _ => {}
+        }
+        Ok(if is_break { StmtKind::Break(label) } else { StmtKind::Continue(label) })
     }
 
-    fn parse_while(&mut self) -> Result<Stmt, String> {
+    fn parse_loop_body(&mut self, label: Option<Symbol>) -> Result<Vec<Stmt>, String> {
+        self.loops.push(label);
+        let body = self.parse_block();
+        self.loops.pop();
+        body
+    }
+
+    fn parse_while(&mut self, label: Option<Symbol>) -> Result<StmtKind, String> {
         self.advance(); // consume 'while'
         let cond = self.parse_expr()?;
-        let body = self.parse_block()?;
-        Ok(Stmt::While(cond, body))
+        let body = self.parse_loop_body(label)?;
+        Ok(StmtKind::While(cond, body))
     }
 
-    fn parse_for(&mut self) -> Result<Stmt, String> {
+    fn parse_for(&mut self, label: Option<Symbol>) -> Result<StmtKind, String> {
         self.advance(); // consume 'for'
-        let var = match self.advance() {
-            Token::Ident(n) => n,
-            t => return Err(format!("Expected identifier after 'for', got {:?}", t)),
-        };
+        let var = self.expect_ident("identifier after 'for'")?;
         self.expect(&Token::In)?;
         let start = self.parse_expr()?;
-        self.expect(&Token::DotDot)?;
+        // Without `..` this loops over the elements of an array or string.
+        if *self.peek() != Token::DotDot {
+            let body = self.parse_loop_body(label)?;
+            return Ok(StmtKind::ForEach(var, start, body));
+        }
+        self.advance();
         let end = self.parse_expr()?;
-        let body = self.parse_block()?;
-        Ok(Stmt::For(var, start, end, body))
+        let body = self.parse_loop_body(label)?;
+        Ok(StmtKind::For(var, start, end, body))
     }
 
-    fn parse_fn(&mut self) -> Result<Stmt, String> {
+    fn parse_fn(&mut self) -> Result<StmtKind, String> {
         self.advance(); // consume 'fn'
-        let name = match self.advance() {
-            Token::Ident(n) => n,
-            t => return Err(format!("Expected function name, got {:?}", t)),
-        };
+        let name = self.expect_ident("function name")?;
         self.expect(&Token::LParen)?;
         let mut params = Vec::new();
         if *self.peek() != Token::RParen {
-            match self.advance() {
-                Token::Ident(p) => params.push(p),
-                t => return Err(format!("Expected parameter name, got {:?}", t)),
-            }
+            params.push(self.expect_ident("parameter name")?);
             while *self.peek() == Token::Comma {
                 self.advance();
-                match self.advance() {
-                    Token::Ident(p) => params.push(p),
-                    t => return Err(format!("Expected parameter name, got {:?}", t)),
-                }
+                params.push(self.expect_ident("parameter name")?);
             }
         }
         self.expect(&Token::RParen)?;
-        let body = self.parse_block()?;
-        Ok(Stmt::Fn(name, params, body))
+        let loops = std::mem::take(&mut self.loops);
+        let body = self.parse_block();
+        self.loops = loops;
+        Ok(StmtKind::Fn(name, params, body?.into()))
+    }
+
+    fn parse_global(&mut self) -> Result<StmtKind, String> {
+        self.advance(); // consume 'global'
+        let mut names = vec![self.expect_ident("name after 'global'")?];
+        while *self.peek() == Token::Comma {
+            self.advance();
+            names.push(self.expect_ident("name after 'global'")?);
+        }
+        Ok(StmtKind::Global(names))
     }
 
-    fn parse_return(&mut self) -> Result<Stmt, String> {
+    fn parse_return(&mut self) -> Result<StmtKind, String> {
         self.advance(); // consume 'return'
         // If the next token could start an expression, parse it
         let expr = match self.peek() {
             Token::RBrace | Token::Eof => None,
             _ => Some(self.parse_expr()?),
         };
-        Ok(Stmt::Return(expr))
+        Ok(StmtKind::Return(expr))
     }
 
     fn parse_block(&mut self) -> Result<Vec<Stmt>, String> {
         self.expect(&Token::LBrace)?;
         let mut stmts = Vec::new();
         while *self.peek() != Token::RBrace {
-            stmts.push(self.parse_stmt()?);
+            stmts.push(self.nested(Self::parse_stmt)?);
         }
         self.expect(&Token::RBrace)?;
         Ok(stmts)
     }
 
     fn parse_expr(&mut self) -> Result<Expr, String> {
-        self.parse_logic()
+        self.nested(Self::parse_logic)
     }
 
     fn parse_logic(&mut self) -> Result<Expr, String> {
@@ -253,6 +592,7 @@ impl Parser {
                 _ => unreachable!(),
             };
             let right = self.parse_equality()?;
+            self.link()?;
             left = Expr::Binary(Box::new(left), op, Box::new(right));
         }
         Ok(left)
@@ -260,13 +600,22 @@ impl Parser {
 
     fn parse_equality(&mut self) -> Result<Expr, String> {
         let mut left = self.parse_compare()?;
-        while matches!(self.peek(), Token::EqEq | Token::BangEq) {
+        while matches!(self.peek(), Token::EqEq | Token::BangEq | Token::EqEqEq | Token::BangEqEq | Token::Is) {
             let op = match self.advance() {
                 Token::EqEq => BinOp::Eq,
                 Token::BangEq => BinOp::Neq,
+                Token::EqEqEq => BinOp::StrictEq,
+                Token::BangEqEq => BinOp::StrictNeq,
+                // `a is not b` negates the check rather than comparing with `not b`.
+                Token::Is if *self.peek() == Token::Not => {
+                    self.advance();
+                    BinOp::IsNot
+                }
+                Token::Is => BinOp::Is,
                 _ => unreachable!(),
             };
             let right = self.parse_compare()?;
+            self.link()?;
             left = Expr::Binary(Box::new(left), op, Box::new(right));
         }
         Ok(left)
@@ -283,6 +632,7 @@ impl Parser {
                 _ => unreachable!(),
             };
             let right = self.parse_term()?;
+            self.link()?;
             left = Expr::Binary(Box::new(left), op, Box::new(right));
         }
         Ok(left)
@@ -297,6 +647,7 @@ impl Parser {
                 _ => unreachable!(),
             };
             let right = self.parse_factor()?;
+            self.link()?;
             left = Expr::Binary(Box::new(left), op, Box::new(right));
         }
         Ok(left)
@@ -312,6 +663,7 @@ impl Parser {
                 _ => unreachable!(),
             };
             let right = self.parse_unary()?;
+            self.link()?;
             left = Expr::Binary(Box::new(left), op, Box::new(right));
         }
         Ok(left)
@@ -321,12 +673,12 @@ impl Parser {
         match self.peek() {
             Token::Minus => {
                 self.advance();
-                let expr = self.parse_unary()?;
+                let expr = self.nested(Self::parse_unary)?;
                 Ok(Expr::Unary(UnaryOp::Neg, Box::new(expr)))
             }
             Token::Not => {
                 self.advance();
-                let expr = self.parse_unary()?;
+                let expr = self.nested(Self::parse_unary)?;
                 Ok(Expr::Unary(UnaryOp::Not, Box::new(expr)))
             }
             _ => self.parse_call(),
@@ -336,15 +688,18 @@ impl Parser {
     fn parse_call(&mut self) -> Result<Expr, String> {
         let mut expr = self.parse_primary()?;
         loop {
+            if matches!(self.peek(), Token::LParen | Token::LBracket | Token::Question) {
+                self.link()?;
+            }
             match self.peek() {
                 Token::LParen => {
                     self.advance(); // consume '('
                     let mut args = Vec::new();
                     if *self.peek() != Token::RParen {
-                        args.push(self.parse_expr()?);
+                        args.push(self.parse_item()?);
                         while *self.peek() == Token::Comma {
                             self.advance();
-                            args.push(self.parse_expr()?);
+                            args.push(self.parse_item()?);
                         }
                     }
                     self.expect(&Token::RParen)?;
@@ -356,54 +711,52 @@ impl Parser {
                     self.expect(&Token::RBracket)?;
                     expr = Expr::Index(Box::new(expr), Box::new(index));
                 }
+                Token::Question => {
+                    self.advance();
+                    expr = Expr::Try(Box::new(expr));
+                }
                 _ => break,
             }
         }
         Ok(expr)
     }
 
+    // An array element or call argument, which may be spread.
+    fn parse_item(&mut self) -> Result<Expr, String> {
+        if *self.peek() == Token::Ellipsis {
+            self.advance();
+            return Ok(Expr::Spread(Box::new(self.parse_expr()?)));
+        }
+        self.parse_expr()
+    }
+
     fn parse_primary(&mut self) -> Result<Expr, String> {
-        match self.peek().clone() {
-            Token::Number(n) => {
-                self.advance();
-                Ok(Expr::Number(n))
-            }
-            Token::StringLit(s) => {
-                self.advance();
-                Ok(Expr::StringLit(s))
-            }
-            Token::True => {
-                self.advance();
-                Ok(Expr::Bool(true))
-            }
-            Token::False => {
-                self.advance();
-                Ok(Expr::Bool(false))
-            }
-            Token::Ident(name) => {
-                self.advance();
-                Ok(Expr::Ident(name))
-            }
+        let span = self.current.span;
+        match self.advance() {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::StringLit(s) => Ok(Expr::StringLit(s)),
+            Token::True => Ok(Expr::Bool(true)),
+            Token::False => Ok(Expr::Bool(false)),
+            Token::Ident(name) => Ok(Expr::Ident(name)),
             Token::LBracket => {
-                self.advance(); // consume '['
                 let mut elems = Vec::new();
                 if *self.peek() != Token::RBracket {
-                    elems.push(self.parse_expr()?);
+                    elems.push(self.parse_item()?);
                     while *self.peek() == Token::Comma {
                         self.advance();
-                        elems.push(self.parse_expr()?);
+                        elems.push(self.parse_item()?);
                     }
                 }
                 self.expect(&Token::RBracket)?;
                 Ok(Expr::Array(elems))
             }
             Token::LParen => {
-                self.advance(); // consume '('
                 let expr = self.parse_expr()?;
                 self.expect(&Token::RParen)?;
                 Ok(expr)
             }
-            t => Err(format!("Unexpected token {:?}", t)),
+            // Reported at the unexpected token, not the one after it.
+            t => Err(self.error_at(span, format!("Unexpected token {:?}", t))),
         }
     }
 }
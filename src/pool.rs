@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::interpreter::{Interpreter, InterpreterConfig, Value, lock};
+use crate::json::Json;
+use crate::program::Program;
+use crate::symbol::Symbol;
+
+// Shareable between threads: acquire(), release() and the run methods take
+// `&self`, so each thread can run its own script at the same time.
+pub struct InterpreterPool {
+    prelude_globals: HashMap<Symbol, Value>,
+    idle: Mutex<Vec<Interpreter>>,
+    capacity: usize,
+    step_limit: Option<u64>,
+    memory_limit: Option<usize>,
+    timeout: Option<Duration>,
+    config: InterpreterConfig,
+}
+
+impl InterpreterPool {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_prelude("", capacity).expect("empty prelude always loads")
+    }
+
+    // The prelude is run once; every interpreter handed out starts from the
+    // globals it defined.
+    pub fn with_prelude(prelude: &str, capacity: usize) -> Result<Self, String> {
+        let program = Program::parse(prelude)?;
+        let mut template = Interpreter::new();
+        template.run_program(&program)?;
+        let prelude_globals = template.globals().clone();
+
+        let pool = InterpreterPool {
+            prelude_globals,
+            idle: Mutex::new(Vec::with_capacity(capacity)),
+            capacity,
+            step_limit: None,
+            memory_limit: None,
+            timeout: None,
+            config: InterpreterConfig::default(),
+        };
+        for _ in 0..capacity {
+            let interpreter = pool.fresh();
+            lock(&pool.idle).push(interpreter);
+        }
+        Ok(pool)
+    }
+
+    fn fresh(&self) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        interpreter.reset_to(self.prelude());
+        interpreter
+    }
+
+    // Arrays are shared by reference, so each run gets its own copy of the
+    // prelude's values; otherwise one run's push() would show up in the next.
+    fn prelude(&self) -> HashMap<Symbol, Value> {
+        let mut copies = HashMap::new();
+        self.prelude_globals.iter().map(|(name, value)| (*name, value.copy_with(&mut copies))).collect()
+    }
+
+    // Applied to every interpreter handed out from now on.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = Some(limit);
+    }
+
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.memory_limit = Some(bytes);
+    }
+
+    // Wall-clock budget for each execute()/run().
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    pub fn set_config(&mut self, config: InterpreterConfig) {
+        self.config = config;
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn idle_count(&self) -> usize {
+        lock(&self.idle).len()
+    }
+
+    pub fn acquire(&self) -> Interpreter {
+        let idle = lock(&self.idle).pop();
+        let mut interpreter = match idle {
+            Some(interpreter) => interpreter,
+            None => self.fresh(),
+        };
+        interpreter.set_config(self.config);
+        if let Some(limit) = self.step_limit {
+            interpreter.set_step_limit(limit);
+        }
+        if let Some(bytes) = self.memory_limit {
+            interpreter.set_memory_limit(bytes);
+        }
+        interpreter
+    }
+
+    // The returned interpreter is dropped and a fresh one takes its place:
+    // besides globals and output, a host may have registered functions or
+    // changed its output, limits, strictness, locale or hooks, and none of
+    // that may reach the next tenant.
+    pub fn release(&self, interpreter: Interpreter) {
+        drop(interpreter);
+        if self.idle_count() >= self.capacity {
+            return;
+        }
+        let interpreter = self.fresh();
+        let mut idle = lock(&self.idle);
+        if idle.len() < self.capacity {
+            idle.push(interpreter);
+        }
+    }
+
+    pub fn run(&self, source: &str) -> Result<Vec<String>, String> {
+        let result = self.execute(source);
+        match result.error {
+            Some(e) => Err(e),
+            None => Ok(result.output),
+        }
+    }
+
+    // Like run(), but keeps whatever was printed before an error.
+    pub fn execute(&self, source: &str) -> RunResult {
+        match Program::parse(source) {
+            Ok(program) => self.execute_program(&program),
+            Err(e) => RunResult {
+                output: Vec::new(),
+                error: Some(e),
+            },
+        }
+    }
+
+    // For scripts run many times: parse them once with Program::parse.
+    pub fn execute_program(&self, program: &Arc<Program>) -> RunResult {
+        let mut interpreter = self.acquire();
+        let result = match self.timeout {
+            Some(timeout) => interpreter.run_with_timeout(program.stmts(), timeout),
+            None => interpreter.run_program(program),
+        };
+        let output = std::mem::take(&mut interpreter.output);
+        self.release(interpreter);
+        RunResult {
+            output,
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResult {
+    pub output: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl RunResult {
+    // {"output": [...], "error": null | "message"}
+    pub fn to_json(&self) -> Json {
+        let output = self.output.iter().cloned().map(Json::String).collect();
+        let error = match &self.error {
+            Some(e) => Json::String(e.clone()),
+            None => Json::Null,
+        };
+        Json::Object(vec![
+            ("output".to_string(), Json::Array(output)),
+            ("error".to_string(), error),
+        ])
+    }
+}
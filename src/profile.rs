@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::symbol::{self, Symbol};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FnStats {
+    pub calls: u64,
+    // Wall time from entering the function until it returned, including
+    // callees; recursive calls are not counted twice.
+    pub total: Duration,
+    // Time spent in the function's own statements.
+    pub self_time: Duration,
+}
+
+struct Frame {
+    name: Symbol,
+    start: Instant,
+    children: Duration,
+}
+
+// Call counts and timings per minilang function, collected by an
+// interpreter with profiling enabled.
+#[derive(Default)]
+pub struct Profile {
+    stats: HashMap<Symbol, FnStats>,
+    // Self time per call stack, for folded-stack output.
+    stacks: HashMap<Vec<Symbol>, Duration>,
+    frames: Vec<Frame>,
+}
+
+impl Profile {
+    pub(crate) fn enter(&mut self, name: Symbol) {
+        self.frames.push(Frame {
+            name,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    pub(crate) fn exit(&mut self) {
+        let Some(frame) = self.frames.pop() else { return };
+        let elapsed = frame.start.elapsed();
+        let self_time = elapsed.saturating_sub(frame.children);
+        if let Some(parent) = self.frames.last_mut() {
+            parent.children += elapsed;
+        }
+
+        let recursive = self.frames.iter().any(|f| f.name == frame.name);
+        let stats = self.stats.entry(frame.name).or_default();
+        stats.calls += 1;
+        stats.self_time += self_time;
+        if !recursive {
+            stats.total += elapsed;
+        }
+
+        let mut stack: Vec<Symbol> = self.frames.iter().map(|f| f.name).collect();
+        stack.push(frame.name);
+        *self.stacks.entry(stack).or_default() += self_time;
+    }
+
+    // Every function called, slowest self time first.
+    pub fn functions(&self) -> Vec<(String, FnStats)> {
+        let mut rows: Vec<(String, FnStats)> =
+            self.stats.iter().map(|(name, stats)| (name.to_string(), *stats)).collect();
+        rows.sort_by(|a, b| b.1.self_time.cmp(&a.1.self_time).then_with(|| a.0.cmp(&b.0)));
+        rows
+    }
+
+    pub fn table(&self) -> String {
+        let mut out = format!("{:<24} {:>10} {:>14} {:>14}\n", "function", "calls", "total", "self");
+        for (name, stats) in self.functions() {
+            out.push_str(&format!(
+                "{:<24} {:>10} {:>14} {:>14}\n",
+                name,
+                stats.calls,
+                format!("{:.3?}", stats.total),
+                format!("{:.3?}", stats.self_time)
+            ));
+        }
+        out
+    }
+
+    // One `outer;inner microseconds` line per call stack, the input format
+    // of flamegraph.pl and similar tools.
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| format!("{} {}", symbol::join(stack, ";"), time.as_micros()))
+            .collect();
+        lines.sort();
+        lines.iter().map(|l| format!("{}\n", l)).collect()
+    }
+}
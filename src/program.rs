@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use crate::parser::{Stmt, parse_source};
+
+// A parsed script that any number of interpreters, on any threads, can run
+// as often as they like. Running borrows the statements and function
+// definitions share their bodies with it, so the AST is never copied.
+#[derive(Debug, PartialEq)]
+pub struct Program {
+    stmts: Vec<Stmt>,
+}
+
+impl Program {
+    pub fn parse(source: &str) -> Result<Arc<Program>, String> {
+        Ok(Program::new(parse_source(source)?))
+    }
+
+    pub fn new(stmts: Vec<Stmt>) -> Arc<Program> {
+        Arc::new(Program { stmts })
+    }
+
+    pub fn stmts(&self) -> &[Stmt] {
+        &self.stmts
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST: &str = "minilang.toml";
+
+// A directory with a minilang.toml:
+//
+//     [project]
+//     name = "tools"
+//     entry = "src/main.ml"
+//     sources = ["lib"]
+//
+//     [scripts]
+//     report = "scripts/report.ml"
+//
+// Every `.ml` file under the source directories runs, in path order, before
+// the entry point or script, so they can define shared functions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub root: PathBuf,
+    pub name: Option<String>,
+    pub entry: Option<PathBuf>,
+    pub sources: Vec<PathBuf>,
+    pub scripts: Vec<(String, PathBuf)>,
+}
+
+enum TomlValue {
+    String(String),
+    Array(Vec<String>),
+}
+
+// The subset of TOML a manifest needs: [tables], comments, and keys set to
+// strings or single-line arrays of strings.
+fn parse_toml(text: &str) -> Result<Vec<(String, String, TomlValue)>, String> {
+    let mut table = String::new();
+    let mut entries = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let error = |msg: &str| format!("{} line {}: {}", MANIFEST, i + 1, msg);
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            table = name.strip_suffix(']').ok_or_else(|| error("expected ']'"))?.trim().to_string();
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| error("expected key = value"))?;
+        let value = value.trim();
+        let (value, rest) = if let Some(items) = value.strip_prefix('[') {
+            let mut items = items.trim_start();
+            let mut strings = Vec::new();
+            while !items.starts_with(']') {
+                let (s, rest) = parse_string(items).ok_or_else(|| error("expected a string"))?;
+                strings.push(s);
+                items = rest.trim_start();
+                items = items.strip_prefix(',').unwrap_or(items).trim_start();
+                if items.is_empty() {
+                    return Err(error("expected ']'"));
+                }
+            }
+            (TomlValue::Array(strings), &items[1..])
+        } else {
+            let (s, rest) = parse_string(value).ok_or_else(|| error("expected a string"))?;
+            (TomlValue::String(s), rest)
+        };
+        let rest = rest.trim();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(error("unexpected text after the value"));
+        }
+        entries.push((table.clone(), key.trim().to_string(), value));
+    }
+    Ok(entries)
+}
+
+// A double-quoted string at the start of `text`, and what follows it.
+fn parse_string(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    let mut s = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((s, &text[i + 2..])),
+            '\\' => match chars.next()?.1 {
+                'n' => s.push('\n'),
+                't' => s.push('\t'),
+                c => s.push(c),
+            },
+            c => s.push(c),
+        }
+    }
+    None
+}
+
+impl Project {
+    pub fn parse(root: &Path, text: &str) -> Result<Project, String> {
+        let mut project = Project {
+            root: root.to_path_buf(),
+            name: None,
+            entry: None,
+            sources: Vec::new(),
+            scripts: Vec::new(),
+        };
+        for (table, key, value) in parse_toml(text)? {
+            match (table.as_str(), key.as_str(), value) {
+                ("project", "name", TomlValue::String(s)) => project.name = Some(s),
+                ("project", "entry", TomlValue::String(s)) => project.entry = Some(root.join(s)),
+                ("project", "sources", TomlValue::Array(dirs)) => {
+                    project.sources = dirs.iter().map(|d| root.join(d)).collect()
+                }
+                ("scripts", alias, TomlValue::String(s)) => project.scripts.push((alias.to_string(), root.join(s))),
+                (_, key, _) => {
+                    return Err(format!("{}: unknown or mistyped key '{}' in [{}]", MANIFEST, key, table));
+                }
+            }
+        }
+        Ok(project)
+    }
+
+    // The manifest in `dir` or the nearest directory above it.
+    pub fn find(dir: &Path) -> Result<Option<Project>, String> {
+        for root in dir.ancestors() {
+            let path = root.join(MANIFEST);
+            if path.is_file() {
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| format!("Error reading file '{}': {}", path.display(), e))?;
+                return Project::parse(root, &text).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    // The file to run for `minilang run [name]`: the entry point when no
+    // name is given, otherwise the script with that alias.
+    pub fn target(&self, name: Option<&str>) -> Option<&Path> {
+        match name {
+            None => self.entry.as_deref(),
+            Some(alias) => self.scripts.iter().find(|(a, _)| a == alias).map(|(_, p)| p.as_path()),
+        }
+    }
+
+    // The `.ml` files under the source directories, sorted, without `main`.
+    pub fn source_files(&self, main: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut files = Vec::new();
+        for dir in &self.sources {
+            collect_scripts(dir, &mut files)?;
+        }
+        files.sort();
+        let main = main.canonicalize().ok();
+        files.retain(|f| f.canonicalize().ok() != main);
+        Ok(files)
+    }
+}
+
+fn collect_scripts(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Error reading directory '{}': {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_dir() {
+            collect_scripts(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "ml") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,388 @@
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+use crate::completion;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,
+    Down,
+    KillToEnd,
+    KillToStart,
+    KillWord,
+    Yank,
+    CtrlD,
+    Interrupt,
+    Tab,
+    Ignored,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadLine {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+    killed: String,
+}
+
+impl LineBuffer {
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    pub fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.insert(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+
+    pub fn kill_to_end(&mut self) {
+        self.killed = self.chars.drain(self.cursor..).collect();
+    }
+
+    pub fn kill_to_start(&mut self) {
+        self.killed = self.chars.drain(..self.cursor).collect();
+        self.cursor = 0;
+    }
+
+    pub fn kill_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        self.killed = self.chars.drain(start..self.cursor).collect();
+        self.cursor = start;
+    }
+
+    pub fn yank(&mut self) {
+        let killed = self.killed.clone();
+        self.insert_str(&killed);
+    }
+}
+
+#[derive(Default)]
+pub struct Editor {
+    history: Vec<String>,
+    completions: Vec<String>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Editor::default()
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    // Words offered by Tab until the next call.
+    pub fn set_completions(&mut self, words: Vec<String>) {
+        self.completions = words;
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|l| l == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+    }
+
+    // Applies keys until the line is finished. `redraw` is called after every
+    // key with the current buffer, plus the candidates to list when Tab could
+    // not complete a unique word.
+    pub fn edit<I, F>(&mut self, keys: I, mut redraw: F) -> ReadLine
+    where
+        I: IntoIterator<Item = Key>,
+        F: FnMut(&LineBuffer, Option<&[String]>),
+    {
+        let mut buffer = LineBuffer::default();
+        let mut index = self.history.len();
+        let mut draft = String::new();
+
+        for key in keys {
+            let mut listing = None;
+            match key {
+                Key::Enter => return ReadLine::Line(buffer.text()),
+                Key::Interrupt => return ReadLine::Interrupted,
+                Key::CtrlD if buffer.is_empty() => return ReadLine::Eof,
+                Key::CtrlD | Key::Delete => buffer.delete(),
+                Key::Char(c) => buffer.insert(c),
+                Key::Tab => {
+                    let text = buffer.text();
+                    let (start, matches) =
+                        completion::complete(&text, buffer.cursor(), &self.completions);
+                    if text.chars().take(buffer.cursor()).all(char::is_whitespace) {
+                        buffer.insert_str("  ");
+                    } else if !matches.is_empty() {
+                        let common = completion::common_prefix(&matches);
+                        let typed = buffer.cursor() - start;
+                        let rest: String = common.chars().skip(typed).collect();
+                        if matches.len() == 1 {
+                            buffer.insert_str(&rest);
+                        } else if rest.is_empty() {
+                            listing = Some(matches);
+                        } else {
+                            buffer.insert_str(&rest);
+                        }
+                    }
+                }
+                Key::Backspace => buffer.backspace(),
+                Key::Left => buffer.left(),
+                Key::Right => buffer.right(),
+                Key::Home => buffer.home(),
+                Key::End => buffer.end(),
+                Key::KillToEnd => buffer.kill_to_end(),
+                Key::KillToStart => buffer.kill_to_start(),
+                Key::KillWord => buffer.kill_word(),
+                Key::Yank => buffer.yank(),
+                Key::Up => {
+                    if index > 0 {
+                        if index == self.history.len() {
+                            draft = buffer.text();
+                        }
+                        index -= 1;
+                        buffer.set(&self.history[index]);
+                    }
+                }
+                Key::Down => {
+                    if index < self.history.len() {
+                        index += 1;
+                        match self.history.get(index) {
+                            Some(line) => buffer.set(line),
+                            None => buffer.set(&draft),
+                        }
+                    }
+                }
+                Key::Ignored => {}
+            }
+            redraw(&buffer, listing.as_deref());
+        }
+        ReadLine::Eof
+    }
+
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadLine> {
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", prompt)?;
+        stdout.flush()?;
+
+        let raw = if io::stdin().is_terminal() {
+            RawMode::enable()
+        } else {
+            None
+        };
+        if raw.is_none() {
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line)? == 0 {
+                return Ok(ReadLine::Eof);
+            }
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            return Ok(ReadLine::Line(line.to_string()));
+        }
+
+        let stdin = io::stdin();
+        let keys = KeyReader {
+            bytes: stdin.lock().bytes(),
+        };
+        let result = self.edit(keys, |buffer, listing| {
+            if let Some(words) = listing {
+                let _ = write!(stdout, "\r\n{}\r\n", words.join("  "));
+            }
+            let back = buffer.text().chars().count() - buffer.cursor();
+            let _ = write!(stdout, "\r{}{}\x1b[K", prompt, buffer.text());
+            if back > 0 {
+                let _ = write!(stdout, "\x1b[{}D", back);
+            }
+            let _ = stdout.flush();
+        });
+        drop(raw);
+        match result {
+            ReadLine::Interrupted => writeln!(stdout, "^C")?,
+            _ => writeln!(stdout)?,
+        }
+        Ok(result)
+    }
+}
+
+// Turns terminal input bytes into keys, decoding UTF-8 and the common
+// VT100/xterm escape sequences.
+pub struct KeyReader<R: Read> {
+    pub bytes: io::Bytes<R>,
+}
+
+impl<R: Read> KeyReader<R> {
+    fn next_byte(&mut self) -> Option<u8> {
+        self.bytes.next().and_then(|b| b.ok())
+    }
+}
+
+impl<R: Read> Iterator for KeyReader<R> {
+    type Item = Key;
+
+    fn next(&mut self) -> Option<Key> {
+        let b = self.next_byte()?;
+        let key = match b {
+            b'\r' | b'\n' => Key::Enter,
+            0x01 => Key::Home,
+            0x02 => Key::Left,
+            0x03 => Key::Interrupt,
+            0x04 => Key::CtrlD,
+            0x05 => Key::End,
+            0x06 => Key::Right,
+            0x08 | 0x7f => Key::Backspace,
+            b'\t' => Key::Tab,
+            0x0b => Key::KillToEnd,
+            0x0e => Key::Down,
+            0x10 => Key::Up,
+            0x15 => Key::KillToStart,
+            0x17 => Key::KillWord,
+            0x19 => Key::Yank,
+            0x1b => match self.next_byte()? {
+                b'[' | b'O' => match self.next_byte()? {
+                    b'A' => Key::Up,
+                    b'B' => Key::Down,
+                    b'C' => Key::Right,
+                    b'D' => Key::Left,
+                    b'H' => Key::Home,
+                    b'F' => Key::End,
+                    d @ b'0'..=b'9' => {
+                        // ESC [ n ~
+                        let mut last = self.next_byte()?;
+                        while last.is_ascii_digit() || last == b';' {
+                            last = self.next_byte()?;
+                        }
+                        match (d, last) {
+                            (b'1' | b'7', b'~') => Key::Home,
+                            (b'4' | b'8', b'~') => Key::End,
+                            (b'3', b'~') => Key::Delete,
+                            _ => Key::Ignored,
+                        }
+                    }
+                    _ => Key::Ignored,
+                },
+                _ => Key::Ignored,
+            },
+            b if b < 0x20 => Key::Ignored,
+            b if b < 0x80 => Key::Char(b as char),
+            b => {
+                let len = match b {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => return Some(Key::Ignored),
+                };
+                let mut buf = vec![b];
+                for _ in 1..len {
+                    buf.push(self.next_byte()?);
+                }
+                match std::str::from_utf8(&buf).ok().and_then(|s| s.chars().next()) {
+                    Some(c) => Key::Char(c),
+                    None => Key::Ignored,
+                }
+            }
+        };
+        Some(key)
+    }
+}
+
+// Puts the controlling terminal into non-canonical, no-echo mode via stty and
+// restores the previous settings on drop.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Option<RawMode> {
+        let output = Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let saved = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        let status = Command::new("stty")
+            .args(["-icanon", "-echo", "-isig", "-iexten", "min", "1"])
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .status()
+            .ok()?;
+        status.success().then_some(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = Command::new("stty")
+            .arg(&self.saved)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
@@ -0,0 +1,230 @@
+use std::fs;
+use std::time::Instant;
+
+use crate::completion;
+use crate::interpreter::{Interpreter, Value};
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::readline::{Editor, ReadLine};
+use crate::style;
+use crate::symbol;
+
+const PROMPT: &str = ">> ";
+const SESSION_FILE: &str = "session.mini";
+const CONTINUATION_PROMPT: &str = ".. ";
+
+const HELP: &str = "\
+:help          show this help
+:vars          list defined variables and functions
+:reset         clear all variables and functions
+:load <file>   run a script file in this session
+:save [file]   write the inputs that ran successfully to a file (default session.mini)
+:restore [file] replay a saved session into this one
+:time on|off   print how long each input takes to run";
+
+struct Session {
+    interpreter: Interpreter,
+    timing: bool,
+    // Inputs that ran without error, for :save.
+    inputs: Vec<String>,
+}
+
+// True when the input cannot be complete yet: an unclosed bracket, brace or
+// paren, or an unterminated string literal.
+pub fn is_incomplete(source: &str) -> bool {
+    let tokens = match Lexer::new(source).tokenize() {
+        Ok(t) => t,
+        Err(e) => return e.starts_with("Unterminated string"),
+    };
+    let mut depth: i64 = 0;
+    for token in &tokens {
+        match token {
+            Token::LParen | Token::LBrace | Token::LBracket => depth += 1,
+            Token::RParen | Token::RBrace | Token::RBracket => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+// Starts the REPL, first replaying the session file `init` if given.
+pub fn run(init: Option<&str>) {
+    run_with(Interpreter::new(), init);
+}
+
+// Starts the REPL on an interpreter that may already hold definitions, as
+// after `minilang -i script.ml`.
+pub fn run_with(interpreter: Interpreter, init: Option<&str>) {
+    println!("minilang REPL (Ctrl+Z to exit)");
+    let mut editor = Editor::new();
+    let mut session = Session {
+        interpreter,
+        timing: false,
+        inputs: Vec::new(),
+    };
+    if let Some(path) = init {
+        restore(&mut session, path);
+    }
+    let mut buffer = String::new();
+
+    loop {
+        let prompt = if buffer.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+
+        editor.set_completions(completion::candidates(&session.interpreter));
+        let eof = match editor.read_line(&style::prompt(prompt)) {
+            Ok(ReadLine::Line(line)) => {
+                editor.add_history(&line);
+                buffer.push_str(&line);
+                buffer.push('\n');
+                false
+            }
+            Ok(ReadLine::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            Ok(ReadLine::Eof) => true,
+            Err(e) => {
+                eprintln!("{}", style::error(&format!("Read error: {}", e)));
+                break;
+            }
+        };
+
+        if buffer.trim().is_empty() {
+            buffer.clear();
+            if eof {
+                break;
+            }
+            continue;
+        }
+        if buffer.trim_start().starts_with(':') {
+            let line = std::mem::take(&mut buffer);
+            command(&mut session, line.trim());
+            if eof {
+                break;
+            }
+            continue;
+        }
+        if !eof && is_incomplete(&buffer) {
+            continue;
+        }
+
+        let source = std::mem::take(&mut buffer);
+        eval_input(&mut session, source.trim());
+        if eof {
+            break;
+        }
+    }
+}
+
+fn command(session: &mut Session, line: &str) {
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (line, ""),
+    };
+    match name {
+        ":help" => println!("{}", HELP),
+        ":vars" => {
+            for (name, value) in session.interpreter.variables() {
+                match value {
+                    Value::Function { params, .. } => {
+                        println!("fn {}({})", name, symbol::join(&params, ", "))
+                    }
+                    value => println!("{} = {}", name, value.repr()),
+                }
+            }
+        }
+        ":reset" => {
+            session.interpreter.reset();
+            session.inputs.clear();
+            println!("State cleared");
+        }
+        ":load" if arg.is_empty() => eprintln!("Usage: :load <file>"),
+        ":load" => match fs::read_to_string(arg) {
+            Ok(source) => eval_input(session, &source),
+            Err(e) => eprintln!("{}", style::error(&format!("Error reading file '{}': {}", arg, e))),
+        },
+        ":save" => {
+            let path = if arg.is_empty() { SESSION_FILE } else { arg };
+            let mut contents = String::new();
+            for input in &session.inputs {
+                contents.push_str(input);
+                contents.push('\n');
+            }
+            match fs::write(path, contents) {
+                Ok(()) => println!("Saved {} inputs to {}", session.inputs.len(), path),
+                Err(e) => eprintln!("{}", style::error(&format!("Error writing file '{}': {}", path, e))),
+            }
+        }
+        ":restore" => restore(session, if arg.is_empty() { SESSION_FILE } else { arg }),
+        ":time" => match arg {
+            "on" => session.timing = true,
+            "off" => session.timing = false,
+            "" => println!("Timing is {}", if session.timing { "on" } else { "off" }),
+            _ => eprintln!("Usage: :time on|off"),
+        },
+        _ => eprintln!("{}", style::error(&format!("Unknown command '{}' (try :help)", name))),
+    }
+}
+
+fn restore(session: &mut Session, path: &str) {
+    match fs::read_to_string(path) {
+        Ok(source) => eval_input(session, source.trim_end()),
+        Err(e) => eprintln!("{}", style::error(&format!("Error reading file '{}': {}", path, e))),
+    }
+}
+
+fn eval_input(session: &mut Session, source: &str) {
+    let start = Instant::now();
+    if run_source(&mut session.interpreter, source) {
+        session.inputs.push(source.to_string());
+    }
+    if session.timing {
+        println!("Time: {:.3?}", start.elapsed());
+    }
+}
+
+// Returns whether the input ran without error.
+fn run_source(interpreter: &mut Interpreter, source: &str) -> bool {
+    let mut lexer = Lexer::new(source);
+    let tokens = match lexer.tokenize_spanned() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", style::error(&format!("Lexer error: {}", e)));
+            return false;
+        }
+    };
+
+    let mut parser = Parser::with_spans(tokens);
+    let stmts = match parser.parse_program() {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", style::error(&format!("Parse error: {}", e)));
+            return false;
+        }
+    };
+
+    let prev_len = interpreter.output.len();
+    let result = interpreter.run_interactive(&stmts);
+    for line in &interpreter.output[prev_len..] {
+        println!("{}", line);
+    }
+    if let Some(code) = interpreter.exit_code() {
+        std::process::exit(code);
+    }
+    match result {
+        Ok(Some(value)) => {
+            println!("{}", style::value(&value.repr()));
+            interpreter.define_global("_", value);
+            true
+        }
+        Ok(None) => true,
+        Err(e) => {
+            eprintln!("{}", style::error(&format!("Runtime error: {}", e)));
+            false
+        }
+    }
+}
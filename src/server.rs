@@ -0,0 +1,153 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::json::{self, Json};
+use crate::pool::InterpreterPool;
+
+const MAX_BODY: usize = 1 << 20;
+
+// Limits for each script unless `serve` is given others, so that one that
+// never finishes cannot hold on to the service.
+pub const DEFAULT_STEP_LIMIT: u64 = 10_000_000;
+pub const DEFAULT_MEMORY_LIMIT: usize = 64 << 20;
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long a client may leave a read or write waiting before its
+// connection is dropped.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+fn error_response(status: u16, message: &str) -> Response {
+    Response {
+        status,
+        body: Json::Object(vec![("error".to_string(), Json::String(message.to_string()))])
+            .to_string(),
+    }
+}
+
+// Routes a single request. A JSON body must look like {"source": "..."};
+// any other content type is taken to be the script itself.
+pub fn handle(
+    pool: &InterpreterPool,
+    method: &str,
+    path: &str,
+    content_type: &str,
+    body: &str,
+) -> Response {
+    let path = path.split('?').next().unwrap_or(path);
+    match (method, path) {
+        ("GET", "/health") => Response {
+            status: 200,
+            body: Json::Object(vec![("status".to_string(), Json::String("ok".to_string()))])
+                .to_string(),
+        },
+        ("POST", "/run") => {
+            let source = if content_type.starts_with("application/json") {
+                let request = match json::parse(body) {
+                    Ok(j) => j,
+                    Err(e) => return error_response(400, &e),
+                };
+                match request.get("source").and_then(Json::as_str) {
+                    Some(s) => s.to_string(),
+                    None => return error_response(400, "Missing string field 'source'"),
+                }
+            } else {
+                body.to_string()
+            };
+
+            Response {
+                status: 200,
+                body: pool.execute(&source).to_json().to_string(),
+            }
+        }
+        (_, "/health") | (_, "/run") => error_response(405, "Method not allowed"),
+        _ => error_response(404, "Not found"),
+    }
+}
+
+// Serves with one thread per pooled interpreter, each accepting and
+// handling connections in turn, so a slow client only holds up its own.
+pub fn serve(listener: TcpListener, pool: &InterpreterPool) -> io::Result<()> {
+    std::thread::scope(|scope| {
+        for _ in 0..pool.capacity().max(1) {
+            scope.spawn(|| {
+                for stream in listener.incoming() {
+                    let result = stream.and_then(|stream| handle_connection(stream, pool));
+                    if let Err(e) = result {
+                        eprintln!("Connection error: {}", e);
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, pool: &InterpreterPool) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+    let mut content_type = String::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            break;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = value.to_ascii_lowercase();
+            }
+        }
+    }
+
+    let response = if content_length > MAX_BODY {
+        error_response(413, "Request body too large")
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let body = String::from_utf8_lossy(&body);
+        handle(pool, &method, &path, &content_type, &body)
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Error",
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex};
+
+use crate::ast_json::{block_from_json, block_to_json};
+use crate::interpreter::{Elements, Value, lock};
+use crate::json::{self, Json};
+use crate::symbol::Symbol;
+
+// Global bindings captured by Interpreter::snapshot, sorted by name.
+#[derive(Debug, Clone)]
+pub struct InterpreterState {
+    pub globals: Vec<(String, Value)>,
+}
+
+impl InterpreterState {
+    // An object mapping each global to its value. Functions become
+    // {"params": [...], "body": <AST JSON>}; NaN and infinities, which JSON
+    // numbers cannot hold, become {"number": "nan" | "inf" | "-inf"}, and
+    // frozen arrays {"frozen": [...]}. Arrays are stored by value, so
+    // aliases become separate copies, and an array that contains itself is
+    // an error.
+    pub fn to_json(&self) -> Result<String, String> {
+        let fields = self
+            .globals
+            .iter()
+            .map(|(name, value)| {
+                let json = value_to_json(value, &mut Vec::new())
+                    .map_err(|e| format!("Cannot save '{}': {}", name, e))?;
+                Ok((name.clone(), json))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Json::Object(fields).to_string())
+    }
+
+    pub fn from_json(text: &str) -> Result<InterpreterState, String> {
+        match json::parse(text)? {
+            Json::Object(fields) => {
+                let mut globals = fields
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), value_from_json(value)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                globals.sort_by(|a, b| a.0.cmp(&b.0));
+                Ok(InterpreterState { globals })
+            }
+            _ => Err("Interpreter state must be a JSON object".to_string()),
+        }
+    }
+}
+
+fn value_to_json(value: &Value, open: &mut Vec<*const Mutex<Elements>>) -> Result<Json, String> {
+    Ok(match value {
+        Value::Number(n) if n.is_nan() => special("nan"),
+        Value::Number(n) if n.is_infinite() => special(if *n > 0.0 { "inf" } else { "-inf" }),
+        Value::Number(n) => Json::Number(*n),
+        Value::Str(s) => Json::String(s.clone()),
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Array(items) if open.contains(&Arc::as_ptr(items)) => {
+            return Err("an array that contains itself cannot be stored".to_string());
+        }
+        Value::Array(items) => {
+            let (elems, frozen) = {
+                let items = lock(items);
+                (items.to_vec(), items.frozen)
+            };
+            open.push(Arc::as_ptr(items));
+            let json = Json::Array(elems.iter().map(|v| value_to_json(v, open)).collect::<Result<_, _>>()?);
+            open.pop();
+            if frozen {
+                Json::Object(vec![("frozen".to_string(), json)])
+            } else {
+                json
+            }
+        }
+        Value::Function { params, body } => Json::Object(vec![
+            (
+                "params".to_string(),
+                Json::Array(params.iter().map(|p| Json::String(p.to_string())).collect()),
+            ),
+            ("body".to_string(), block_to_json(body)),
+        ]),
+        Value::Error(message) => Json::Object(vec![("error".to_string(), Json::String(message.clone()))]),
+        // An iterator's position can point into code that is no longer
+        // running, so it is not kept.
+        Value::Iterator(_) | Value::Null => Json::Null,
+    })
+}
+
+fn special(name: &str) -> Json {
+    Json::Object(vec![("number".to_string(), Json::String(name.to_string()))])
+}
+
+fn value_from_json(json: &Json) -> Result<Value, String> {
+    Ok(match json {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => Value::Number(*n),
+        Json::String(s) => Value::Str(s.clone()),
+        Json::Array(items) => Value::array(items.iter().map(value_from_json).collect::<Result<_, _>>()?),
+        Json::Object(_) => {
+            if let Some(number) = json.get("number") {
+                return match number.as_str() {
+                    Some("nan") => Ok(Value::Number(f64::NAN)),
+                    Some("inf") => Ok(Value::Number(f64::INFINITY)),
+                    Some("-inf") => Ok(Value::Number(f64::NEG_INFINITY)),
+                    _ => Err("Unknown special number in interpreter state".to_string()),
+                };
+            }
+            if let Some(Json::Array(items)) = json.get("frozen") {
+                return Ok(value_from_json(&Json::Array(items.clone()))?.freeze());
+            }
+            if let Some(message) = json.get("error") {
+                return match message.as_str() {
+                    Some(message) => Ok(Value::Error(message.to_string())),
+                    None => Err("Error message in interpreter state must be a string".to_string()),
+                };
+            }
+            let params = match json.get("params") {
+                Some(Json::Array(params)) => params
+                    .iter()
+                    .map(|p| p.as_str().map(Symbol::intern))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("Function parameters must be strings")?,
+                _ => return Err("Function value is missing 'params'".to_string()),
+            };
+            let body = block_from_json(json.get("body").ok_or("Function value is missing 'body'")?)?;
+            Value::Function { params, body: body.into() }
+        }
+    })
+}
@@ -0,0 +1,64 @@
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Yellow,
+    Green,
+    Cyan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// Turns colors off for the rest of the process (`--no-color`).
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+// Colors are used only when the stream is a terminal, `--no-color` was not
+// given and NO_COLOR is unset or empty.
+pub fn enabled(stream: Stream) -> bool {
+    if DISABLED.load(Ordering::Relaxed) || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    match stream {
+        Stream::Stdout => io::stdout().is_terminal(),
+        Stream::Stderr => io::stderr().is_terminal(),
+    }
+}
+
+pub fn colorize(text: &str, color: Color, enabled: bool) -> String {
+    if !enabled {
+        return text.to_string();
+    }
+    let code = match color {
+        Color::Red => "31",
+        Color::Yellow => "33",
+        Color::Green => "32",
+        Color::Cyan => "36",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+pub fn error(text: &str) -> String {
+    colorize(text, Color::Red, enabled(Stream::Stderr))
+}
+
+pub fn warning(text: &str) -> String {
+    colorize(text, Color::Yellow, enabled(Stream::Stderr))
+}
+
+pub fn prompt(text: &str) -> String {
+    colorize(text, Color::Cyan, enabled(Stream::Stdout))
+}
+
+pub fn value(text: &str) -> String {
+    colorize(text, Color::Green, enabled(Stream::Stdout))
+}
@@ -0,0 +1,39 @@
+use crate::interpreter::Interpreter;
+use crate::parser::{StmtKind, parse_source};
+
+// Test functions are the top-level functions whose names start with this.
+const PREFIX: &str = "test_";
+
+pub struct TestResult {
+    pub name: String,
+    // The failure message, or None when the test passed.
+    pub error: Option<String>,
+    // What the test printed.
+    pub output: Vec<String>,
+}
+
+// Runs every `test_*` function in `source`, in the order they are defined.
+// Each gets a fresh interpreter that has run the file's top level first, so
+// tests cannot see each other's changes. Fails only when the file does not
+// parse or its top level fails.
+pub fn run_tests(source: &str) -> Result<Vec<TestResult>, String> {
+    let program = parse_source(source)?;
+    let names = program.iter().filter_map(|stmt| match &stmt.kind {
+        StmtKind::Fn(name, params, _) if name.starts_with(PREFIX) && params.is_empty() => Some(*name),
+        _ => None,
+    });
+
+    let mut results = Vec::new();
+    for name in names {
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program)?;
+        let setup_output = interpreter.output.len();
+        let error = interpreter.call_function(&name, Vec::new()).err();
+        results.push(TestResult {
+            name: name.to_string(),
+            error,
+            output: interpreter.output.split_off(setup_output),
+        });
+    }
+    Ok(results)
+}
@@ -0,0 +1,59 @@
+// Bindings for running minilang in the browser. Build with
+//   cargo rustc --release --lib --features wasm --target wasm32-unknown-unknown --crate-type cdylib
+// Strings cross the boundary as (pointer, length) pairs in linear memory: the
+// host allocates the source with minilang_alloc, calls minilang_eval, then
+// reads minilang_result_len() bytes of JSON from the returned pointer.
+
+use std::cell::RefCell;
+
+use crate::interpreter::InterpreterConfig;
+use crate::pool::InterpreterPool;
+
+// Keeps an accidental `while true { }` from freezing the page.
+const STEP_LIMIT: u64 = 10_000_000;
+
+thread_local! {
+    static RESULT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+// Runs `source` in a fresh sandboxed interpreter and returns
+// {"output": [...], "error": null | "message"} as JSON text.
+pub fn eval(source: &str) -> String {
+    let mut pool = InterpreterPool::new(1);
+    pool.set_config(InterpreterConfig::sandboxed());
+    pool.set_step_limit(STEP_LIMIT);
+    pool.execute(source).to_json().to_string()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn minilang_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// # Safety
+/// `ptr` must come from `minilang_alloc(len)` and not have been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn minilang_free(ptr: *mut u8, len: usize) {
+    unsafe { drop(Vec::from_raw_parts(ptr, 0, len)) }
+}
+
+/// # Safety
+/// `ptr` must point to `len` readable bytes. The returned pointer stays valid
+/// until the next call to `minilang_eval`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn minilang_eval(ptr: *const u8, len: usize) -> *const u8 {
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let json = eval(&String::from_utf8_lossy(bytes));
+    RESULT.with(|r| {
+        *r.borrow_mut() = json;
+        r.borrow().as_ptr()
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn minilang_result_len() -> usize {
+    RESULT.with(|r| r.borrow().len())
+}
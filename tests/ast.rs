@@ -0,0 +1,53 @@
+use minilang::ast::{Visitor, VisitorMut, walk_expr, walk_expr_mut};
+use minilang::dump;
+use minilang::parser::{BinOp, Expr, parse_source};
+
+struct CallCounter {
+    calls: Vec<String>,
+}
+
+impl Visitor for CallCounter {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call(callee, _) = expr
+            && let Expr::Ident(name) = callee.as_ref()
+        {
+            self.calls.push(name.to_string());
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[test]
+fn visitor_reaches_nested_expressions() {
+    let program = parse_source(
+        "fn f(x) {\n  if g(x) { return h([k(1)]) }\n}\nfor i in 0..len(a) { print(f(i)) }",
+    )
+    .unwrap();
+    let mut counter = CallCounter { calls: Vec::new() };
+    counter.visit_block(&program);
+    assert_eq!(counter.calls, vec!["g", "h", "k", "len", "print", "f"]);
+}
+
+// Folds additions of two number literals.
+struct FoldAdd;
+
+impl VisitorMut for FoldAdd {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+        if let Expr::Binary(left, BinOp::Add, right) = expr
+            && let (Expr::Number(a), Expr::Number(b)) = (left.as_ref(), right.as_ref())
+        {
+            *expr = Expr::Number(a + b);
+        }
+    }
+}
+
+#[test]
+fn visitor_mut_rewrites_in_place() {
+    let mut program = parse_source("let x = 1 + 2 + 3\nwhile x < 2 + 2 { print(x + 1) }").unwrap();
+    FoldAdd.visit_block_mut(&mut program);
+    assert_eq!(
+        dump::ast_sexp(&program),
+        "(let x 6)\n(while (< x 4) (block (call print (+ x 1))))\n"
+    );
+}
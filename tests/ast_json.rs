@@ -0,0 +1,43 @@
+use minilang::parser::{from_json, parse_source, to_json};
+
+#[test]
+fn ast_json_round_trips_every_node_type() {
+    let source = r#"
+let xs = [1, 2.5, "s", true]
+xs[0] = -xs[1]
+fn add(a, b) {
+  return a + b * 2
+}
+if not (xs[0] < 1) and false {
+  print(add(1, 2))
+} else {
+  x = 1
+}
+while false { }
+for i in 0..3 {
+  return
+}
+for c in "ab" { }
+"#;
+    let program = parse_source(source).unwrap();
+    let json = to_json(&program);
+    assert_eq!(from_json(&json).unwrap(), program);
+}
+
+#[test]
+fn ast_json_shape() {
+    let program = parse_source("let x = 1 + 2").unwrap();
+    assert_eq!(
+        to_json(&program),
+        r#"[{"type":"Let","name":"x","value":{"type":"Binary","op":"+","left":{"type":"Number","value":1},"right":{"type":"Number","value":2}},"line":1}]"#
+    );
+}
+
+#[test]
+fn ast_json_rejects_malformed_input() {
+    assert!(from_json("{}").unwrap_err().contains("must be an array"));
+    assert!(from_json(r#"[{"type":"Goto"}]"#).unwrap_err().contains("Unknown statement type"));
+    assert!(from_json(r#"[{"type":"ExprStmt","expr":{"type":"Binary","op":"^","left":null,"right":null}}]"#)
+        .unwrap_err()
+        .contains("Unknown binary operator"));
+}
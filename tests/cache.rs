@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use minilang::cache::{ProgramCache, source_hash};
+use minilang::interpreter::Interpreter;
+
+#[test]
+fn memory_cache_parses_each_source_once() {
+    let mut cache = ProgramCache::new();
+    let first = cache.parse("print(1)").unwrap();
+    assert!(Arc::ptr_eq(&first, &cache.parse("print(1)").unwrap()));
+    assert!(!Arc::ptr_eq(&first, &cache.parse("print(2)").unwrap()));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.parse("let = 1").is_err());
+    assert_eq!(cache.len(), 2);
+    assert_ne!(source_hash("print(1)"), source_hash("print(1) "));
+}
+
+#[test]
+fn disk_cache_survives_a_new_cache() {
+    let dir = std::env::temp_dir().join(format!("minilang_cache_{}", std::process::id()));
+    let source = "## Doubles n.\nfn double(n) {\n  return n * 2\n}\nprint(double(21))";
+    let parsed = ProgramCache::with_dir(&dir).parse(source).unwrap();
+    assert!(dir.join(format!("{:016x}.json", source_hash(source))).is_file());
+
+    let mut fresh = ProgramCache::with_dir(&dir);
+    let loaded = fresh.get(source).unwrap();
+    assert_eq!(loaded, parsed);
+    let mut interp = Interpreter::new();
+    interp.run_program(&loaded).unwrap();
+    assert_eq!(interp.output, vec!["42"]);
+
+    // A damaged entry is treated as a miss.
+    std::fs::write(dir.join(format!("{:016x}.json", source_hash("print(3)"))), "{").unwrap();
+    assert!(ProgramCache::with_dir(&dir).get("print(3)").is_none());
+
+    // So is an entry written for a different source with the same hash.
+    let other = std::fs::read_to_string(dir.join(format!("{:016x}.json", source_hash(source)))).unwrap();
+    std::fs::write(dir.join(format!("{:016x}.json", source_hash("print(4)"))), other).unwrap();
+    assert!(ProgramCache::with_dir(&dir).get("print(4)").is_none());
+    let mut overwritten = ProgramCache::with_dir(&dir);
+    overwritten.parse("print(4)").unwrap();
+    assert!(ProgramCache::with_dir(&dir).get("print(4)").is_some());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
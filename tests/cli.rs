@@ -0,0 +1,427 @@
+use std::process::Command;
+
+fn minilang(args: &[&str]) -> (String, String, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(args)
+        .output()
+        .expect("failed to start minilang");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    (stdout, stderr, output.status.success())
+}
+
+#[test]
+fn cli_run_file() {
+    let (stdout, _, ok) = minilang(&["examples/hello.ml"]);
+    assert!(ok);
+    assert_eq!(stdout, "Hello, world!\n");
+}
+
+#[test]
+fn cli_ast_tree() {
+    let (stdout, _, ok) = minilang(&["--ast", "examples/hello.ml"]);
+    assert!(ok);
+    assert_eq!(stdout, "ExprStmt\n  Call\n    Ident print\n    String \"Hello, world!\"\n");
+}
+
+#[test]
+fn cli_ast_sexp() {
+    let (stdout, _, ok) = minilang(&["--ast=sexp", "examples/hello.ml"]);
+    assert!(ok);
+    assert_eq!(stdout, "(call print \"Hello, world!\")\n");
+}
+
+#[test]
+fn cli_unknown_option() {
+    let (_, stderr, ok) = minilang(&["--bogus", "examples/hello.ml"]);
+    assert!(!ok);
+    assert!(stderr.contains("Unknown option"));
+}
+
+#[test]
+fn cli_tokens() {
+    let (stdout, _, ok) = minilang(&["--tokens", "examples/hello.ml"]);
+    assert!(ok);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("2:1 "));
+    assert!(lines[0].ends_with("Ident(\"print\")"));
+    assert!(lines[4].ends_with("Eof"));
+}
+
+#[test]
+fn cli_check_valid_file() {
+    let (stdout, stderr, ok) = minilang(&["--check", "examples/hello.ml"]);
+    assert!(ok);
+    assert!(stdout.is_empty());
+    assert!(stderr.is_empty());
+}
+
+#[test]
+fn cli_check_reports_parse_error() {
+    let path = std::env::temp_dir().join("minilang_check_bad.ml");
+    std::fs::write(&path, "let x = 1\nfn f( {\n").unwrap();
+    let (stdout, stderr, ok) = minilang(&["--check", path.to_str().unwrap()]);
+    assert!(!ok);
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("Parse error"));
+    assert!(stderr.contains("at line 2"));
+}
+
+#[test]
+fn cli_fmt_check() {
+    let path = std::env::temp_dir().join("minilang_fmt_check.ml");
+    std::fs::write(&path, "let x=1\n").unwrap();
+    let (_, stderr, ok) = minilang(&["fmt", "--check", path.to_str().unwrap()]);
+    assert!(!ok);
+    assert!(stderr.contains("not formatted"));
+
+    let (_, _, ok) = minilang(&["fmt", "--write", path.to_str().unwrap()]);
+    assert!(ok);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "let x = 1\n");
+    let (_, _, ok) = minilang(&["fmt", "--check", path.to_str().unwrap()]);
+    assert!(ok);
+}
+
+#[test]
+fn cli_lint_deny_sets_exit_code() {
+    let path = std::env::temp_dir().join("minilang_lint.ml");
+    std::fs::write(&path, "let x = 1\nif x == true {\n  print(x)\n}\n").unwrap();
+    let (_, stderr, ok) = minilang(&["lint", path.to_str().unwrap()]);
+    assert!(ok);
+    assert!(stderr.contains("line 2: warning[bool-comparison]"));
+
+    let (_, stderr, ok) = minilang(&["lint", "--deny", "bool-comparison", path.to_str().unwrap()]);
+    assert!(!ok);
+    assert!(stderr.contains("line 2: error[bool-comparison]"));
+}
+
+#[test]
+fn cli_no_color_flag() {
+    let (stdout, stderr, ok) = minilang(&["--no-color", "examples/hello.ml"]);
+    assert!(ok);
+    assert!(stderr.is_empty());
+    assert_eq!(stdout, "Hello, world!\n");
+}
+
+#[test]
+fn cli_no_color_after_the_program_belongs_to_the_script() {
+    let path = std::env::temp_dir().join(format!("minilang_args_{}.ml", std::process::id()));
+    std::fs::write(&path, "print(args())\n").unwrap();
+    let file = path.to_str().unwrap();
+    let (stdout, _, ok) = minilang(&["run", file, "x", "--no-color", "y"]);
+    assert!(ok);
+    assert_eq!(stdout, "[\"x\", \"--no-color\", \"y\"]\n");
+    let (stdout, _, ok) = minilang(&["--no-color", "run", "--no-color", file, "--no-color"]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(ok);
+    assert_eq!(stdout, "[\"--no-color\"]\n");
+    let (stdout, _, ok) = minilang(&["-e", "print(args())", "--no-color"]);
+    assert!(ok);
+    assert_eq!(stdout, "[\"--no-color\"]\n");
+}
+
+#[test]
+fn cli_run_warns_about_shadowing_and_still_runs() {
+    let path = std::env::temp_dir().join(format!("minilang_shadow_{}.ml", std::process::id()));
+    std::fs::write(&path, "let x = 1\nif true {\n  let x = 2\n  print(x)\n}\nprint(x == true)\n").unwrap();
+    let file = path.to_str().unwrap();
+    let (stdout, stderr, ok) = minilang(&["--no-color", "run", file]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(ok);
+    assert_eq!(stdout, "2\nfalse\n");
+    // Only the shadowing rule runs; `x == true` is left to `minilang lint`.
+    assert_eq!(
+        stderr,
+        format!("{}: line 3: warning[shadowed-variable]: 'x' shadows the variable declared at line 1, which keeps its own value\n", file)
+    );
+    assert!(!stderr.contains('\x1b'));
+}
+
+#[test]
+fn cli_errors_are_plain_when_piped() {
+    let path = std::env::temp_dir().join(format!("minilang_color_{}.ml", std::process::id()));
+    std::fs::write(&path, "print(missing)\n").unwrap();
+    let (_, stderr, ok) = minilang(&[path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!ok);
+    assert!(stderr.starts_with("Runtime error:"));
+    assert!(!stderr.contains('\x1b'));
+}
+
+#[test]
+fn cli_eval_one_liner() {
+    let (stdout, _, ok) = minilang(&["-e", "print(1 + 2)"]);
+    assert!(ok);
+    assert_eq!(stdout, "3\n");
+
+    let (stdout, _, ok) = minilang(&["--ast=sexp", "--eval", "let x = 1"]);
+    assert!(ok);
+    assert_eq!(stdout, "(let x 1)\n");
+
+    let (_, stderr, ok) = minilang(&["-e"]);
+    assert!(!ok);
+    assert!(stderr.contains("requires a program"));
+}
+
+#[test]
+fn cli_script_args() {
+    let (stdout, _, ok) = minilang(&["-e", "print(args())\nprint(len(args()))", "one", "--two", "-e"]);
+    assert!(ok);
+    assert_eq!(stdout, "[\"one\", \"--two\", \"-e\"]\n3\n");
+
+    let (stdout, _, ok) = minilang(&["-e", "print(len(args()))"]);
+    assert!(ok);
+    assert_eq!(stdout, "0\n");
+}
+
+#[test]
+fn cli_exit_sets_status() {
+    let (stdout, _, ok) = minilang(&["-e", "print(1)\nexit(0)\nprint(2)"]);
+    assert!(ok);
+    assert_eq!(stdout, "1\n");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(["-e", "fn fail() { exit(3) }\nprint(\"before\")\nfail()\nprint(\"after\")"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before\n");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn cli_run_subcommand() {
+    let (stdout, _, ok) = minilang(&["run", "examples/hello.ml"]);
+    assert!(ok);
+    assert_eq!(stdout, "Hello, world!\n");
+
+    let (_, stderr, ok) = minilang(&["run"]);
+    assert!(!ok);
+    assert!(stderr.contains("Usage: minilang run"));
+}
+
+#[test]
+fn cli_check_subcommand() {
+    let path = std::env::temp_dir().join(format!("minilang_check_{}.ml", std::process::id()));
+    std::fs::write(&path, "let = 1\n").unwrap();
+    let (_, stderr, ok) = minilang(&["check", "examples/hello.ml", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!ok);
+    assert!(stderr.contains("line 1"));
+    assert!(!stderr.contains("hello.ml"));
+
+    let (stdout, stderr, ok) = minilang(&["check", "examples/hello.ml"]);
+    assert!(ok);
+    assert!(stdout.is_empty() && stderr.is_empty());
+}
+
+#[test]
+fn cli_help_and_version() {
+    let (stdout, _, ok) = minilang(&["--help"]);
+    assert!(ok);
+    assert!(stdout.starts_with("Usage: minilang"));
+    assert!(stdout.contains("repl"));
+
+    let (stdout, _, ok) = minilang(&["--version"]);
+    assert!(ok);
+    assert_eq!(stdout, format!("minilang {}\n", env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn cli_output_before_runtime_error_is_printed() {
+    let (stdout, stderr, ok) = minilang(&["-e", "print(\"first\")\nprint(missing)"]);
+    assert!(!ok);
+    assert_eq!(stdout, "first\n");
+    assert!(stderr.contains("Undefined variable 'missing'"));
+}
+
+#[test]
+fn cli_input_reads_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(["-e", "let line = input()\nwhile line {\n  print(len(line))\n  line = input()\n}"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"abc\r\nhello\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n5\n");
+}
+
+#[test]
+fn cli_ast_json() {
+    let (stdout, _, ok) = minilang(&["--ast=json", "-e", "print(1)"]);
+    assert!(ok);
+    assert!(stdout.starts_with(r#"[{"type":"ExprStmt","expr":{"type":"Call""#));
+}
+
+#[test]
+fn cli_transpile() {
+    let (stdout, _, ok) = minilang(&["transpile", "examples/hello.ml"]);
+    assert!(ok);
+    assert!(stdout.ends_with("print(_ml_str(\"Hello, world!\"))\n"));
+
+    let (_, stderr, ok) = minilang(&["transpile", "--target", "cobol", "examples/hello.ml"]);
+    assert!(!ok);
+    assert!(stderr.contains("Unknown target 'cobol'"));
+}
+
+#[test]
+fn cli_profile_reports_to_stderr() {
+    let (stdout, stderr, ok) = minilang(&["--profile", "-e", "fn f() { return 1 }\nprint(f())"]);
+    assert!(ok);
+    assert_eq!(stdout, "1\n");
+    assert!(stderr.starts_with("function"));
+    assert!(stderr.lines().any(|l| l.starts_with("f ") && l.contains(" 1 ")));
+
+    let (_, stderr, ok) = minilang(&["--profile=folded", "-e", "fn f() { return 1 }\nf()"]);
+    assert!(ok);
+    assert!(stderr.starts_with("f "));
+}
+
+#[test]
+fn cli_test_subcommand() {
+    let path = std::env::temp_dir().join(format!("minilang_tests_{}.ml", std::process::id()));
+    std::fs::write(&path, "fn test_ok() { assert(true) }\nfn test_bad() { assert_eq(1 + 1, 3) }\n").unwrap();
+    let (stdout, _, ok) = minilang(&["test", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!ok);
+    assert!(stdout.contains("::test_ok ... ok"));
+    assert!(stdout.contains("::test_bad ... FAILED\n    Assertion failed: values differ\n      expected: 3\n        actual: 2\n"));
+    assert!(stdout.ends_with("1 passed, 1 failed\n"));
+}
+
+#[test]
+fn cli_run_in_project_directory() {
+    let root = std::env::temp_dir().join(format!("minilang_project_{}", std::process::id()));
+    let sub = root.join("lib").join("text");
+    std::fs::create_dir_all(&sub).unwrap();
+    std::fs::create_dir_all(root.join("scripts")).unwrap();
+    std::fs::write(
+        root.join("minilang.toml"),
+        "[project]\nname = \"demo\"\nentry = \"main.ml\"\nsources = [\"lib\"]\n\n[scripts]\nshout = \"scripts/shout.ml\"\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("lib").join("greet.ml"), "fn greet(name) { return \"hi \" + name }").unwrap();
+    std::fs::write(sub.join("bang.ml"), "fn bang(s) { return s + \"!\" }").unwrap();
+    std::fs::write(root.join("main.ml"), "print(bang(greet(\"ada\")))").unwrap();
+    std::fs::write(root.join("scripts").join("shout.ml"), "print(greet(args()[0]))").unwrap();
+
+    let run = |args: &[&str], dir: &std::path::Path| {
+        let output = Command::new(env!("CARGO_BIN_EXE_minilang")).args(args).current_dir(dir).output().unwrap();
+        (String::from_utf8_lossy(&output.stdout).to_string(), output.status.success())
+    };
+    assert_eq!(run(&["run"], &sub), ("hi ada!\n".to_string(), true));
+    assert_eq!(run(&["run", "shout", "all"], &root), ("hi all\n".to_string(), true));
+    assert_eq!(run(&["run", "whisper"], &root), (String::new(), false));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn cli_interactive_keeps_script_definitions() {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(["-i", "-e", "fn sq(x) { return x * x }\nprint(\"loaded\")"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"sq(7)\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("loaded\nminilang REPL"), "{}", stdout);
+    assert!(stdout.contains(">> 49\n"), "{}", stdout);
+}
+
+#[test]
+fn cli_watch_reruns_on_change() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    let path = std::env::temp_dir().join(format!("minilang_watch_{}.ml", std::process::id()));
+    std::fs::write(&path, "print(\"first\")").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(["watch", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut next_run = || {
+        let mut seen = Vec::new();
+        for line in lines.by_ref() {
+            let line = line.unwrap();
+            if line.starts_with("[watching") {
+                break;
+            }
+            seen.push(line);
+        }
+        seen
+    };
+    assert_eq!(next_run(), vec!["first"]);
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    std::fs::write(&path, "print(\"second\")").unwrap();
+    assert_eq!(next_run(), vec!["second"]);
+    child.kill().unwrap();
+    child.wait().unwrap();
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn cli_cache_dir_reuses_parsed_scripts() {
+    let dir = std::env::temp_dir().join(format!("minilang_cli_cache_{}", std::process::id()));
+    let dir_arg = dir.to_str().unwrap();
+    for _ in 0..2 {
+        let (stdout, _, ok) = minilang(&["run", "--cache-dir", dir_arg, "examples/hello.ml"]);
+        assert!(ok);
+        assert_eq!(stdout, "Hello, world!\n");
+    }
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_tools_handle_long_expressions() {
+    let path = std::env::temp_dir().join(format!("minilang_chain_{}.ml", std::process::id()));
+    std::fs::write(&path, format!("print({})\n", vec!["1"; 20_000].join(" + "))).unwrap();
+    let path = path.to_str().unwrap();
+    for args in [&["run", path][..], &["--ast=json", path], &["lint", path], &["fmt", "--check", path], &["transpile", path]] {
+        let (_, stderr, ok) = minilang(args);
+        assert!(ok, "{:?}: {}", args, stderr);
+    }
+    assert_eq!(minilang(&["run", path]).0, "20000\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_serve_sandboxes_and_limits_scripts_by_default() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(["serve", "--port", "0"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to start minilang");
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap()).read_line(&mut banner).unwrap();
+    let addr = banner.trim().strip_prefix("Listening on http://").unwrap().to_string();
+
+    let run = |source: &str| {
+        let mut stream = std::net::TcpStream::connect(&addr).unwrap();
+        write!(stream, "POST /run HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", source.len(), source).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let looping = run("while true {}");
+    let reading = run("print(input())");
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(looping.ends_with(r#"{"output":[],"error":"Step limit exceeded"}"#), "{}", looping);
+    assert!(reading.contains("capability 'stdin' is disabled"), "{}", reading);
+}
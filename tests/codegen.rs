@@ -0,0 +1,61 @@
+use std::process::Command;
+
+use minilang::codegen::python::transpile;
+use minilang::parser::parse_source;
+
+fn python(source: &str) -> String {
+    transpile(&parse_source(source).unwrap()).unwrap()
+}
+
+fn body(code: &str) -> &str {
+    // Skip the runtime helpers that precede every program.
+    code.rsplit_once("\n\n").map_or(code, |(_, rest)| rest)
+}
+
+#[test]
+fn python_statements_and_expressions() {
+    let code = python("let x = [1, 2.5, \"a\"]\nx[0] = -x[1] % 2\nif not true and x { print(x) } else { }");
+    assert_eq!(
+        body(&code),
+        "x = [1, 2.5, \"a\"]\n\
+         x[int(0)] = math.fmod((-x[int(1)]), 2)\n\
+         if ((not True) and x):\n    print(_ml_str(x))\nelse:\n    pass\n"
+    );
+}
+
+#[test]
+fn python_functions_declare_outer_assignments() {
+    let code = python("let count = 0\nfn bump(n) {\n  let local = n\n  count = count + local\n  return count\n}\nreturn");
+    assert!(body(&code).contains(
+        "def bump(n):\n    global count\n    local = n\n    count = (count + local)\n    return count\nraise SystemExit\n"
+    ));
+}
+
+#[test]
+fn python_output_matches_interpreter() {
+    if Command::new("python3").arg("--version").output().is_err() {
+        return;
+    }
+    for example in ["examples/fizzbuzz.ml", "examples/quicksort.ml", "examples/fibonacci.ml"] {
+        let source = std::fs::read_to_string(example).unwrap();
+        let path = std::env::temp_dir().join(format!("minilang_codegen_{}.py", std::process::id()));
+        std::fs::write(&path, python(&source)).unwrap();
+        let py = Command::new("python3").arg(&path).output().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let ml = Command::new(env!("CARGO_BIN_EXE_minilang")).arg(example).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&py.stdout), String::from_utf8_lossy(&ml.stdout), "{}", example);
+    }
+}
+
+#[test]
+fn python_for_each() {
+    let code = python("for c in \"ab\" {\n  print(c)\n}");
+    assert_eq!(body(&code), "for c in \"ab\":\n    print(_ml_str(c))\n");
+}
+
+#[test]
+fn python_builtin_calls_the_original() {
+    assert_eq!(body(&python("builtin(\"print\", len([1]))")), "print(_ml_str(len([1])))\n");
+    let err = transpile(&parse_source("fn len(x) { return 0 }").unwrap()).unwrap_err();
+    assert_eq!(err, "Redefining builtin 'len' is not supported by the Python backend");
+}
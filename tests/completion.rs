@@ -0,0 +1,27 @@
+use minilang::completion::{candidates, common_prefix, complete};
+use minilang::interpreter::Interpreter;
+use minilang::parser::parse_source;
+
+fn words(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn completion_candidates_include_scope_builtins_and_keywords() {
+    let mut interp = Interpreter::new();
+    let program = parse_source("let counter = 1\nfn greet() { return 1 }").unwrap();
+    interp.run(&program).unwrap();
+    let names = candidates(&interp);
+    for name in ["counter", "greet", "print", "len", "while", "return"] {
+        assert!(names.contains(&name.to_string()), "missing {}", name);
+    }
+}
+
+#[test]
+fn completion_matches_word_before_cursor() {
+    let names = words(&["print", "prefix", "len"]);
+    assert_eq!(complete("x = pr", 6, &names), (4, words(&["print", "prefix"])));
+    assert_eq!(complete("len(pri)", 7, &names), (4, words(&["print"])));
+    assert_eq!(complete("x = ", 4, &names), (4, Vec::new()));
+    assert_eq!(common_prefix(&words(&["print", "prefix"])), "pr");
+}
@@ -0,0 +1,30 @@
+use minilang::csv::{parse, stringify};
+
+fn row(fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|f| f.to_string()).collect()
+}
+
+#[test]
+fn parses_quoted_fields() {
+    let text = "name,note\r\nada,\"says \"\"hi\"\", twice\"\nbob,\"two\nlines\"\n,\"\"";
+    assert_eq!(
+        parse(text).unwrap(),
+        vec![
+            row(&["name", "note"]),
+            row(&["ada", "says \"hi\", twice"]),
+            row(&["bob", "two\nlines"]),
+            row(&["", ""]),
+        ]
+    );
+    assert_eq!(parse("").unwrap(), Vec::<Vec<String>>::new());
+    assert_eq!(parse("a,\"b\nc").unwrap_err(), "Unterminated quoted field starting on line 1");
+    assert_eq!(parse("\"a\"b").unwrap_err(), "Unexpected text after a quoted field on line 1");
+}
+
+#[test]
+fn stringify_round_trips() {
+    let rows = vec![row(&["a,b", "plain", ""]), row(&["x\"y", "two\nlines", "z"])];
+    let text = stringify(&rows);
+    assert_eq!(text, "\"a,b\",plain,\n\"x\"\"y\",\"two\nlines\",z\n");
+    assert_eq!(parse(&text).unwrap(), rows);
+}
@@ -0,0 +1,52 @@
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
+
+use minilang::debugger;
+use minilang::interpreter::Interpreter;
+use minilang::parser::parse_source;
+
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn debug(source: &str, commands: &str) -> (Interpreter, Result<(), String>, String) {
+    let program = parse_source(source).unwrap();
+    let mut interp = Interpreter::new();
+    let out = Shared::default();
+    let input = Box::new(Cursor::new(commands.to_string()));
+    let result = debugger::run(&mut interp, &program, source, input, Box::new(out.clone()));
+    let transcript = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+    (interp, result, transcript)
+}
+
+const SOURCE: &str = "fn f(a) {\n  let b = a * 2\n  return b\n}\nlet x = f(3)\nprint(x)\n";
+
+#[test]
+fn debugger_breakpoints_and_vars() {
+    let (interp, result, transcript) = debug(SOURCE, "break 3\ncontinue\nvars\ncontinue\n");
+    assert_eq!(result, Ok(()));
+    assert_eq!(interp.output, vec!["6"]);
+    assert!(transcript.starts_with("Stopped at line 1: fn f(a) {\n"));
+    assert!(transcript.contains("Breakpoint at line 3\n"));
+    assert!(transcript.contains("Stopped at line 3: return b\n(debug) scope 0:\n  a = 3\n  b = 6\nglobals:\n  fn f(a)\n"));
+}
+
+#[test]
+fn debugger_steps_and_quits() {
+    let (interp, result, transcript) = debug(SOURCE, "s\ns\ns\nq\n");
+    assert_eq!(result, Ok(()));
+    assert!(interp.output.is_empty());
+    let stops: Vec<&str> = transcript.lines().filter(|l| l.contains("Stopped")).collect();
+    assert_eq!(stops.len(), 4);
+    assert!(stops[3].ends_with("Stopped at line 3: return b"));
+}
@@ -0,0 +1,29 @@
+use minilang::docgen;
+use minilang::parser::{from_json, parse_source, to_json};
+
+const SOURCE: &str = "## Adds two numbers.\n##\n## Strings concatenate.\nfn add(a, b) {\n  ## Not documentation of add.\n  return a + b\n}\n# plain comment\nfn helper() { }\n";
+
+#[test]
+fn doc_comments_attach_to_the_next_statement() {
+    let program = parse_source(SOURCE).unwrap();
+    assert_eq!(program[0].doc.as_deref(), Some("Adds two numbers.\n\nStrings concatenate."));
+    assert_eq!(program[1].doc, None);
+    assert_eq!(from_json(&to_json(&program)).unwrap(), program);
+}
+
+#[test]
+fn markdown_lists_functions_with_their_docs() {
+    let program = parse_source(SOURCE).unwrap();
+    assert_eq!(
+        docgen::markdown("lib.ml", &program),
+        "# lib.ml\n\n## `add(a, b)`\n\nAdds two numbers.\n\nStrings concatenate.\n\n## `helper()`\n"
+    );
+    assert!(docgen::html("a<b>", &program).contains("<title>a&lt;b&gt;</title>"));
+}
+
+#[test]
+fn docstrings_document_functions_without_doc_comments() {
+    let program = parse_source("fn area(w, h) {\n  \"Area of a\n   w by h rectangle.\"\n  return w * h\n}\n## Wins.\nfn f() {\n  \"Loses.\"\n}").unwrap();
+    let docs: Vec<_> = docgen::functions(&program).into_iter().map(|f| f.doc).collect();
+    assert_eq!(docs, vec![Some("Area of a\nw by h rectangle.".to_string()), Some("Wins.".to_string())]);
+}
@@ -0,0 +1,445 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use minilang::interpreter::{DebugHook, Interpreter, InterpreterConfig, Output, Value};
+use minilang::parser::parse_source;
+use minilang::program::Program;
+use minilang::state::InterpreterState;
+
+fn run_with(interp: &mut Interpreter, source: &str) -> Result<Vec<String>, String> {
+    interp.run(&parse_source(source)?)?;
+    Ok(interp.output.clone())
+}
+
+#[test]
+fn register_fn_exposes_host_function() {
+    let mut interp = Interpreter::new();
+    interp.register_fn("double", |args| match args {
+        [Value::Number(n)] => Ok(Value::Number(n * 2.0)),
+        _ => Err("double() requires one number".to_string()),
+    });
+    assert_eq!(run_with(&mut interp, "print(double(21))").unwrap(), vec!["42"]);
+    assert_eq!(
+        run_with(&mut interp, "double(\"x\")").unwrap_err(),
+        "double() requires one number"
+    );
+}
+
+#[test]
+fn register_fn_can_capture_host_state_and_override_builtins() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let mut interp = Interpreter::new();
+    interp.register_fn("print", move |args| {
+        log.lock().unwrap().push(args.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" "));
+        Ok(Value::Null)
+    });
+    run_with(&mut interp, "print(1, \"two\")\nprint([3])").unwrap();
+    assert!(interp.output.is_empty());
+    assert_eq!(*seen.lock().unwrap(), vec!["1 two", "[3]"]);
+}
+
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn output_writer_receives_printed_lines() {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let mut interp = Interpreter::new();
+    interp.set_output(Output::Writer(Box::new(SharedBuffer(buffer.clone()))));
+    run_with(&mut interp, "print(1)\nprint(\"two\")").unwrap();
+    assert!(interp.output.is_empty());
+    assert_eq!(String::from_utf8(buffer.lock().unwrap().clone()).unwrap(), "1\ntwo\n");
+
+    interp.set_output(Output::Capture);
+    run_with(&mut interp, "print(3)").unwrap();
+    assert_eq!(interp.output, vec!["3"]);
+}
+
+#[test]
+fn input_reads_from_configured_source() {
+    let mut interp = Interpreter::new();
+    let lines: VecDeque<String> = ["alice", "3"].iter().map(|s| s.to_string()).collect();
+    interp.set_input(lines);
+    let out = run_with(
+        &mut interp,
+        "let name = input()\nprint(\"hi \" + name)\nprint(input())\nprint(input())",
+    )
+    .unwrap();
+    assert_eq!(out, vec!["hi alice", "3", "null"]);
+}
+
+#[test]
+fn step_limit_aborts_long_running_scripts() {
+    let mut interp = Interpreter::new();
+    interp.set_step_limit(10_000);
+    let err = run_with(&mut interp, "let i = 0\nwhile true { i = i + 1 }").unwrap_err();
+    assert_eq!(err, "Step limit exceeded");
+
+    // The budget is per run, so a short script afterwards still works.
+    assert_eq!(run_with(&mut interp, "print(i > 100)").unwrap(), vec!["true"]);
+}
+
+#[test]
+fn eval_does_not_catch_limits_or_hook_aborts() {
+    let mut interp = Interpreter::new();
+    interp.set_step_limit(10_000);
+    let err = run_with(&mut interp, "let r = eval(\"while true { }\")\nprint(\"caught\")").unwrap_err();
+    assert_eq!(err, "Step limit exceeded");
+    assert!(interp.output.is_empty());
+    // Ordinary errors are still values on the next run.
+    assert_eq!(run_with(&mut interp, "print(is_error(eval(\"nope\")))").unwrap(), vec!["true"]);
+
+    let mut interp = Interpreter::new();
+    interp.set_memory_limit(4096);
+    let grow = "fn grow() {\n  let s = \"ab\"\n  while true { s = s + s }\n}\n";
+    let err = run_with(&mut interp, &format!("{}eval(\"grow()\")\nprint(1)", grow)).unwrap_err();
+    assert_eq!(err, "Memory limit exceeded");
+
+    struct StopAt(usize);
+    impl DebugHook for StopAt {
+        fn before_stmt(&mut self, _: &Interpreter, line: usize) -> Result<(), String> {
+            if line == self.0 { Err("stopped".to_string()) } else { Ok(()) }
+        }
+    }
+    let mut interp = Interpreter::new();
+    interp.set_debug_hook(Some(Box::new(StopAt(2))));
+    let err = run_with(&mut interp, "fn f() {\n  print(\"inside\")\n}\nprint(eval(\"f()\"))\nprint(\"after\")").unwrap_err();
+    assert_eq!(err, "stopped");
+    assert!(interp.output.is_empty());
+}
+
+#[test]
+fn expression_statements_in_the_repl_and_eval_are_full_statements() {
+    let mut interp = Interpreter::new();
+    interp.set_step_limit(3);
+    let program = parse_source("1\n2\n3\n4").unwrap();
+    assert_eq!(interp.run_interactive(&program).unwrap_err(), "Step limit exceeded");
+
+    let mut interp = Interpreter::new();
+    interp.set_strict_math(true);
+    let program = parse_source("1\n0 / 0").unwrap();
+    assert!(interp.run_interactive(&program).unwrap_err().ends_with("at line 2"));
+
+    struct Lines(Arc<Mutex<Vec<usize>>>);
+    impl DebugHook for Lines {
+        fn before_stmt(&mut self, _: &Interpreter, line: usize) -> Result<(), String> {
+            self.0.lock().unwrap().push(line);
+            Ok(())
+        }
+    }
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut interp = Interpreter::new();
+    interp.set_debug_hook(Some(Box::new(Lines(seen.clone()))));
+    let program = parse_source("let a = 1\na + 1").unwrap();
+    interp.run_interactive(&program).unwrap();
+    run_with(&mut interp, "print(eval(\"a + 1\"))").unwrap();
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2, 1, 1]);
+}
+
+#[test]
+fn stack_limit_bounds_recursion() {
+    let src = "fn f(n) { if n == 0 { return 0 } return f(n - 1) + 1 }\nprint(f(200))";
+    let big = std::thread::Builder::new()
+        .stack_size(64 << 20)
+        .spawn(move || {
+            let mut interp = Interpreter::new();
+            interp.set_stack_limit(60 << 20);
+            run_with(&mut interp, src)
+        })
+        .unwrap();
+    assert_eq!(big.join().unwrap().unwrap(), vec!["200"]);
+
+    let mut interp = Interpreter::new();
+    interp.set_stack_limit(64 << 10);
+    assert_eq!(run_with(&mut interp, src).unwrap_err(), "Maximum recursion depth exceeded");
+    // Unwinding resets the depth, so the interpreter stays usable.
+    assert_eq!(run_with(&mut interp, "print(1)").unwrap(), vec!["1"]);
+}
+
+#[test]
+fn strict_math_rejects_division_by_zero_and_nan() {
+    let mut interp = Interpreter::new();
+    assert_eq!(run_with(&mut interp, "print(1 / 0)\nprint(0 / 0 == 0 / 0)").unwrap(), vec!["inf", "false"]);
+
+    interp.set_strict_math(true);
+    assert_eq!(run_with(&mut interp, "let x = 1\nprint(x / 0)").unwrap_err(), "Division by zero at line 2");
+    assert_eq!(run_with(&mut interp, "fn f(n) {\n  return 5 % n\n}\nf(0)").unwrap_err(), "Modulo by zero at line 2");
+    let err = run_with(&mut interp, "let big = 1\nfor i in 0..400 { big = big * 10 }\nprint(big - big)").unwrap_err();
+    assert_eq!(err, "'-' produced NaN at line 3");
+    interp.output.clear();
+    assert_eq!(run_with(&mut interp, "print(7 % 4 / 2)").unwrap(), vec!["1.5"]);
+}
+
+#[test]
+fn strict_equality_rejects_mixed_type_comparisons() {
+    let mut interp = Interpreter::new();
+    interp.set_strict_equality(true);
+    assert_eq!(
+        run_with(&mut interp, "let n = 1\nprint(n == \"1\")").unwrap_err(),
+        "Cannot compare number with string using '==' at line 2 (use '===' for a check that allows it)"
+    );
+    assert!(run_with(&mut interp, "print([1] != true)").unwrap_err().contains("array with bool using '!='"));
+    assert_eq!(
+        run_with(&mut interp, "print(1 === \"1\")\nprint(1 !== \"1\")\nprint(input() == 2)\nprint(2 == 2)").unwrap(),
+        vec!["false", "true", "false", "true"]
+    );
+}
+
+#[test]
+fn strict_vars_rejects_assignment_to_undeclared_names() {
+    let mut interp = Interpreter::new();
+    run_with(&mut interp, "fresh = 1").unwrap();
+
+    interp.set_strict_vars(true);
+    assert_eq!(
+        run_with(&mut interp, "let total = 0\nfor x in [1, 2] {\n  totl = total + x\n}").unwrap_err(),
+        "Assignment to undeclared variable 'totl' at line 3 (declare it with 'let' first)"
+    );
+    assert!(run_with(&mut interp, "let a = 1\na, b = 2, 3").unwrap_err().contains("'b' at line 2"));
+    assert_eq!(
+        run_with(&mut interp, "let n = 0\nfn bump() {\n  n = n + 1\n}\nbump()").unwrap_err(),
+        "Assignment to outer variable 'n' at line 3 (declare it with 'global n' first)"
+    );
+    assert_eq!(
+        run_with(&mut interp, "let n = 0\nfn bump() {\n  global n\n  n = n + 1\n}\nbump()\nfresh = 2\nprint([n, fresh])")
+            .unwrap(),
+        vec!["[1, 2]"]
+    );
+}
+
+#[test]
+fn memory_limit_stops_doubling_strings_and_arrays() {
+    let mut interp = Interpreter::new();
+    interp.set_memory_limit(4096);
+    let err = run_with(&mut interp, "let s = \"ab\"\nwhile true { s = s + s }").unwrap_err();
+    assert_eq!(err, "Memory limit exceeded");
+
+    let err = run_with(&mut interp, "let a = [1]\nwhile true { a = a + a }").unwrap_err();
+    assert_eq!(err, "Memory limit exceeded");
+
+    // collect() counts the strings it gathers, not just the elements.
+    let src = "let s = \"ab\"\nwhile len(s) < 1024 { s = s + s }\nfn big(x) {\n  return s\n}\nprint(len(collect(map_lazy(iter([1, 2, 3, 4, 5]), big))))";
+    assert_eq!(run_with(&mut interp, src).unwrap_err(), "Memory limit exceeded");
+
+    assert_eq!(run_with(&mut interp, "print(len(\"ok\" + \"!\"))").unwrap(), vec!["3"]);
+}
+
+#[test]
+fn run_with_timeout_aborts_infinite_loop() {
+    let mut interp = Interpreter::new();
+    let program = parse_source("while true { }").unwrap();
+    let start = Instant::now();
+    let err = interp.run_with_timeout(&program, Duration::from_millis(50)).unwrap_err();
+    assert!(err.starts_with("Timeout"), "{}", err);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let program = parse_source("print(1)").unwrap();
+    interp.run_with_timeout(&program, Duration::from_secs(5)).unwrap();
+    assert_eq!(interp.output, vec!["1"]);
+}
+
+#[test]
+fn sandboxed_config_disables_privileged_builtins() {
+    let mut interp = Interpreter::new();
+    interp.set_config(InterpreterConfig::sandboxed());
+    let err = run_with(&mut interp, "exit(1)").unwrap_err();
+    assert_eq!(err, "exit() is unavailable: capability 'process' is disabled");
+    assert!(interp.exit_code().is_none());
+    let err = run_with(&mut interp, "run(\"ls\")").unwrap_err();
+    assert_eq!(err, "run() is unavailable: capability 'process' is disabled");
+    let err = run_with(&mut interp, "print(args())").unwrap_err();
+    assert!(err.contains("capability 'env' is disabled"));
+    assert_eq!(run_with(&mut interp, "eval(\"1\")").unwrap_err(), "eval() is unavailable: capability 'eval' is disabled");
+    assert_eq!(run_with(&mut interp, "input()").unwrap_err(), "input() is unavailable: capability 'stdin' is disabled");
+    assert_eq!(run_with(&mut interp, "print(len([1, 2]))").unwrap(), vec!["2"]);
+
+    interp.set_config(InterpreterConfig {
+        process: true,
+        ..InterpreterConfig::sandboxed()
+    });
+    run_with(&mut interp, "exit(1)").unwrap();
+    assert_eq!(interp.exit_code(), Some(1));
+}
+
+#[test]
+fn audit_log_records_capability_gated_calls() {
+    let mut interp = Interpreter::new();
+    interp.set_config(InterpreterConfig {
+        env: true,
+        eval: true,
+        ..InterpreterConfig::sandboxed()
+    });
+    interp.set_args(vec!["a".to_string()]);
+    interp.enable_audit();
+    let err = run_with(
+        &mut interp,
+        "let flags = [\"-l\"]\nprint(eval(\"len(args())\"))\nprint(len([1]))\nrun(\"rm\", flags)\nexit(1)",
+    )
+    .unwrap_err();
+    assert_eq!(err, "run() is unavailable: capability 'process' is disabled");
+    let log: Vec<String> = interp.audit_log().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        log,
+        vec!["line 2: eval(\"len(args())\")", "line 1: args()", "line 4: run() [denied]"]
+    );
+    assert!(interp.audit_log()[0].allowed);
+
+    // A refused call evaluates none of its arguments, with or without the log.
+    let src = "let seen = []\nfn note(x) {\n  push(seen, x)\n  return x\n}\nlet r = eval(\"exit(note(3))\")\nprint(seen)";
+    interp.set_config(InterpreterConfig {
+        eval: true,
+        ..InterpreterConfig::sandboxed()
+    });
+    assert_eq!(run_with(&mut interp, src).unwrap().last().unwrap(), "[]");
+    let denied = interp.audit_log().last().unwrap();
+    assert_eq!((denied.builtin, denied.args.len(), denied.allowed), ("exit", 0, false));
+
+    assert!(Interpreter::new().audit_log().is_empty());
+}
+
+#[test]
+fn snapshot_round_trips_through_json() {
+    let mut interp = Interpreter::new();
+    run_with(
+        &mut interp,
+        "let count = 2\nlet names = [\"a\", [true, 1 / 0]]\nfn bump(n) {\n  return n + count\n}\nprint(\"setup\")",
+    )
+    .unwrap();
+    let text = interp.snapshot().to_json().unwrap();
+
+    let mut restored = Interpreter::new();
+    run_with(&mut restored, "let stale = 1").unwrap();
+    restored.restore(InterpreterState::from_json(&text).unwrap());
+    assert!(restored.output.is_empty());
+    assert_eq!(
+        run_with(&mut restored, "print(bump(40))\nprint(names)").unwrap(),
+        vec!["42", "[\"a\", [true, inf]]"]
+    );
+    assert!(run_with(&mut restored, "print(stale)").is_err());
+
+    let mut cyclic = Interpreter::new();
+    run_with(&mut cyclic, "let a = [1]\npush(a, [a])").unwrap();
+    assert_eq!(
+        cyclic.snapshot().to_json().unwrap_err(),
+        "Cannot save 'a': an array that contains itself cannot be stored"
+    );
+    run_with(&mut cyclic, "let a = [1]\nlet b = [a, a]").unwrap();
+    assert!(cyclic.snapshot().to_json().is_ok());
+
+    assert!(InterpreterState::from_json("[1]").is_err());
+    assert!(InterpreterState::from_json(r#"{"f": {"params": [1], "body": []}}"#).is_err());
+}
+
+#[test]
+fn with_globals_seeds_bindings() {
+    let config = Value::array(vec![Value::Str("debug".to_string()), Value::Number(3.0)]);
+    let mut interp = Interpreter::with_globals([("config", config), ("user", Value::Str("ada".to_string()))]);
+    assert_eq!(
+        run_with(&mut interp, "print(user)\nprint(config[1] + 1)").unwrap(),
+        vec!["ada", "4"]
+    );
+}
+
+#[test]
+fn frozen_globals_cannot_be_changed_by_scripts() {
+    let config = Value::array(vec![Value::Str("debug".to_string())]).freeze();
+    let mut interp = Interpreter::with_globals([("config", config.clone())]);
+    assert_eq!(
+        run_with(&mut interp, "push(config, 1)").unwrap_err(),
+        "FrozenError: cannot push to a frozen array"
+    );
+    assert_eq!(config.to_string(), "[\"debug\"]");
+
+    let text = interp.snapshot().to_json().unwrap();
+    let mut restored = Interpreter::new();
+    restored.restore(InterpreterState::from_json(&text).unwrap());
+    assert_eq!(run_with(&mut restored, "print(is_frozen(config))").unwrap(), vec!["true"]);
+}
+
+#[test]
+fn function_values_share_the_parsed_body() {
+    let program = parse_source("fn f(x) {\n  return x\n}").unwrap();
+    let mut interp = Interpreter::new();
+    interp.run(&program).unwrap();
+    let minilang::parser::StmtKind::Fn(_, _, parsed) = &program[0].kind else { panic!() };
+    let Some((_, Value::Function { body, .. })) = interp.variables().into_iter().next() else { panic!() };
+    assert!(Arc::ptr_eq(parsed, &body));
+}
+
+#[test]
+fn profiling_counts_calls_per_function() {
+    let mut interp = Interpreter::new();
+    interp.enable_profiling();
+    let src = "fn fib(n) {\n  if n < 2 { return n }\n  return fib(n - 1) + fib(n - 2)\n}\n\
+               fn main() { return fib(10) }\nfn even(x) { return x % 2 == 0 }\nmain()\nfind([1, 2], even)";
+    run_with(&mut interp, src).unwrap();
+    let profile = interp.profile().unwrap();
+    let mut calls: Vec<(String, u64)> = profile.functions().into_iter().map(|(name, s)| (name, s.calls)).collect();
+    calls.sort();
+    assert_eq!(calls, vec![("even".to_string(), 2), ("fib".to_string(), 177), ("main".to_string(), 1)]);
+    let main = profile.functions().into_iter().find(|(name, _)| name == "main").unwrap().1;
+    assert!(main.total >= main.self_time);
+    assert!(profile.folded().lines().any(|l| l.starts_with("main;fib;fib ")));
+}
+
+#[test]
+fn programs_and_interpreters_cross_threads() {
+    let program = Arc::new(parse_source("let total = 0\nfor i in 0..n { total = total + i }\nprint(total)").unwrap());
+    let workers: Vec<_> = (1..=4)
+        .map(|n| {
+            let program = Arc::clone(&program);
+            std::thread::spawn(move || {
+                let mut interp = Interpreter::with_globals([("n", Value::Number(n as f64 * 10.0))]);
+                interp.run(&program).unwrap();
+                interp
+            })
+        })
+        .collect();
+    let outputs: Vec<_> = workers.into_iter().map(|w| w.join().unwrap().output).collect();
+    assert_eq!(outputs, vec![vec!["45"], vec!["190"], vec!["435"], vec!["780"]]);
+
+    let mut interp = Interpreter::new();
+    run_with(&mut interp, "let shared = [1]").unwrap();
+    let (_, shared) = interp.variables().into_iter().find(|(name, _)| name == "shared").unwrap();
+    let mut interp = std::thread::spawn(move || {
+        run_with(&mut interp, "push(shared, 2)").unwrap();
+        interp
+    })
+    .join()
+    .unwrap();
+    assert_eq!(shared.to_string(), "[1, 2]");
+    assert_eq!(run_with(&mut interp, "print(shared + shared)").unwrap(), vec!["[1, 2, 1, 2]"]);
+}
+
+#[test]
+fn program_runs_in_many_interpreters_without_copying() {
+    let program = Program::parse("fn f(x) {\n  return x * 2\n}\nprint(f(n))").unwrap();
+    let minilang::parser::StmtKind::Fn(_, _, parsed) = &program.stmts()[0].kind else { panic!() };
+    for n in 0..3 {
+        let mut interp = Interpreter::with_globals([("n", Value::Number(n as f64))]);
+        interp.run_program(&program).unwrap();
+        assert_eq!(interp.output, vec![(n * 2).to_string()]);
+        let Some((_, Value::Function { body, .. })) = interp.variables().into_iter().find(|(name, _)| name == "f") else {
+            panic!()
+        };
+        assert!(Arc::ptr_eq(parsed, &body));
+    }
+    assert!(Program::parse("let = 1").is_err());
+
+    let pool = minilang::pool::InterpreterPool::new(1);
+    let program = Program::parse("print(1 + 1)").unwrap();
+    assert_eq!(pool.execute_program(&program).output, vec!["2"]);
+    assert_eq!(pool.execute_program(&program).output, vec!["2"]);
+}
@@ -0,0 +1,35 @@
+use minilang::formatter::format_source;
+
+#[test]
+fn fmt_normalizes_spacing() {
+    assert_eq!(
+        format_source("let   x=1+2*3\nprint( x )").unwrap(),
+        "let x = 1 + 2 * 3\nprint(x)\n"
+    );
+}
+
+#[test]
+fn fmt_reindents_blocks() {
+    let src = "fn f(a,b){\nif a>b{\nreturn -a\n}\nelse\n{\nreturn b\n}\n}\n";
+    let expected = "fn f(a, b) {\n  if a > b {\n    return -a\n  } else {\n    return b\n  }\n}\n";
+    assert_eq!(format_source(src).unwrap(), expected);
+}
+
+#[test]
+fn fmt_preserves_comments_and_blank_lines() {
+    let src = "# header\n\n\n\nlet x = 1   # trailing\nfor i in 0 .. 3 { print(x[i]) }\n";
+    let expected = "# header\n\nlet x = 1  # trailing\nfor i in 0..3 { print(x[i]) }\n";
+    assert_eq!(format_source(src).unwrap(), expected);
+}
+
+#[test]
+fn fmt_is_idempotent() {
+    let src = std::fs::read_to_string("examples/brainfuck.ml").unwrap();
+    let once = format_source(&src).unwrap();
+    assert_eq!(format_source(&once).unwrap(), once);
+}
+
+#[test]
+fn fmt_rejects_invalid_programs() {
+    assert!(format_source("let = 1").is_err());
+}
@@ -0,0 +1,30 @@
+use minilang::highlight::{TokenClass, highlight};
+
+#[test]
+fn highlight_classifies_tokens_and_comments() {
+    let source = "# greet\nlet s = \"hi\" + 1\n";
+    let tokens = highlight(source).unwrap();
+    let classes: Vec<TokenClass> = tokens.iter().map(|t| t.class).collect();
+    assert_eq!(
+        classes,
+        vec![
+            TokenClass::Comment,
+            TokenClass::Keyword,
+            TokenClass::Identifier,
+            TokenClass::Operator,
+            TokenClass::String,
+            TokenClass::Operator,
+            TokenClass::Number,
+        ]
+    );
+    let chars: Vec<char> = source.chars().collect();
+    let text = |i: usize| chars[tokens[i].span.start..tokens[i].span.end].iter().collect::<String>();
+    assert_eq!(text(0), "# greet");
+    assert_eq!(text(4), "\"hi\"");
+    assert_eq!((tokens[1].span.line, tokens[1].span.col), (2, 1));
+}
+
+#[test]
+fn highlight_reports_lexer_errors() {
+    assert!(highlight("let s = \"open").is_err());
+}
@@ -139,6 +139,160 @@ fn array_len() {
     assert_eq!(run_ok("print(len([10, 20, 30]))"), vec!["3"]);
 }
 
+#[test]
+fn array_spread_in_literals_and_calls() {
+    assert_eq!(
+        run_ok(
+            "let rest = [2, 3]\nprint([1, ...rest, 9])\nprint([...[], ...rest])\n\
+             fn add3(a, b, c) { return a + b + c }\nprint(add3(...rest, 10))\nprint(add3(...[1, 2, 3]))"
+        ),
+        vec!["[1, 2, 3, 9]", "[2, 3]", "15", "6"]
+    );
+    assert_eq!(run_err("print([...5])"), "Cannot spread a number; '...' requires an array");
+    assert_eq!(run_err("print(...[1])"), "print() does not accept '...' arguments");
+    assert!(run_err("let x = ...[1]").contains("Unexpected token Ellipsis"));
+}
+
+#[test]
+fn array_identity() {
+    assert_eq!(
+        run_ok(
+            "let a = [1]\nlet b = a\nlet c = [1]\nfn f() { return 1 }\nlet g = f\n\
+             print([a is b, a is c, a is not c, g is f, 1 is 1, 1 is \"1\"])"
+        ),
+        vec!["[true, false, true, true, true, false]"]
+    );
+}
+
+#[test]
+fn for_each_over_iterator_function() {
+    let src = "let lines = [\"a\", \"b\", \"c\"]\nlet pos = 0\n\
+               fn next_line() {\n  if pos == len(lines) { return }\n  pos = pos + 1\n  return lines[pos - 1]\n}\n\
+               for line in next_line {\n  if line == \"b\" { continue }\n  print(line)\n}\nprint(pos)";
+    assert_eq!(run_ok(src), vec!["a", "c", "3"]);
+    assert_eq!(
+        run_err("fn f(x) { return x }\nfor x in f { }"),
+        "For-each over a function requires one that takes no arguments"
+    );
+}
+
+#[test]
+fn lazy_iterator_adapters() {
+    let src = "let calls = 0\nfn sq(x) {\n  global calls\n  calls = calls + 1\n  return x * x\n}\n\
+               let big = []\nfor i in 0..1000 { push(big, i) }\n\
+               print(collect(take(map_lazy(skip(big, 10), sq), 3)))\nprint(calls)";
+    assert_eq!(run_ok(src), vec!["[100, 121, 144]", "3"]);
+    assert_eq!(
+        run_ok("let it = iter(\"héllo\")\nprint(collect(take(it, 2)))\nfor c in it { print(c) }\nprint(collect(it))"),
+        vec!["[\"h\", \"é\"]", "l", "l", "o", "[]"]
+    );
+    assert_eq!(run_ok("print(iter([]))\nprint(collect(skip([1, 2], 5)))"), vec!["<iterator>", "[]"]);
+    assert_eq!(run_err("take(5, 1)"), "take() requires an array, string, function without parameters or iterator");
+}
+
+#[test]
+fn break_and_continue() {
+    assert_eq!(
+        run_ok("let i = 0\nwhile true {\n  i = i + 1\n  if i < 3 { continue }\n  break\n}\nprint(i)"),
+        vec!["3"]
+    );
+    assert_eq!(
+        run_ok("for x in [1, 2, 3, 4] {\n  if x == 2 { continue }\n  if x == 4 { break }\n  print(x)\n}"),
+        vec!["1", "3"]
+    );
+    assert_eq!(
+        run_ok("fn f() {\n  for i in 0..10 {\n    if i == 2 { return i * 10 }\n  }\n}\nprint(f())"),
+        vec!["20"]
+    );
+    assert!(run_err("break").starts_with("'break' outside a loop"));
+    assert!(run_err("while true {\n  fn f() { continue }\n}").starts_with("'continue' outside a loop"));
+}
+
+#[test]
+fn labeled_loops() {
+    assert_eq!(
+        run_ok(
+            "outer: for r in 0..3 {\n  for c in 0..3 {\n    if c > r { continue outer }\n    \
+             if r == 2 { break outer }\n    print([r, c])\n  }\n}"
+        ),
+        vec!["[0, 0]", "[1, 0]", "[1, 1]"]
+    );
+    assert_eq!(
+        run_ok("let n = 0\nloop: while true {\n  for x in [1, 2] {\n    n = n + x\n    if n > 4 { break loop }\n  }\n}\nprint(n)"),
+        vec!["6"]
+    );
+    assert!(run_err("while true { break nope }").starts_with("Unknown loop label 'nope' in 'break'"));
+    assert!(run_err("x: print(1)").starts_with("Expected a loop after label 'x:'"));
+}
+
+#[test]
+fn error_values_and_propagation() {
+    let src = "fn half(n) {\n  if n % 2 == 1 { return error(\"odd: \" + repr(n)) }\n  return n / 2\n}\n\
+               fn quarter(n) {\n  return half(half(n)?)?\n}\n\
+               print(quarter(8))\nlet e = quarter(6)\nprint(e)\nprint([is_error(e), error_message(e), e == error(\"odd: 3\")])\n\
+               print([e, not e])";
+    assert_eq!(
+        run_ok(src),
+        vec!["2", "error: odd: 3", "[true, \"odd: 3\", true]", "[error(\"odd: 3\"), true]"]
+    );
+    assert_eq!(run_ok("print(5?)"), vec!["5"]);
+    assert!(run_err("let x = error(\"boom\")?").ends_with(": boom"));
+    assert_eq!(run_err("error(1)"), "error() requires a string message");
+}
+
+#[test]
+fn panic_stops_the_program() {
+    let err = run_err("fn area(r) {\n  if r < 0 { panic(\"negative radius\") }\n  return r * r\n}\nprint(area(2))\narea(-1)");
+    assert!(err.starts_with("negative radius at line"), "{}", err);
+    assert!(run_err("panic(error(\"from value\"))").starts_with("from value at line"));
+    assert!(run_err("panic([1, \"a\"])").starts_with("[1, \"a\"] at line"));
+}
+
+#[test]
+fn eval_runs_code_in_the_current_scope() {
+    assert_eq!(
+        run_ok("let x = 4\nprint(eval(\"x * 3\"))\neval(\"let y = x + 1\")\nprint(y)\nprint(eval(\"let z = 1\"))"),
+        vec!["12", "5", "null"]
+    );
+    let calc = "fn calc(expr) {\n  let r = eval(expr)\n  if is_error(r) { return \"bad\" }\n  return r\n}\n";
+    assert_eq!(
+        run_ok(&format!("{}print(calc(\"2 + \"))\nprint(calc(\"nope\"))\nprint(calc(\"(1 + 2) * 4\"))", calc)),
+        vec!["bad", "bad", "12"]
+    );
+    assert!(run_ok("print(eval(\"1 +\"))")[0].starts_with("error: "));
+    assert_eq!(
+        run_ok("fn fail() { return error(\"boom\") }\nfn f() {\n  eval(\"fail()?\")\n  return 2\n}\nprint(f())"),
+        vec!["error: boom"]
+    );
+    assert_eq!(run_ok("eval(\"fn g() { return 1 }\")\nprint(g())"), vec!["1"]);
+    assert_eq!(run_err("eval(1)"), "eval() requires a string");
+}
+
+#[test]
+fn globals_and_locals_list_bindings() {
+    let src = "let b = 2\nlet a = [1]\nfn f(x) {\n  let y = x + 1\n  if true {\n    let y = 0\n    print(locals())\n  }\n  print(len(globals()))\n}\nf(5)\nprint(locals()[0])";
+    assert_eq!(run_ok(src), vec!["[[\"x\", 5], [\"y\", 0]]", "3", "[\"a\", [1]]"]);
+    assert_eq!(run_err("globals(1)"), "globals() takes no arguments");
+}
+
+#[test]
+fn help_prints_signature_and_docstring() {
+    let src = "fn area(w, h) {\n  \"Returns the area of a\n\n   w by h rectangle.\"\n  return w * h\n}\nhelp(area)\nfn bare(x) { return x }\nhelp(bare)\nprint(area(2, 3))";
+    assert_eq!(run_ok(src), vec!["fn area(w, h)\n  Returns the area of a\n\n  w by h rectangle.", "fn bare(x)", "6"]);
+    assert_eq!(run_err("help(1)"), "help() requires a function");
+    assert_eq!(run_ok("help(slice)\nhelp(print)"), vec!["builtin slice(target, start, end?)", "builtin print(values...)"]);
+    assert_eq!(run_err("help()"), "help() takes exactly 1 argument");
+}
+
+#[test]
+fn script_functions_shadow_builtins() {
+    let src = "fn print(x) {\n  builtin(\"print\", \"> \" + x)\n}\nprint(\"hi\")\nfn len(x) { return 0 }\nprint(repr(len([1, 2])))";
+    assert_eq!(run_ok(src), vec!["> hi", "> 0"]);
+    // Only functions shadow: other values leave the builtin callable.
+    assert_eq!(run_ok("let max = 0\nprint(max([3, 9]))\nprint(builtin(\"len\", \"abc\"))"), vec!["9", "3"]);
+    assert_eq!(run_err("builtin(\"nope\")"), "'nope' is not a builtin");
+}
+
 // ===== Variables & Scoping =====
 
 #[test]
@@ -151,6 +305,44 @@ fn var_assign() {
     assert_eq!(run_ok("let x = 1\nx = 2\nprint(x)"), vec!["2"]);
 }
 
+#[test]
+fn let_destructures_arrays() {
+    assert_eq!(
+        run_ok("let a = [1, 2, 3]\nlet [head, ...tail] = a\nprint(head)\nprint(tail)\npush(tail, 4)\nprint(a)"),
+        vec!["1", "[2, 3]", "[1, 2, 3]"]
+    );
+    assert_eq!(run_ok("let [x, y] = [\"p\", 2]\nlet [z, ...r] = [0]\nprint([x, y, z, r])"), vec!["[\"p\", 2, 0, []]"]);
+    assert_eq!(run_err("let [x, y] = [1, 2, 3]"), "Cannot destructure an array of length 3: expected 2");
+    assert_eq!(run_err("let [x, ...r] = []"), "Cannot destructure an array of length 0: expected at least 1");
+    assert_eq!(run_err("let [c] = \"c\""), "Cannot destructure a string; expected an array");
+    assert!(run_err("let [...r, x] = [1]").contains("Expected RBracket"));
+}
+
+#[test]
+fn multiple_assignment_evaluates_all_values_first() {
+    assert_eq!(run_ok("let a = 1\nlet b = 2\na, b = b, a\nprint([a, b])"), vec!["[2, 1]"]);
+    assert_eq!(
+        run_ok("let x = 0\nlet y = 1\nfor i in 0..10 {\n  x, y = y, x + y\n}\nprint(x)"),
+        vec!["55"]
+    );
+    assert!(run_err("let a = 1\na, b = 1").contains("Expected 2 values after '=', got 1"));
+    assert!(run_err("let a = [1]\na[0], b = 1, 2").contains("Only names can be assigned together"));
+}
+
+#[test]
+fn global_declaration_reaches_top_level_binding() {
+    assert_eq!(
+        run_ok(
+            "let count = 0\nfn inner() {\n  global count\n  count = count + 10\n}\n\
+             fn outer() {\n  let count = 1\n  inner()\n  return count\n}\nprint(outer())\nprint(count)"
+        ),
+        vec!["1", "10"]
+    );
+    assert_eq!(run_ok("fn f() {\n  global made\n  made = 3\n}\nf()\nprint(made)"), vec!["3"]);
+    assert!(run_err("global x").starts_with("'global' used outside a function"));
+    assert!(run_err("fn f() {\n  global x\n  let x = 1\n}\nf()").starts_with("'x' is declared global in this function"));
+}
+
 #[test]
 fn var_block_shadow() {
     assert_eq!(
@@ -173,6 +365,17 @@ fn var_undefined_error() {
     assert!(err.contains("Undefined variable"));
 }
 
+#[test]
+fn var_undefined_suggests_close_names() {
+    assert_eq!(run_err("print(lenn([1]))"), "Undefined variable 'lenn'; did you mean 'len'?");
+    assert_eq!(
+        run_err("let total = 1\nprint(totl)"),
+        "Undefined variable 'totl'; did you mean 'total'?"
+    );
+    assert_eq!(run_err("retrun 1"), "Undefined variable 'retrun'; did you mean 'return'?");
+    assert_eq!(run_err("print(zzz)"), "Undefined variable 'zzz'");
+}
+
 #[test]
 fn var_null() {
     assert_eq!(
@@ -305,6 +508,119 @@ fn builtin_len_arg_count() {
     assert!(err.contains("len() takes exactly 1 argument"));
 }
 
+#[test]
+fn builtin_date_and_time() {
+    assert_eq!(
+        run_ok(
+            "let t = parse_time(\"2024-02-29T13:05:09.5+01:30\")\nprint(t)\nprint(date(t))\n\
+             print(format_time(t, \"%Y-%m-%d %H:%M:%S %a %b %j\"))\nprint(date(-1))\n\
+             print(parse_time(\"1970-01-02\"))\nprint(parse_time(\"2023-02-29\"))\nprint(now() > t)"
+        ),
+        vec![
+            "1709206509.5",
+            "[2024, 2, 29, 11, 35, 9, 4]",
+            "2024-02-29 11:35:09 Thu Feb 060",
+            "[1969, 12, 31, 23, 59, 59, 3]",
+            "86400",
+            "null",
+            "true"
+        ]
+    );
+    assert_eq!(run_err("format_time(0, \"%q\")"), "Unknown format specifier '%q'");
+}
+
+#[test]
+fn builtin_repr_quotes_strings() {
+    assert_eq!(
+        run_ok("print(repr(\"a b\"))\nprint(repr(1))\nprint([\"1\", 1, [\"x\"]])\nprint(\"plain\")"),
+        vec!["\"a b\"", "1", "[\"1\", 1, [\"x\"]]", "plain"]
+    );
+}
+
+#[test]
+fn builtin_sum_min_max() {
+    assert_eq!(
+        run_ok("let xs = [3, -1.5, 10]\nprint(sum(xs))\nprint(min(xs))\nprint(max(xs))\nprint(max([\"pear\", \"apple\"]))\nprint(sum([]))\nprint(min([]))"),
+        vec!["11.5", "-1.5", "10", "pear", "0", "null"]
+    );
+    assert_eq!(run_err("min([1, \"a\"])"), "min() requires an array of numbers or of strings");
+    assert_eq!(run_err("sum([1, true])"), "sum() requires an array of numbers");
+}
+
+#[test]
+fn builtin_sorted_and_reverse_copy() {
+    assert_eq!(
+        run_ok("let xs = [3, -1, 2]\nprint(sorted(xs))\nprint(reverse(xs))\nprint(xs)\nprint(sorted([\"b\", \"a\"]))\nprint(sorted([]))"),
+        vec!["[-1, 2, 3]", "[2, -1, 3]", "[3, -1, 2]", "[\"a\", \"b\"]", "[]"]
+    );
+    assert_eq!(run_err("sorted([1, \"a\"])"), "sorted() requires an array of numbers or of strings");
+}
+
+#[test]
+fn builtin_any_all_short_circuit() {
+    assert_eq!(
+        run_ok(
+            "let seen = []\nfn big(x) {\n  push(seen, x)\n  return x > 1\n}\nprint(any([1, 2, 3], big))\nprint(seen)\n\
+             print(all([2, 0, 3], big))\nprint(any([0, \"\"]))\nprint(all([]))"
+        ),
+        vec!["true", "[1, 2]", "false", "false", "true"]
+    );
+    assert_eq!(run_err("all([1], 2)"), "all() requires a function");
+}
+
+#[test]
+fn builtin_pprint_breaks_long_arrays() {
+    let out = run_ok(
+        "let rows = []\nfor i in 0..2 { push(rows, [\"a fairly long row label\", i, [\"nested\", \"values\", i * 1000]]) }\n\
+         pprint(rows)\npprint([1, \"2\"])",
+    );
+    assert_eq!(
+        out,
+        vec![
+            "[\n  [\"a fairly long row label\", 0, [\"nested\", \"values\", 0]],\n  [\"a fairly long row label\", 1, [\"nested\", \"values\", 1000]]\n]",
+            "[1, \"2\"]"
+        ]
+    );
+}
+
+#[test]
+fn builtin_pprint_handles_cyclic_arrays() {
+    let out = run_ok("let a = [\"a label long enough to push the printed array well past the eighty column limit\"]\npush(a, a)\npprint(a)");
+    assert_eq!(out, vec!["[\n  \"a label long enough to push the printed array well past the eighty column limit\",\n  [...]\n]"]);
+}
+
+#[test]
+fn number_display_and_to_fixed() {
+    assert_eq!(
+        run_ok(
+            "print(9223372036854775808)\nprint(0.1 + 0.2)\nprint(100000000000000000000)\n\
+             print(1000000000000000000000 * 1000)\nprint(0.0000001 * 1.5)\nprint(0 - 0)\n\
+             print(to_fixed(2.345, 2))\nprint(to_fixed(1 / 3, 0))\nprint(to_fixed(-1.5, 3))"
+        ),
+        vec!["9223372036854776000", "0.30000000000000004", "100000000000000000000", "1e24", "1.5e-7", "0", "2.35", "0", "-1.500"]
+    );
+    assert_eq!(run_err("to_fixed(1, 101)"), "to_fixed() digits must be between 0 and 100");
+}
+
+#[test]
+fn builtin_csv() {
+    assert_eq!(
+        run_ok("let rows = csv_parse(\"id,tag\n1,a b\n\")\nprint(rows[1][1])\nprint(csv_stringify([[1, \"x,y\"], [true]]))"),
+        vec!["a b", "1,\"x,y\"\ntrue\n"]
+    );
+    assert_eq!(run_err("csv_stringify([1])"), "csv_stringify() rows must be arrays");
+}
+
+#[cfg(unix)]
+#[test]
+fn builtin_run_captures_output_and_status() {
+    assert_eq!(
+        run_ok("let r = run(\"sh\", [\"-c\", \"echo $0; echo oops >&2; exit 3\", 7])\nprint(r[0])\nprint(r[1])\nprint(r[2])"),
+        vec!["7\n", "oops\n", "3"]
+    );
+    assert!(run_err("run(\"minilang-no-such-program\")").starts_with("run(): cannot start 'minilang-no-such-program'"));
+}
+
 // ===== Error Handling =====
 
 #[test]
@@ -395,3 +711,215 @@ print(sum)
 "#;
     assert_eq!(run_ok(source), vec!["103", "10"]);
 }
+
+#[test]
+fn parser_error_reports_line() {
+    let err = minilang::parser::parse_source("let x = 1\nlet = 2").unwrap_err();
+    assert_eq!(err, "Expected identifier after 'let', got Eq at line 2");
+}
+
+#[test]
+fn parser_assignment_targets() {
+    assert_eq!(run_ok("let a = [[1], 2]\nlet i = 0\na[i + 1] = 3\na[0][0]\nprint(a)"), vec!["[[1], 3]"]);
+    let err = minilang::parser::parse_source("let a = [[1]]\na[0][0] = 2").unwrap_err();
+    assert_eq!(err, "Invalid assignment target at line 2");
+    assert!(minilang::parser::parse_source("f() = 1").is_err());
+    assert_eq!(
+        minilang::parser::parse_source("print(1) )").unwrap_err(),
+        "Unexpected token RParen at line 1"
+    );
+}
+
+#[test]
+fn parser_limits_nesting_depth() {
+    let deep = format!("let x = {}1{}", "(".repeat(10_000), ")".repeat(10_000));
+    let err = minilang::parser::parse_source(&deep).unwrap_err();
+    assert_eq!(err, "Nesting exceeds the limit of 200 levels at line 1");
+    assert!(minilang::parser::parse_source(&format!("print({}1)", "-".repeat(10_000))).is_err());
+
+    let mut parser = Parser::from_lexer(Lexer::new("if true {\n  if true {\n    print([[1]])\n  }\n}"));
+    parser.set_max_depth(4);
+    assert_eq!(parser.parse_program().unwrap_err(), "Nesting exceeds the limit of 4 levels at line 3");
+}
+
+#[test]
+fn parser_limits_operator_chains() {
+    let long = format!("let x = {}", vec!["1"; 200_000].join(" + "));
+    let err = minilang::parser::parse_source(&long).unwrap_err();
+    assert_eq!(err, "Expression exceeds the limit of 20000 chained operators at line 1");
+    assert!(minilang::parser::parse_source(&format!("f{}", "()".repeat(200_000))).is_err());
+
+    // Each expression has its own budget, and nested parts count toward it.
+    let source = "let a = 1 + 2 + 3\nlet b = [1][0] + (2 + 3 + 4 + 5) + 6\nprint(a + b)";
+    let mut parser = Parser::from_lexer(Lexer::new(source));
+    parser.set_max_chain(4);
+    assert!(parser.parse_program().is_ok());
+    let mut parser = Parser::from_lexer(Lexer::new(source));
+    parser.set_max_chain(3);
+    assert_eq!(parser.parse_program().unwrap_err(), "Expression exceeds the limit of 3 chained operators at line 2");
+}
+
+// ===== Number Locales =====
+
+#[test]
+fn builtin_format_number_locales() {
+    assert_eq!(
+        run_ok("print(format_number(1234567.5, \"de\"))\nprint(format_number(-1234.25, \"en-US\"))\nprint(format_number(999, \"fr\"))"),
+        vec!["1.234.567,5", "-1,234.25", "999"]
+    );
+}
+
+#[test]
+fn builtin_format_number_default_is_plain() {
+    assert_eq!(run_ok("print(format_number(1234567.5))"), vec!["1234567.5"]);
+}
+
+#[test]
+fn builtin_format_number_unknown_locale() {
+    let err = run_err("format_number(1, \"xx\")");
+    assert!(err.contains("Unknown locale 'xx'"));
+}
+
+#[test]
+fn interpreter_locale_affects_print() {
+    use minilang::locale::NumberLocale;
+    let program = minilang::parser::parse_source("print(12345.5)\nprint([1000, 2.5])\nprint(\"1.5\")").unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_locale(NumberLocale::from_name("de").unwrap());
+    interpreter.run(&program).unwrap();
+    assert_eq!(interpreter.output, vec!["12.345,5", "[1.000, 2,5]", "1.5"]);
+}
+
+#[test]
+fn repl_incomplete_detection() {
+    use minilang::repl::is_incomplete;
+    assert!(is_incomplete("fn f(a, b) {"));
+    assert!(is_incomplete("print([1,"));
+    assert!(is_incomplete("let s = \"abc"));
+    assert!(!is_incomplete("fn f() { return 1 }"));
+    assert!(!is_incomplete(")"));
+}
+
+#[test]
+fn run_interactive_returns_last_expression_value() {
+    let program = minilang::parser::parse_source("let x = 2\nx * 5").unwrap();
+    let mut interp = Interpreter::new();
+    let value = interp.run_interactive(&program).unwrap();
+    assert_eq!(value.map(|v| v.to_string()), Some("10".to_string()));
+
+    let program = minilang::parser::parse_source("x * 5\nlet y = 1").unwrap();
+    assert!(interp.run_interactive(&program).unwrap().is_none());
+}
+
+#[test]
+fn exit_stops_execution_and_records_code() {
+    let program = minilang::parser::parse_source("print(1)\nexit(2)\nprint(3)").unwrap();
+    let mut interp = Interpreter::new();
+    interp.run(&program).unwrap();
+    assert_eq!(interp.output, vec!["1"]);
+    assert_eq!(interp.exit_code(), Some(2));
+    assert!(run_err("exit(\"x\")").contains("requires a number"));
+}
+
+#[test]
+fn index_of_and_find() {
+    assert_eq!(
+        run_ok("let a = [3, \"x\", true, 3]\nprint(index_of(a, 3))\nprint(index_of(a, true))\nprint(index_of(a, 4))"),
+        vec!["0", "2", "-1"]
+    );
+    assert_eq!(
+        run_ok("fn big(n) { return n > 10 }\nprint(find([4, 12, 30], big))\nprint(find([1], big))"),
+        vec!["12", "null"]
+    );
+    assert_eq!(run_err("find([1], 2)"), "find() requires a function");
+    assert_eq!(run_err("index_of(1, 2)"), "index_of() requires an array");
+}
+
+#[test]
+fn for_each_over_arrays_and_strings() {
+    assert_eq!(
+        run_ok("let total = 0\nfor n in [1, 2, 3] {\n  total = total + n\n}\nprint(total)"),
+        vec!["6"]
+    );
+    assert_eq!(run_ok("for ch in \"hé!\" {\n  print(ch)\n}"), vec!["h", "é", "!"]);
+    assert_eq!(
+        run_ok("fn first_big(a) {\n  for n in a {\n    if n > 1 {\n      return n\n    }\n  }\n}\nprint(first_big([1, 5, 9]))"),
+        vec!["5"]
+    );
+    assert_eq!(run_err("for x in 5 { }"), "For-each requires an array, string, function or iterator");
+}
+
+#[test]
+fn arrays_are_shared_references() {
+    assert_eq!(
+        run_ok("let a = [1, 2]\nlet b = a\nb[0] = 9\nfn set(x) {\n  x[1] = 7\n  return x\n}\nprint(set(a))\nprint(a)\nprint(a + [3])\nprint(b)"),
+        vec!["[9, 7]", "[9, 7]", "[9, 7, 3]", "[9, 7]"]
+    );
+}
+
+#[test]
+fn push_appends_in_place() {
+    assert_eq!(
+        run_ok("fn add(list, x) {\n  push(list, x)\n}\nlet a = []\nadd(a, 1)\nadd(a, \"two\")\nprint(a)\nprint(len(a))"),
+        vec!["[1, \"two\"]", "2"]
+    );
+    assert_eq!(run_ok("let a = [1]\npush(a, a)\nprint(a)"), vec!["[1, [...]]"]);
+    assert_eq!(run_ok("let a = [1, 2]\nfor x in a {\n  push(a, x)\n}\nprint(a)"), vec!["[1, 2, 1, 2]"]);
+    assert_eq!(run_err("push(1, 2)"), "push() requires an array");
+}
+
+#[test]
+fn freeze_makes_arrays_read_only() {
+    assert_eq!(
+        run_ok("let a = freeze([1, [2]])\nlet b = a\nprint(is_frozen(b))\nprint(is_frozen(a[1]))\nlet c = a + [3]\npush(c, 4)\nprint(c)\nprint(is_frozen(c))\nprint(is_frozen([1]))"),
+        vec!["true", "true", "[1, [2], 3, 4]", "false", "false"]
+    );
+    assert_eq!(run_err("let a = freeze([1])\npush(a, 2)"), "FrozenError: cannot push to a frozen array");
+    assert_eq!(
+        run_err("let a = freeze([1])\na[0] = 5"),
+        "FrozenError: cannot assign to an element of frozen array 'a'"
+    );
+    assert_eq!(run_err("let a = freeze([[1]])\npush(a[0], 2)"), "FrozenError: cannot push to a frozen array");
+    assert_eq!(run_ok("let a = [1]\npush(a, a)\nfreeze(a)\nprint(is_frozen(a[1]))"), vec!["true"]);
+}
+
+#[test]
+fn unicode_identifiers() {
+    assert_eq!(run_ok("let café = 2\nfn dobro(ñ) { return ñ * 2 }\nprint(dobro(café))"), vec!["4"]);
+}
+
+#[test]
+fn strings_are_measured_and_indexed_by_character() {
+    assert_eq!(
+        run_ok("let s = \"héllo\"\nprint(len(s))\nprint(s[1])\nprint(slice(s, 1, 3))\nprint(slice(s, 3))\nprint(bytes(\"é\"))"),
+        vec!["5", "é", "él", "lo", "[195, 169]"]
+    );
+    assert_eq!(run_ok("print(slice([1, 2, 3, 4], 1, 3))\nprint(slice([1, 2], 5))"), vec!["[2, 3]", "[]"]);
+    assert_eq!(run_err("print(\"é\"[1])"), "Index 1 out of bounds");
+}
+
+#[test]
+fn unbounded_recursion_is_an_error() {
+    assert_eq!(run_err("fn f(n) { return f(n + 1) }\nf(0)"), "Maximum recursion depth exceeded");
+    assert_eq!(run_ok("fn f(n) { if n == 0 { return 0 } return f(n - 1) + 1 }\nprint(f(20))"), vec!["20"]);
+}
+
+#[test]
+fn long_operator_chains_are_not_deep_recursion() {
+    let sum = vec!["1"; 10_000].join(" + ");
+    assert_eq!(run_ok(&format!("print({})", sum)), vec!["10000"]);
+    let all = vec!["true"; 10_000].join(" and ");
+    assert_eq!(run_ok(&format!("print({} and false or 1)", all)), vec!["1"]);
+}
+
+#[test]
+fn appending_to_a_string_variable() {
+    let src = "let s = \"\"\nlet copy = s\nfor i in 0..50000 { s = s + \"ab\" }\nprint(len(s))\nprint(len(copy))";
+    assert_eq!(run_ok(src), vec!["100000", "0"]);
+    assert_eq!(run_ok("let s = \"ab\"\ns = s + s + s\nprint(s)"), vec!["ababab"]);
+    assert_eq!(
+        run_ok("let s = \"a\"\nfn grow() {\n  global s\n  s = \"reset\"\n  return \"!\"\n}\ns = s + grow()\nprint(s)"),
+        vec!["a!"]
+    );
+    assert_eq!(run_err("let s = \"a\"\ns = s + 1"), "'+' requires two numbers, two strings, or two arrays");
+}
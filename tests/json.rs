@@ -0,0 +1,21 @@
+use minilang::json::{self, Json};
+
+#[test]
+fn json_roundtrip() {
+    let text = r#"{"a":[1,2.5,-3],"b":"x\"y\n","c":null,"d":true}"#;
+    let value = json::parse(text).unwrap();
+    assert_eq!(value.to_string(), text);
+}
+
+#[test]
+fn json_parses_whitespace_and_unicode_escapes() {
+    let value = json::parse(" { \"s\" : \"caf\\u00e9 \\ud83d\\ude00\" } ").unwrap();
+    assert_eq!(value.get("s"), Some(&Json::String("café 😀".to_string())));
+}
+
+#[test]
+fn json_rejects_malformed_input() {
+    assert!(json::parse("{\"a\": }").is_err());
+    assert!(json::parse("[1, 2").is_err());
+    assert!(json::parse("1 2").is_err());
+}
@@ -0,0 +1,78 @@
+use minilang::lexer::{LexError, Lexer, Span, Token};
+use minilang::parser::parse_source;
+
+#[test]
+fn lexer_spans_track_lines_and_columns() {
+    let tokens = Lexer::new("let x = 1\n  print(x)").tokenize_spanned().unwrap();
+    assert_eq!(tokens[0].token, Token::Let);
+    assert_eq!(tokens[0].span, Span { start: 0, end: 3, line: 1, col: 1 });
+    assert_eq!(tokens[4].token, Token::Ident("print".into()));
+    assert_eq!(tokens[4].span, Span { start: 12, end: 17, line: 2, col: 3 });
+}
+
+#[test]
+fn lexer_spans_after_multiline_string() {
+    let tokens = Lexer::new("\"a\nb\" x").tokenize_spanned().unwrap();
+    assert_eq!(tokens[1].span.line, 2);
+    assert_eq!(tokens[1].span.col, 4);
+}
+
+#[test]
+fn lexer_ends_with_eof() {
+    let tokens = Lexer::new("").tokenize_spanned().unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].token, Token::Eof);
+}
+
+#[test]
+fn lexer_prefers_three_char_operators() {
+    let tokens = Lexer::new("a === b !== c == d").tokenize().unwrap();
+    assert_eq!(tokens[1], Token::EqEqEq);
+    assert_eq!(tokens[3], Token::BangEqEq);
+    assert_eq!(tokens[5], Token::EqEq);
+}
+
+#[test]
+fn lexer_iterator_stops_after_error() {
+    let mut lexer = Lexer::new("x $ y");
+    assert_eq!(lexer.next().unwrap().unwrap().token, Token::Ident("x".into()));
+    let err = lexer.next().unwrap().unwrap_err();
+    assert_eq!(err, LexError { message: "Unexpected character '$'".to_string(), line: 1, col: 3 });
+    assert!(lexer.next().is_none());
+}
+
+#[test]
+fn parser_reports_lex_errors_from_the_stream() {
+    assert_eq!(parse_source("print(1)\nprint(\"abc").unwrap_err(), "Unterminated string at line 2");
+    assert_eq!(parse_source("let x = 1\n@").unwrap_err(), "Unexpected character '@' at line 2");
+}
+
+#[test]
+fn lexer_spans_are_byte_offsets() {
+    let source = "let s = \"héllo\" x";
+    let tokens = Lexer::new(source).tokenize_spanned().unwrap();
+    assert_eq!(tokens[3].span, Span { start: 8, end: 16, line: 1, col: 9 });
+    assert_eq!(&source[tokens[3].span.start..tokens[3].span.end], "\"héllo\"");
+    assert_eq!(tokens[4].span, Span { start: 17, end: 18, line: 1, col: 17 });
+}
+
+#[test]
+fn lexer_accepts_unicode_identifiers() {
+    let tokens = Lexer::new("let café = αβ_1").tokenize().unwrap();
+    assert_eq!(tokens[1], Token::Ident("café".into()));
+    assert_eq!(tokens[3], Token::Ident("αβ_1".into()));
+    assert!(Lexer::new("let ¿ = 1").tokenize().is_err());
+}
+
+#[test]
+fn lexer_recovery_collects_every_error() {
+    let (tokens, errors) = Lexer::new("let @x = 1 $\n\"open").tokenize_recovering();
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec!["Unexpected character '@' at line 1", "Unexpected character '$' at line 1", "Unterminated string at line 2"]
+    );
+    assert_eq!(errors[1].col, 12);
+    let kinds: Vec<&Token> = tokens.iter().map(|t| &t.token).collect();
+    assert_eq!(kinds, vec![&Token::Let, &Token::Ident("x".into()), &Token::Eq, &Token::Number(1.0), &Token::Eof]);
+}
@@ -0,0 +1,74 @@
+use minilang::diagnostics::Severity;
+use minilang::lint::{LintConfig, Level, lint_source};
+
+fn codes(source: &str) -> Vec<(&'static str, usize)> {
+    lint_source(source, &LintConfig::default())
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.code, d.line))
+        .collect()
+}
+
+#[test]
+fn lint_shadowed_variable() {
+    assert_eq!(
+        codes("let x = 1\nif x > 0 {\n  let x = 2\n}"),
+        vec![("shadowed-variable", 3)]
+    );
+}
+
+#[test]
+fn lint_shadowing_names_the_outer_declaration() {
+    let diagnostics =
+        lint_source("let total = 0\nfor x in [1] {\n  let total = total + x\n}", &LintConfig::default()).unwrap();
+    assert_eq!(
+        diagnostics[0].message,
+        "'total' shadows the variable declared at line 1, which keeps its own value"
+    );
+}
+
+#[test]
+fn lint_redeclaration_in_same_scope_is_not_shadowing() {
+    assert_eq!(codes("let x = 1\nlet x = 2"), vec![]);
+}
+
+#[test]
+fn lint_shadowed_parameter() {
+    assert_eq!(
+        codes("let n = 1\nfn f(n) { return n }"),
+        vec![("shadowed-variable", 2)]
+    );
+}
+
+#[test]
+fn lint_bool_comparison() {
+    assert_eq!(
+        codes("let ok = true\nif ok == true { print(1) }"),
+        vec![("bool-comparison", 2)]
+    );
+}
+
+#[test]
+fn lint_empty_block_and_constant_condition() {
+    assert_eq!(
+        codes("if false {\n}"),
+        vec![("constant-condition", 1), ("empty-block", 1)]
+    );
+}
+
+#[test]
+fn lint_while_true_is_allowed() {
+    assert_eq!(codes("fn f() {\n  while true { return 1 }\n}"), vec![]);
+}
+
+#[test]
+fn lint_levels() {
+    let mut config = LintConfig::default();
+    config.set("empty-block", Level::Deny).unwrap();
+    config.set("constant-condition", Level::Allow).unwrap();
+    let diags = lint_source("if false {}", &config).unwrap();
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "empty-block");
+    assert_eq!(diags[0].severity, Severity::Error);
+    assert!(config.set("no-such-rule", Level::Deny).is_err());
+}
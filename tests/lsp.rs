@@ -0,0 +1,113 @@
+use minilang::json::{self, Json};
+use minilang::lsp::{self, Server};
+
+fn request(server: &mut Server, text: &str) -> Vec<Json> {
+    server.handle(&json::parse(text).unwrap())
+}
+
+fn open(server: &mut Server, source: &str) -> Vec<Json> {
+    let text = Json::String(source.to_string()).to_string();
+    request(
+        server,
+        &format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"file:///a.ml","text":{}}}}}}}"#,
+            text
+        ),
+    )
+}
+
+fn at(server: &mut Server, method: &str, line: usize, character: usize) -> Json {
+    let replies = request(
+        server,
+        &format!(
+            r#"{{"jsonrpc":"2.0","id":7,"method":"textDocument/{}","params":{{"textDocument":{{"uri":"file:///a.ml"}},"position":{{"line":{},"character":{}}}}}}}"#,
+            method, line, character
+        ),
+    );
+    replies[0].get("result").unwrap().clone()
+}
+
+#[test]
+fn lsp_initialize_advertises_capabilities() {
+    let mut server = Server::new();
+    let replies = request(&mut server, r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#);
+    assert_eq!(replies.len(), 1);
+    let caps = replies[0].get("result").unwrap().get("capabilities").unwrap();
+    assert_eq!(caps.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(caps.get("definitionProvider"), Some(&Json::Bool(true)));
+    assert!(request(&mut server, r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#).is_empty());
+}
+
+#[test]
+fn lsp_publishes_diagnostics_on_change() {
+    let mut server = Server::new();
+    let replies = open(&mut server, "let x = 1\nfn f( {\n");
+    let params = replies[0].get("params").unwrap();
+    let Some(Json::Array(diagnostics)) = params.get("diagnostics") else { panic!() };
+    assert_eq!(diagnostics.len(), 1);
+    let start = diagnostics[0].get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("line"), Some(&Json::Number(1.0)));
+    assert_eq!(diagnostics[0].get("severity"), Some(&Json::Number(1.0)));
+
+    let replies = request(
+        &mut server,
+        r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.ml","version":2},"contentChanges":[{"text":"if true {\n  print(1)\n}\n"}]}}"#,
+    );
+    let Some(Json::Array(diagnostics)) = replies[0].get("params").unwrap().get("diagnostics") else { panic!() };
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].get("code"), Some(&Json::String("constant-condition".to_string())));
+    assert_eq!(diagnostics[0].get("severity"), Some(&Json::Number(2.0)));
+}
+
+#[test]
+fn lsp_reports_every_bad_character() {
+    let mut server = Server::new();
+    let replies = open(&mut server, "let x = 1 @\nlet y = $\nprint(x ~ y)\n");
+    let Some(Json::Array(diagnostics)) = replies[0].get("params").unwrap().get("diagnostics") else { panic!() };
+    let lines: Vec<_> = diagnostics
+        .iter()
+        .map(|d| d.get("range").unwrap().get("start").unwrap().get("line").cloned())
+        .collect();
+    assert_eq!(lines, vec![Some(Json::Number(0.0)), Some(Json::Number(1.0)), Some(Json::Number(2.0))]);
+}
+
+#[test]
+fn lsp_definition_and_hover() {
+    let mut server = Server::new();
+    open(&mut server, "fn add(a, b) {\n  return a + b\n}\nlet total = add(1, 2)\nlet big = total > 2\n");
+
+    let def = at(&mut server, "definition", 3, 13);
+    let start = def.get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("line"), Some(&Json::Number(0.0)));
+    assert_eq!(start.get("character"), Some(&Json::Number(3.0)));
+
+    let def = at(&mut server, "definition", 1, 9);
+    let start = def.get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("character"), Some(&Json::Number(7.0)));
+
+    let hover = at(&mut server, "hover", 3, 13);
+    let value = hover.get("contents").unwrap().get("value").unwrap().as_str().unwrap();
+    assert!(value.contains("fn add(a, b)"));
+    let hover = at(&mut server, "hover", 4, 5);
+    let value = hover.get("contents").unwrap().get("value").unwrap().as_str().unwrap();
+    assert!(value.contains("let big: bool"));
+
+    assert_eq!(at(&mut server, "hover", 2, 0), Json::Null);
+}
+
+#[test]
+fn lsp_run_frames_messages() {
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#;
+    let exit = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+    let input = format!(
+        "Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+        body.len(),
+        body,
+        exit.len(),
+        exit
+    );
+    let mut output = Vec::new();
+    lsp::run(&mut input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output, "Content-Length: 38\r\n\r\n{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":null}");
+}
@@ -0,0 +1,28 @@
+use minilang::lexer::{Lexer, Token};
+use minilang::parser::Parser;
+
+fn parse(tokens: Vec<Token>) -> Result<usize, String> {
+    Parser::new(tokens).parse_program().map(|p| p.len())
+}
+
+#[test]
+fn parser_accepts_streams_without_eof() {
+    assert_eq!(parse(vec![]), Ok(0));
+    assert_eq!(parse(vec![Token::Ident("x".into())]), Ok(1));
+    assert_eq!(parse(vec![Token::Let]), Err("Expected identifier after 'let', got Eof".to_string()));
+    assert_eq!(
+        parse(vec![Token::Ident("x".into()), Token::Eq]),
+        Err("Unexpected token Eof".to_string())
+    );
+}
+
+#[test]
+fn parser_never_panics_on_truncated_input() {
+    let source = "fn f(a, b) {\n  if a[0] >= -b or not a {\n    return [a, (b + 1) * 2]\n  }\n}\nfor x in f([1], 2) { y = x }\nlet s = \"t\"";
+    let tokens = Lexer::new(source).tokenize().unwrap();
+    for end in 0..tokens.len() {
+        let _ = parse(tokens[..end].to_vec());
+        // Each token on its own, in whatever context it lands.
+        let _ = parse(tokens[end..].to_vec());
+    }
+}
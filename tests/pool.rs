@@ -0,0 +1,99 @@
+use minilang::pool::InterpreterPool;
+
+#[test]
+fn pool_runs_with_prelude() {
+    let pool = InterpreterPool::with_prelude("fn double(n) { return n * 2 }", 2).unwrap();
+    assert_eq!(pool.run("print(double(21))").unwrap(), vec!["42"]);
+}
+
+#[test]
+fn pool_resets_state_between_runs() {
+    let pool = InterpreterPool::new(1);
+    assert_eq!(pool.run("let secret = 1\nprint(secret)").unwrap(), vec!["1"]);
+    let err = pool.run("print(secret)").unwrap_err();
+    assert!(err.contains("Undefined variable"));
+}
+
+#[test]
+fn pool_prelude_mutation_does_not_leak() {
+    let pool = InterpreterPool::with_prelude("let counter = 0", 1).unwrap();
+    pool.run("counter = counter + 5").unwrap();
+    assert_eq!(pool.run("print(counter)").unwrap(), vec!["0"]);
+}
+
+#[test]
+fn pool_prelude_arrays_are_copied_for_each_run() {
+    let pool = InterpreterPool::with_prelude("let config = [1, 2]\nlet alias = config", 1).unwrap();
+    pool.run("push(config, 99)").unwrap();
+    assert_eq!(pool.run("print(config)\npush(alias, 3)\nprint(config)").unwrap(), vec!["[1, 2]", "[1, 2, 3]"]);
+    assert_eq!(pool.run("print(config)").unwrap(), vec!["[1, 2]"]);
+}
+
+#[test]
+fn pool_reuses_idle_interpreters() {
+    let pool = InterpreterPool::new(2);
+    assert_eq!(pool.idle_count(), 2);
+    let a = pool.acquire();
+    assert_eq!(pool.idle_count(), 1);
+    pool.release(a);
+    assert_eq!(pool.idle_count(), 2);
+}
+
+#[test]
+fn pool_prelude_errors_are_reported() {
+    assert!(InterpreterPool::with_prelude("let = 1", 1).is_err());
+}
+
+#[test]
+fn pool_step_limit_stops_runaway_scripts() {
+    let mut pool = InterpreterPool::new(1);
+    pool.set_step_limit(1_000);
+    let err = pool.run("while true { }").unwrap_err();
+    assert_eq!(err, "Step limit exceeded");
+    assert_eq!(pool.run("print(1 + 2)").unwrap(), vec!["3"]);
+}
+
+#[test]
+fn pool_memory_limit_applies_to_every_run() {
+    let mut pool = InterpreterPool::new(1);
+    pool.set_memory_limit(1024);
+    let err = pool.run("let s = \"x\"\nwhile true { s = s + s }").unwrap_err();
+    assert_eq!(err, "Memory limit exceeded");
+    assert_eq!(pool.run("print(\"fine\")").unwrap(), vec!["fine"]);
+}
+
+#[test]
+fn pool_timeout_applies_to_every_run() {
+    let mut pool = InterpreterPool::new(1);
+    pool.set_timeout(std::time::Duration::from_millis(20));
+    let err = pool.run("while true { }").unwrap_err();
+    assert!(err.starts_with("Timeout"));
+    assert_eq!(pool.run("print(2)").unwrap(), vec!["2"]);
+}
+
+#[test]
+fn pool_config_applies_to_every_run() {
+    let mut pool = InterpreterPool::new(1);
+    pool.set_config(minilang::interpreter::InterpreterConfig::sandboxed());
+    let err = pool.run("exit(0)").unwrap_err();
+    assert!(err.contains("capability 'process' is disabled"));
+}
+
+#[test]
+fn pool_host_settings_do_not_reach_the_next_tenant() {
+    let pool = InterpreterPool::new(1);
+    let mut tenant = pool.acquire();
+    tenant.register_fn("secret", |_| Ok(minilang::interpreter::Value::Str("tenantA".to_string())));
+    tenant.set_strict_math(true);
+    tenant.set_step_limit(10);
+    tenant.enable_audit();
+    pool.release(tenant);
+
+    let err = pool.run("print(secret())").unwrap_err();
+    assert!(err.contains("secret"), "{}", err);
+    assert_eq!(pool.run("print(1 / 0)").unwrap(), vec!["inf"]);
+    assert_eq!(pool.run("let i = 0\nwhile i < 100 { i = i + 1 }\nprint(i)").unwrap(), vec!["100"]);
+    let next = pool.acquire();
+    assert!(next.audit_log().is_empty());
+    assert!(next.profile().is_none());
+}
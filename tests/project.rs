@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use minilang::project::Project;
+
+#[test]
+fn parses_manifest_relative_to_root() {
+    let text = "# tools\n[project]\nname = \"tools\"  # shown in errors\nentry = \"src/main.ml\"\n\
+                sources = [\"lib\", \"vendor\"]\n\n[scripts]\nreport = \"scripts/report.ml\"\n";
+    let project = Project::parse(Path::new("/work"), text).unwrap();
+    assert_eq!(project.name.as_deref(), Some("tools"));
+    assert_eq!(project.target(None), Some(Path::new("/work/src/main.ml")));
+    assert_eq!(project.target(Some("report")), Some(Path::new("/work/scripts/report.ml")));
+    assert_eq!(project.target(Some("deploy")), None);
+    assert_eq!(project.sources, vec![Path::new("/work/lib"), Path::new("/work/vendor")]);
+}
+
+#[test]
+fn rejects_malformed_manifests() {
+    let root = Path::new(".");
+    assert_eq!(
+        Project::parse(root, "[project]\nentry = main.ml").unwrap_err(),
+        "minilang.toml line 2: expected a string"
+    );
+    assert_eq!(
+        Project::parse(root, "[project]\nsources = [\"lib\"").unwrap_err(),
+        "minilang.toml line 2: expected ']'"
+    );
+    assert_eq!(
+        Project::parse(root, "[project]\nentry = [\"a.ml\"]").unwrap_err(),
+        "minilang.toml: unknown or mistyped key 'entry' in [project]"
+    );
+}
@@ -0,0 +1,107 @@
+use minilang::readline::{Editor, Key, KeyReader, LineBuffer, ReadLine};
+
+fn keys(text: &str) -> Vec<Key> {
+    text.chars().map(Key::Char).collect()
+}
+
+fn edit(editor: &mut Editor, keys: Vec<Key>) -> ReadLine {
+    editor.edit(keys, |_, _| {})
+}
+
+#[test]
+fn readline_cursor_movement_and_insert() {
+    let mut editor = Editor::new();
+    let mut input = keys("prnt");
+    input.extend([Key::Left, Key::Left, Key::Char('i'), Key::End, Key::Char('!'), Key::Enter]);
+    assert_eq!(edit(&mut editor, input), ReadLine::Line("print!".to_string()));
+}
+
+#[test]
+fn readline_kill_and_yank() {
+    let mut buffer = LineBuffer::default();
+    buffer.set("let x = 1");
+    buffer.kill_word();
+    assert_eq!(buffer.text(), "let x = ");
+    buffer.home();
+    buffer.yank();
+    assert_eq!(buffer.text(), "1let x = ");
+    buffer.kill_to_end();
+    assert_eq!(buffer.text(), "1");
+    buffer.yank();
+    assert_eq!(buffer.text(), "1let x = ");
+    assert_eq!(buffer.cursor(), 9);
+}
+
+#[test]
+fn readline_history_navigation() {
+    let mut editor = Editor::new();
+    editor.add_history("first");
+    editor.add_history("second");
+    editor.add_history("second");
+    editor.add_history("   ");
+    assert_eq!(editor.history(), ["first", "second"]);
+
+    let mut input = keys("draft");
+    input.extend([Key::Up, Key::Up, Key::Up, Key::Down, Key::Down, Key::Enter]);
+    assert_eq!(edit(&mut editor, input), ReadLine::Line("draft".to_string()));
+
+    let input = vec![Key::Up, Key::Up, Key::Down, Key::Enter];
+    assert_eq!(edit(&mut editor, input), ReadLine::Line("second".to_string()));
+}
+
+#[test]
+fn readline_ctrl_d_and_interrupt() {
+    let mut editor = Editor::new();
+    assert_eq!(edit(&mut editor, vec![Key::CtrlD]), ReadLine::Eof);
+    let mut input = keys("ab");
+    input.extend([Key::Home, Key::CtrlD, Key::Enter]);
+    assert_eq!(edit(&mut editor, input), ReadLine::Line("b".to_string()));
+    assert_eq!(edit(&mut editor, vec![Key::Char('x'), Key::Interrupt]), ReadLine::Interrupted);
+}
+
+#[test]
+fn readline_decodes_escape_sequences_and_utf8() {
+    let bytes: &[u8] = b"a\x1b[D\x1b[C\x1b[3~\x1b[H\x7f\x0b\x19\r\xc3\xa9";
+    let decoded: Vec<Key> = KeyReader { bytes: std::io::Read::bytes(bytes) }.collect();
+    assert_eq!(
+        decoded,
+        vec![
+            Key::Char('a'),
+            Key::Left,
+            Key::Right,
+            Key::Delete,
+            Key::Home,
+            Key::Backspace,
+            Key::KillToEnd,
+            Key::Yank,
+            Key::Enter,
+            Key::Char('é'),
+        ]
+    );
+}
+
+#[test]
+fn readline_tab_completes_identifiers() {
+    let mut editor = Editor::new();
+    editor.set_completions(vec!["counter".to_string(), "count_all".to_string(), "print".to_string()]);
+
+    let mut input = keys("pr");
+    input.extend([Key::Tab, Key::Enter]);
+    assert_eq!(edit(&mut editor, input), ReadLine::Line("print".to_string()));
+
+    let mut input = keys("co");
+    input.extend([Key::Tab, Key::Char('e'), Key::Tab, Key::Enter]);
+    assert_eq!(edit(&mut editor, input), ReadLine::Line("counter".to_string()));
+
+    let mut listed = Vec::new();
+    let mut input = keys("count");
+    input.extend([Key::Tab, Key::Enter]);
+    editor.edit(input, |_, listing| {
+        if let Some(words) = listing {
+            listed = words.to_vec();
+        }
+    });
+    assert_eq!(listed, vec!["counter".to_string(), "count_all".to_string()]);
+
+    assert_eq!(edit(&mut editor, vec![Key::Tab, Key::Enter]), ReadLine::Line("  ".to_string()));
+}
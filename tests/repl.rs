@@ -101,3 +101,136 @@ fn repl_exit_on_eof() {
     let (_, _, ok) = repl("");
     assert!(ok);
 }
+
+#[test]
+fn repl_multiline_function() {
+    let (stdout, stderr, ok) = repl("fn add(a, b) {\n  return a + b\n}\nprint(add(2, 3))\n");
+    assert!(ok);
+    assert!(stderr.is_empty());
+    assert!(stdout.contains(".. "));
+    assert!(stdout.contains("5"));
+}
+
+#[test]
+fn repl_multiline_array_and_string() {
+    let (stdout, stderr, ok) = repl("print([1,\n2])\nprint(\"a\nb\")\n");
+    assert!(ok);
+    assert!(stderr.is_empty());
+    assert!(stdout.contains("[1, 2]"));
+    assert!(stdout.contains("a\nb"));
+}
+
+#[test]
+fn repl_incomplete_input_at_eof_reports_error() {
+    let (_, stderr, ok) = repl("fn f() {\n");
+    assert!(ok);
+    assert!(stderr.contains("Parse error"));
+}
+
+#[test]
+fn repl_vars_and_reset_commands() {
+    let (stdout, stderr, ok) =
+        repl("let x = 1\nlet name = \"bob\"\nfn add(a, b) { return a + b }\n:vars\n:reset\n:vars\nprint(x)\n");
+    assert!(ok);
+    assert!(stdout.contains("x = 1"));
+    assert!(stdout.contains("name = \"bob\""));
+    assert!(stdout.contains("fn add(a, b)"));
+    assert!(stdout.contains("State cleared"));
+    assert!(stderr.contains("Undefined variable"));
+}
+
+#[test]
+fn repl_load_and_help_commands() {
+    let path = std::env::temp_dir().join(format!("minilang_repl_load_{}.ml", std::process::id()));
+    std::fs::write(&path, "let loaded = 41\nprint(\"loaded\")\n").unwrap();
+    let (stdout, stderr, ok) = repl(&format!(":load {}\nprint(loaded + 1)\n:help\n:nope\n", path.display()));
+    std::fs::remove_file(&path).unwrap();
+    assert!(ok);
+    assert!(stdout.contains("loaded"));
+    assert!(stdout.contains("42"));
+    assert!(stdout.contains(":vars"));
+    assert!(stderr.contains("Unknown command ':nope'"));
+}
+
+#[test]
+fn repl_echoes_expression_values() {
+    let (stdout, stderr, ok) = repl("1 + 2\nlet s = \"hi\"\ns\nprint(7)\n[1, 2]\n");
+    assert!(ok);
+    assert!(stderr.is_empty());
+    let lines: Vec<&str> = stdout.lines().map(|l| l.trim_start_matches(">> ")).collect();
+    assert!(lines.contains(&"3"));
+    assert!(lines.contains(&"\"hi\""));
+    assert!(lines.contains(&"[1, 2]"));
+    // print() returns null, so only its own output appears
+    assert_eq!(lines.iter().filter(|l| **l == "7").count(), 1);
+    assert!(!stdout.contains("null"));
+}
+
+#[test]
+fn repl_underscore_holds_last_result() {
+    let (stdout, stderr, ok) = repl("20 + 1\n_ * 2\nprint(_)\nlet y = 1\nprint(_)\n");
+    assert!(ok);
+    assert!(stderr.is_empty());
+    let lines: Vec<&str> = stdout.lines().map(|l| l.trim_start_matches(">> ")).collect();
+    assert!(lines.contains(&"21"));
+    assert_eq!(lines.iter().filter(|l| **l == "42").count(), 3);
+}
+
+#[test]
+fn repl_time_command() {
+    let (stdout, stderr, ok) = repl("1 + 1\n:time on\n2 + 2\n:time\n:time off\n3 + 3\n");
+    assert!(ok);
+    assert!(stderr.is_empty());
+    assert_eq!(stdout.matches("Time: ").count(), 1);
+    assert!(stdout.contains("Timing is on"));
+}
+
+#[test]
+fn repl_exit_builtin_ends_session() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b"print(1)\nexit(4)\nprint(2)\n")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains('1'));
+    assert!(!stdout.contains('2'));
+}
+
+#[test]
+fn repl_save_restore_and_init() {
+    let path = std::env::temp_dir().join(format!("minilang_repl_session_{}.mini", std::process::id()));
+    let (stdout, _, ok) = repl(&format!(
+        "let x = 1\nprint(nope)\nfn inc(n) {{\n  return n + 1\n}}\n:save {}\n",
+        path.display()
+    ));
+    assert!(ok);
+    assert!(stdout.contains("Saved 2 inputs"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "let x = 1\nfn inc(n) {\n  return n + 1\n}\n"
+    );
+
+    let (stdout, stderr, ok) = repl(&format!(":restore {}\nprint(inc(x) * 10)\n", path.display()));
+    assert!(ok && stderr.is_empty());
+    assert!(stdout.contains("20"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_minilang"))
+        .args(["repl", "--init", path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b"print(inc(x) * 100)\n")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("200"));
+}
@@ -0,0 +1,81 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use minilang::pool::InterpreterPool;
+use minilang::server::{self, handle};
+
+#[test]
+fn server_health() {
+    let pool = InterpreterPool::new(1);
+    let resp = handle(&pool, "GET", "/health", "", "");
+    assert_eq!(resp.status, 200);
+    assert_eq!(resp.body, r#"{"status":"ok"}"#);
+}
+
+#[test]
+fn server_run_plain_source() {
+    let pool = InterpreterPool::new(1);
+    let resp = handle(&pool, "POST", "/run", "text/plain", "print(1 + 2)\nprint(\"hi\")");
+    assert_eq!(resp.status, 200);
+    assert_eq!(resp.body, r#"{"output":["3","hi"],"error":null}"#);
+}
+
+#[test]
+fn server_run_json_source_with_error() {
+    let pool = InterpreterPool::new(1);
+    let body = r#"{"source": "print(1)\nprint(nope)"}"#;
+    let resp = handle(&pool, "POST", "/run", "application/json", body);
+    assert_eq!(resp.status, 200);
+    assert_eq!(resp.body, r#"{"output":["1"],"error":"Undefined variable 'nope'"}"#);
+}
+
+#[test]
+fn server_rejects_bad_requests() {
+    let pool = InterpreterPool::new(1);
+    assert_eq!(handle(&pool, "POST", "/run", "application/json", "{").status, 400);
+    assert_eq!(handle(&pool, "GET", "/run", "", "").status, 405);
+    assert_eq!(handle(&pool, "GET", "/nope", "", "").status, 404);
+}
+
+#[test]
+fn server_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let pool = InterpreterPool::new(1);
+        server::serve(listener, &pool).unwrap();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let body = "print(6 * 7)";
+    write!(
+        stream,
+        "POST /run HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with(r#"{"output":["42"],"error":null}"#));
+}
+
+#[test]
+fn server_idle_client_does_not_block_others() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let pool = InterpreterPool::new(2);
+        server::serve(listener, &pool).unwrap();
+    });
+
+    // Connects and never sends a request.
+    let _idle = TcpStream::connect(addr).unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+    write!(stream, "GET /health HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with(r#"{"status":"ok"}"#));
+}
@@ -0,0 +1,8 @@
+use minilang::style::{Color, colorize};
+
+#[test]
+fn style_colorize_wraps_text_only_when_enabled() {
+    assert_eq!(colorize("oops", Color::Red, true), "\x1b[31moops\x1b[0m");
+    assert_eq!(colorize("careful", Color::Yellow, true), "\x1b[33mcareful\x1b[0m");
+    assert_eq!(colorize("oops", Color::Red, false), "oops");
+}
@@ -0,0 +1,13 @@
+use minilang::symbol::{self, Symbol};
+
+#[test]
+fn symbols_intern_by_content() {
+    let a = Symbol::intern("counter");
+    let b = Symbol::intern(&String::from("counter"));
+    assert_eq!(a, b);
+    assert_eq!(a.as_str().as_ptr(), b.as_str().as_ptr());
+    assert_ne!(a, Symbol::intern("count"));
+    assert_eq!(a, "counter");
+    assert_eq!(format!("{} {:?}", a, a), "counter \"counter\"");
+    assert_eq!(symbol::join(&[a, "x".into()], ", "), "counter, x");
+}
@@ -0,0 +1,27 @@
+use minilang::testing::run_tests;
+
+#[test]
+fn runs_each_test_function_in_isolation() {
+    let source = "let count = 0\nfn bump() { count = count + 1\n  return count }\n\
+                  fn test_first() { assert_eq(bump(), 1) }\n\
+                  fn test_second() { assert_eq(bump(), 1) }\n\
+                  fn helper() { assert(false) }";
+    let results = run_tests(source).unwrap();
+    let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["test_first", "test_second"]);
+    assert!(results.iter().all(|r| r.error.is_none()));
+}
+
+#[test]
+fn failures_carry_the_assertion_diff_and_output() {
+    let source = "fn test_words() {\n  print(\"start\")\n  assert_eq([1, \"a\"], [1, \"b\"])\n}\n\
+                  fn test_flag() { assert(1 > 2, \"order\") }";
+    let results = run_tests(source).unwrap();
+    assert_eq!(
+        results[0].error.as_deref(),
+        Some("Assertion failed: values differ\n  expected: [1, \"b\"]\n    actual: [1, \"a\"]")
+    );
+    assert_eq!(results[0].output, vec!["start"]);
+    assert_eq!(results[1].error.as_deref(), Some("Assertion failed: order"));
+    assert!(run_tests("print(missing)\nfn test_x() { }").is_err());
+}
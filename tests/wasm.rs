@@ -0,0 +1,25 @@
+#![cfg(feature = "wasm")]
+
+use minilang::json::{self, Json};
+use minilang::wasm::{eval, minilang_alloc, minilang_eval, minilang_free, minilang_result_len};
+
+#[test]
+fn wasm_eval_returns_output_and_error_json() {
+    assert_eq!(eval("print(1 + 2)"), r#"{"output":["3"],"error":null}"#);
+    let result = json::parse(&eval("print(1)\nexit(0)")).unwrap();
+    assert_eq!(result.get("output"), Some(&Json::Array(vec![Json::String("1".to_string())])));
+    assert!(result.get("error").and_then(Json::as_str).unwrap().contains("capability"));
+}
+
+#[test]
+fn wasm_eval_through_raw_pointers() {
+    let source = b"print(\"hi\")";
+    unsafe {
+        let ptr = minilang_alloc(source.len());
+        std::ptr::copy_nonoverlapping(source.as_ptr(), ptr, source.len());
+        let out = minilang_eval(ptr, source.len());
+        let text = std::slice::from_raw_parts(out, minilang_result_len());
+        assert_eq!(text, br#"{"output":["hi"],"error":null}"#);
+        minilang_free(ptr, source.len());
+    }
+}